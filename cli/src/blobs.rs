@@ -0,0 +1,36 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use crate::config::Config;
+
+/// Location of a blob in the store: `<dir>/<first two hex chars>/<sha256>`
+pub fn path(config: &Config, sha256: &str) -> Result<PathBuf> {
+    let prefix = sha256.get(..2).context("Invalid blob hash")?;
+    Ok(config.blob_store_dir()?.join(prefix).join(sha256))
+}
+
+/// Write `data` to the blob store and return its SHA-256. Identical content is
+/// stored once.
+pub fn store(config: &Config, data: &[u8]) -> Result<String> {
+    let sha256 = format!("{:x}", Sha256::digest(data));
+    let path = path(config, &sha256)?;
+    if path.exists() {
+        return Ok(sha256);
+    }
+
+    let dir = path.parent().context("Invalid blob path")?;
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create blob store directory: {}", dir.display()))?;
+
+    // Write then rename so a crash never leaves a truncated blob under its hash
+    let partial = path.with_extension("partial");
+    std::fs::write(&partial, data).with_context(|| format!("Failed to write blob: {}", partial.display()))?;
+    std::fs::rename(&partial, &path).with_context(|| format!("Failed to write blob: {}", path.display()))?;
+
+    Ok(sha256)
+}
+
+pub fn load(config: &Config, sha256: &str) -> Result<Vec<u8>> {
+    let path = path(config, sha256)?;
+    std::fs::read(&path).with_context(|| format!("Blob {} is missing from the store ({})", sha256, path.display()))
+}
@@ -0,0 +1,444 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Upper bounds that keep metadata and tsvectors of very large files reasonable
+const MAX_SYMBOLS: usize = 1000;
+const MAX_IDENTIFIERS: usize = 5000;
+const MAX_IDENTIFIER_LEN: usize = 64;
+
+#[derive(Debug, Serialize)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: &'static str,
+    pub line: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+}
+
+/// Search terms derived from the identifiers in a piece of code
+pub struct IdentifierTerms {
+    /// Lowercased compound identifiers and paths, indexed verbatim (`parse_http_request`, `httpclient::new`)
+    pub raw: Vec<String>,
+    /// Their camelCase and snake_case parts, indexed as English words (`parse http request`)
+    pub words: String,
+}
+
+pub fn detect_language(path: Option<&str>, content: &str) -> Option<&'static str> {
+    let extension = path
+        .and_then(|p| Path::new(p).extension())
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+
+    let by_extension = match extension.as_deref() {
+        Some("rs") => Some("rust"),
+        Some("py" | "pyi") => Some("python"),
+        Some("js" | "mjs" | "cjs" | "jsx") => Some("javascript"),
+        Some("ts" | "tsx" | "mts" | "cts") => Some("typescript"),
+        Some("go") => Some("go"),
+        Some("java") => Some("java"),
+        Some("kt" | "kts") => Some("kotlin"),
+        Some("c" | "h") => Some("c"),
+        Some("cc" | "cpp" | "cxx" | "hpp" | "hh" | "hxx") => Some("cpp"),
+        Some("cs") => Some("csharp"),
+        Some("rb") => Some("ruby"),
+        Some("php") => Some("php"),
+        Some("swift") => Some("swift"),
+        Some("scala") => Some("scala"),
+        Some("sh" | "bash" | "zsh") => Some("shell"),
+        Some("sql") => Some("sql"),
+        Some("lua") => Some("lua"),
+        Some("ex" | "exs") => Some("elixir"),
+        Some("hs") => Some("haskell"),
+        Some("toml") => Some("toml"),
+        Some("yaml" | "yml") => Some("yaml"),
+        Some("json") => Some("json"),
+        _ => None,
+    };
+    if by_extension.is_some() {
+        return by_extension;
+    }
+
+    let shebang = content.lines().next().filter(|l| l.starts_with("#!"))?;
+    if shebang.contains("python") {
+        Some("python")
+    } else if shebang.contains("node") || shebang.contains("deno") {
+        Some("javascript")
+    } else if shebang.contains("sh") {
+        Some("shell")
+    } else if shebang.contains("ruby") {
+        Some("ruby")
+    } else {
+        None
+    }
+}
+
+struct Rule {
+    regex: Regex,
+    kind: &'static str,
+    /// Definitions nested deeper than this line belong to it (impl blocks, classes)
+    container: bool,
+}
+
+fn rule(pattern: &str, kind: &'static str, container: bool) -> Rule {
+    Rule {
+        regex: Regex::new(pattern).expect("invalid symbol pattern"),
+        kind,
+        container,
+    }
+}
+
+/// Definition patterns per language. Each captures `name`, and optionally `parent`.
+fn rules(language: &str) -> &'static [Rule] {
+    static RUST: OnceLock<Vec<Rule>> = OnceLock::new();
+    static PYTHON: OnceLock<Vec<Rule>> = OnceLock::new();
+    static JS: OnceLock<Vec<Rule>> = OnceLock::new();
+    static GO: OnceLock<Vec<Rule>> = OnceLock::new();
+    static JVM: OnceLock<Vec<Rule>> = OnceLock::new();
+    static C: OnceLock<Vec<Rule>> = OnceLock::new();
+    static RUBY: OnceLock<Vec<Rule>> = OnceLock::new();
+    static SHELL: OnceLock<Vec<Rule>> = OnceLock::new();
+    static NONE: Vec<Rule> = Vec::new();
+
+    const RUST_VIS: &str = r"^\s*(?:pub(?:\([^)]*\))?\s+)?";
+
+    match language {
+        "rust" => RUST.get_or_init(|| {
+            vec![
+                rule(
+                    &format!(r#"{}(?:default\s+)?(?:const\s+)?(?:async\s+)?(?:unsafe\s+)?(?:extern\s+"[^"]*"\s+)?fn\s+(?P<name>\w+)"#, RUST_VIS),
+                    "function",
+                    false,
+                ),
+                rule(&format!(r"{}struct\s+(?P<name>\w+)", RUST_VIS), "struct", false),
+                rule(&format!(r"{}enum\s+(?P<name>\w+)", RUST_VIS), "enum", false),
+                rule(&format!(r"{}union\s+(?P<name>\w+)", RUST_VIS), "union", false),
+                rule(&format!(r"{}(?:unsafe\s+)?trait\s+(?P<name>\w+)", RUST_VIS), "trait", true),
+                rule(&format!(r"{}type\s+(?P<name>\w+)", RUST_VIS), "type", false),
+                rule(&format!(r"{}mod\s+(?P<name>\w+)", RUST_VIS), "module", true),
+                rule(&format!(r"{}(?:const|static)\s+(?:mut\s+)?(?P<name>[A-Z_][A-Z0-9_]*)\s*:", RUST_VIS), "constant", false),
+                rule(r"^\s*macro_rules!\s*(?P<name>\w+)", "macro", false),
+                rule(
+                    r"^\s*(?:unsafe\s+)?impl(?:<.*?>)?\s+(?:[\w:<>, ]+?\s+for\s+)?(?P<name>[\w:]+)",
+                    "impl",
+                    true,
+                ),
+            ]
+        }),
+        "python" => PYTHON.get_or_init(|| {
+            vec![
+                rule(r"^\s*(?:async\s+)?def\s+(?P<name>\w+)", "function", false),
+                rule(r"^\s*class\s+(?P<name>\w+)", "class", true),
+            ]
+        }),
+        "javascript" | "typescript" => JS.get_or_init(|| {
+            vec![
+                rule(r"^\s*(?:export\s+)?(?:default\s+)?(?:async\s+)?function\s*\*?\s*(?P<name>\w+)", "function", false),
+                rule(r"^\s*(?:export\s+)?(?:default\s+)?(?:abstract\s+)?class\s+(?P<name>\w+)", "class", true),
+                rule(r"^\s*(?:export\s+)?interface\s+(?P<name>\w+)", "interface", true),
+                rule(r"^\s*(?:export\s+)?type\s+(?P<name>\w+)\s*(?:<[^>]*>)?\s*=", "type", false),
+                rule(r"^\s*(?:export\s+)?(?:const\s+)?enum\s+(?P<name>\w+)", "enum", false),
+                rule(
+                    r"^\s*(?:export\s+)?(?:const|let|var)\s+(?P<name>\w+)\s*(?::[^=]+)?=\s*(?:async\s+)?(?:function\b|\([^)]*\)\s*(?::[^=]+)?=>|\w+\s*=>)",
+                    "function",
+                    false,
+                ),
+                rule(
+                    r"^\s+(?:(?:public|private|protected|static|async|readonly|override)\s+)*(?P<name>[A-Za-z_]\w*)\s*\([^)]*\)\s*(?::[^{]+)?\{\s*$",
+                    "method",
+                    false,
+                ),
+            ]
+        }),
+        "go" => GO.get_or_init(|| {
+            vec![
+                rule(r"^func\s+\(\s*\w*\s*\*?(?P<parent>\w+)[^)]*\)\s*(?P<name>\w+)", "method", false),
+                rule(r"^func\s+(?P<name>\w+)", "function", false),
+                rule(r"^type\s+(?P<name>\w+)\s+struct\b", "struct", false),
+                rule(r"^type\s+(?P<name>\w+)\s+interface\b", "interface", false),
+                rule(r"^type\s+(?P<name>\w+)\b", "type", false),
+            ]
+        }),
+        "java" | "kotlin" | "csharp" | "scala" | "swift" | "php" => JVM.get_or_init(|| {
+            vec![
+                rule(
+                    r"^\s*(?:(?:public|private|protected|internal|static|final|abstract|sealed|open|data|partial)\s+)*(?:class|object|record)\s+(?P<name>\w+)",
+                    "class",
+                    true,
+                ),
+                rule(
+                    r"^\s*(?:(?:public|private|protected|internal|static|sealed)\s+)*(?:interface|trait|protocol)\s+(?P<name>\w+)",
+                    "interface",
+                    true,
+                ),
+                rule(r"^\s*(?:(?:public|private|protected|internal|static)\s+)*enum\s+(?:class\s+)?(?P<name>\w+)", "enum", false),
+                rule(
+                    r"^\s*(?:(?:public|private|protected|internal|static|final|abstract|override|open|suspend|async)\s+)*(?:fun|func|def|function)\s+(?:<[^>]*>\s*)?(?:\w+\.)?(?P<name>\w+)",
+                    "function",
+                    false,
+                ),
+                rule(
+                    r"^\s*(?:(?:public|private|protected|internal|static|final|abstract|synchronized|override|virtual|async)\s+)+[\w<>\[\],?\s]+?\s+(?P<name>\w+)\s*\([^;]*$",
+                    "method",
+                    false,
+                ),
+            ]
+        }),
+        "c" | "cpp" => C.get_or_init(|| {
+            vec![
+                rule(r"^\s*(?:template\s*<[^>]*>\s*)?(?:class|struct)\s+(?P<name>\w+)\s*(?::[^{;]*)?\{?\s*$", "struct", true),
+                rule(r"^\s*(?:typedef\s+)?enum\s+(?:class\s+)?(?P<name>\w+)", "enum", false),
+                rule(r"^\s*namespace\s+(?P<name>\w+)", "namespace", false),
+                rule(
+                    r"^(?:[\w:<>\*&]+\s+)+\**(?:(?P<parent>\w+)::)?(?P<name>~?\w+)\s*\([^;]*\)\s*(?:const\s*)?\{?\s*$",
+                    "function",
+                    false,
+                ),
+                rule(r"^\s*#define\s+(?P<name>\w+)", "macro", false),
+            ]
+        }),
+        "ruby" => RUBY.get_or_init(|| {
+            vec![
+                rule(r"^\s*def\s+(?:self\.)?(?P<name>\w+[?!=]?)", "method", false),
+                rule(r"^\s*class\s+(?P<name>[\w:]+)", "class", true),
+                rule(r"^\s*module\s+(?P<name>[\w:]+)", "module", true),
+            ]
+        }),
+        "shell" => SHELL.get_or_init(|| {
+            vec![rule(r"^\s*(?:function\s+)?(?P<name>[\w-]+)\s*\(\)\s*\{?", "function", false)]
+        }),
+        _ => &NONE,
+    }
+}
+
+/// Extract definitions with a line-based scan. Containers (impl blocks, classes)
+/// are tracked by indentation to attach a `parent` to nested definitions, and a
+/// definition after a container's opening brace on the same line gets it as well.
+pub fn extract_symbols(language: &str, content: &str) -> Vec<Symbol> {
+    let rules = rules(language);
+    if rules.is_empty() {
+        return Vec::new();
+    }
+
+    let mut symbols = Vec::new();
+    let mut containers: Vec<(usize, String)> = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let indent = line
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .map(|c| if c == '\t' { 4 } else { 1 })
+            .sum::<usize>();
+        while containers.last().is_some_and(|(i, _)| *i >= indent) {
+            containers.pop();
+        }
+
+        let Some((rule, captures)) = rules
+            .iter()
+            .find_map(|r| r.regex.captures(line).map(|c| (r, c)))
+        else {
+            continue;
+        };
+
+        let name = captures["name"].to_string();
+        let parent = captures
+            .name("parent")
+            .map(|p| p.as_str().to_string())
+            .or_else(|| containers.last().map(|(_, n)| n.clone()));
+
+        // `impl HttpClient { pub fn new() -> Self { ... } }`
+        let inline = rule
+            .container
+            .then(|| line[captures.get(0).map_or(0, |m| m.end())..].split_once('{'))
+            .flatten()
+            .and_then(|(_, body)| rules.iter().find_map(|r| r.regex.captures(body).map(|c| (r.kind, c["name"].to_string()))));
+
+        if rule.container {
+            containers.push((indent, name.clone()));
+        }
+
+        let container = name.clone();
+        symbols.push(Symbol {
+            name,
+            kind: rule.kind,
+            line: index + 1,
+            parent,
+        });
+        if let Some((kind, name)) = inline {
+            symbols.push(Symbol {
+                name,
+                kind,
+                line: index + 1,
+                parent: Some(container),
+            });
+        }
+
+        if symbols.len() >= MAX_SYMBOLS {
+            break;
+        }
+    }
+
+    symbols
+}
+
+/// Split an identifier on snake_case and camelCase boundaries: `getHTTPResponse_code`
+/// becomes `get`, `http`, `response`, `code`.
+pub fn split_identifier(identifier: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+
+    for segment in identifier.split('_').filter(|s| !s.is_empty()) {
+        let chars: Vec<char> = segment.chars().collect();
+        let mut current = String::new();
+
+        for (i, &c) in chars.iter().enumerate() {
+            let boundary = i > 0
+                && c.is_uppercase()
+                && (chars[i - 1].is_lowercase()
+                    || chars[i - 1].is_ascii_digit()
+                    || chars.get(i + 1).is_some_and(|n| n.is_lowercase()) && chars[i - 1].is_uppercase());
+            if boundary && !current.is_empty() {
+                parts.push(current.to_lowercase());
+                current.clear();
+            }
+            current.push(c);
+        }
+        if !current.is_empty() {
+            parts.push(current.to_lowercase());
+        }
+    }
+
+    parts
+}
+
+/// True for identifiers the English parser would split or flatten: snake_case,
+/// camelCase and `::` paths.
+pub fn is_compound_identifier(word: &str) -> bool {
+    let word = word.trim_matches(|c: char| !c.is_alphanumeric() && c != '_');
+    if word.is_empty() || word.len() > MAX_IDENTIFIER_LEN {
+        return false;
+    }
+    if word.contains("::") {
+        return word.split("::").all(is_identifier);
+    }
+    is_identifier(word) && split_identifier(word).len() > 1
+}
+
+fn is_identifier(word: &str) -> bool {
+    let mut chars = word.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Normalized form used for verbatim identifier lexemes, on both the index and query side.
+pub fn normalize_identifier(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric() && c != '_')
+        .to_lowercase()
+}
+
+pub fn identifier_terms(content: &str) -> IdentifierTerms {
+    let mut raw = BTreeSet::new();
+    let mut words = BTreeSet::new();
+
+    // Tokens are runs of identifier characters, keeping `::` so paths stay whole
+    let mut token = String::new();
+    let mut chars = content.chars().peekable();
+    loop {
+        let c = chars.next();
+        match c {
+            Some(c) if c.is_alphanumeric() || c == '_' => token.push(c),
+            Some(':') if chars.peek() == Some(&':') && !token.is_empty() => {
+                chars.next();
+                token.push_str("::");
+            }
+            _ => {
+                let path = token.trim_end_matches(':');
+                if path.len() <= MAX_IDENTIFIER_LEN && is_compound_identifier(path) {
+                    raw.insert(path.to_lowercase());
+                    for segment in path.split("::") {
+                        if is_compound_identifier(segment) {
+                            raw.insert(segment.to_lowercase());
+                        }
+                        words.extend(split_identifier(segment));
+                    }
+                }
+                token.clear();
+                if c.is_none() || raw.len() >= MAX_IDENTIFIERS {
+                    break;
+                }
+            }
+        }
+    }
+
+    IdentifierTerms {
+        raw: raw.into_iter().collect(),
+        words: words.into_iter().collect::<Vec<_>>().join(" "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(symbols: &[Symbol]) -> Vec<String> {
+        symbols
+            .iter()
+            .map(|s| match &s.parent {
+                Some(parent) => format!("{} {}::{}", s.kind, parent, s.name),
+                None => format!("{} {}", s.kind, s.name),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rust_methods_get_their_impl_as_parent() {
+        let code = "pub struct HttpClient;\n\nimpl HttpClient {\n    pub fn new() -> Self {\n        HttpClient\n    }\n}\n\nfn main() {}\n";
+        assert_eq!(
+            names(&extract_symbols("rust", code)),
+            ["struct HttpClient", "impl HttpClient", "function HttpClient::new", "function main"]
+        );
+    }
+
+    #[test]
+    fn definition_on_the_container_line_gets_it_as_parent() {
+        let code = "impl HttpClient { pub fn new() -> Self { HttpClient } }\nimpl<T> Display for Wrapper<T> { fn fmt(&self) {} }\nfn free() {}\n";
+        assert_eq!(
+            names(&extract_symbols("rust", code)),
+            [
+                "impl HttpClient",
+                "function HttpClient::new",
+                "impl Wrapper",
+                "function Wrapper::fmt",
+                "function free",
+            ]
+        );
+    }
+
+    #[test]
+    fn python_methods_belong_to_their_class() {
+        let code = "class Parser:\n    def parse(self):\n        pass\n\ndef main():\n    pass\n";
+        assert_eq!(names(&extract_symbols("python", code)), ["class Parser", "function Parser::parse", "function main"]);
+    }
+
+    #[test]
+    fn splits_snake_and_camel_case() {
+        assert_eq!(split_identifier("getHTTPResponse_code"), ["get", "http", "response", "code"]);
+        assert_eq!(split_identifier("parse_http_request"), ["parse", "http", "request"]);
+        assert_eq!(split_identifier("utf8Decoder"), ["utf8", "decoder"]);
+        assert_eq!(split_identifier("__init__"), ["init"]);
+        assert!(is_compound_identifier("HttpClient::new"));
+        assert!(!is_compound_identifier("client"));
+    }
+
+    #[test]
+    fn identifier_terms_keep_paths_whole() {
+        let terms = identifier_terms("let client = HttpClient::new(); client.send_request();");
+        assert_eq!(terms.raw, ["httpclient", "httpclient::new", "send_request"]);
+        assert_eq!(terms.words, "client http new request send");
+    }
+}
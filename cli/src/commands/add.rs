@@ -1,62 +1,148 @@
 use anyhow::{bail, Context, Result};
-use std::io::{self, Read};
+use std::io::{self, IsTerminal, Read};
+use std::path::Path;
 use crate::config::Config;
+use crate::entries::{Attachment, Blob};
+use crate::extract::{self, Document, Registry};
+use crate::{blobs, db, entries, language};
 
 pub async fn run(
     entry_type: String,
     content: Option<String>,
     path: Option<String>,
+    attach: Option<String>,
     metadata: Option<String>,
+    lang: Option<String>,
 ) -> Result<()> {
-    // Get content from: -c flag, -p flag (read file), or stdin
-    let (content, file_path) = match (content, path) {
-        (Some(c), None) => (c, None),
-        (None, Some(p)) => {
-            let c = std::fs::read_to_string(&p)
-                .with_context(|| format!("Failed to read file: {}", p))?;
-            (c, Some(p))
-        }
-        (None, None) => (read_stdin()?, None),
+    if let Some(file) = attach {
+        return add_attachment(entry_type, content, file, metadata, lang).await;
+    }
+
+    // Get content from: -c flag, -p flag (read file, extracting text from documents), or stdin
+    let (document, file_path) = match (content, path) {
+        (Some(c), None) => (Document::text(c), None),
+        (None, Some(p)) => (extract::read_file(Path::new(&p))?, Some(entries::stored_path(Path::new(&p))?)),
+        (None, None) => (Document::text(read_stdin()?), None),
         (Some(_), Some(_)) => bail!("Cannot specify both --content and --path"),
     };
 
     let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
 
-    let (client, connection) =
-        tokio_postgres::connect(&config.connection_string(), tokio_postgres::NoTls)
-            .await
-            .context("Failed to connect to database")?;
+    let client = db::connect(&config).await?;
+    if let Some(lang) = &lang {
+        language::check(&client, lang).await?;
+    }
+
+    // Parse metadata JSON if provided; it wins over metadata extracted from the file
+    let metadata_json = with_explicit_metadata(document.metadata.clone(), metadata.as_deref())?;
+
+    let id = entries::insert(
+        &client,
+        &entry_type,
+        &document.content,
+        file_path.as_deref(),
+        &metadata_json,
+        document.blob(),
+        lang.as_deref(),
+    )
+    .await?;
+    println!("Inserted entry with id: {}", id);
+
+    Ok(())
+}
+
+/// Store a binary file (image, diagram, PDF, ...) with a text description that makes
+/// it searchable. The description comes from -c or stdin; formats with an extractor
+/// fall back to their extracted text.
+async fn add_attachment(
+    entry_type: String,
+    description: Option<String>,
+    file: String,
+    metadata: Option<String>,
+    lang: Option<String>,
+) -> Result<()> {
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+
+    let path = Path::new(&file);
+    let size = std::fs::metadata(path)
+        .with_context(|| format!("Failed to read file: {}", file))?
+        .len();
+    if size > config.attachments.max_size {
+        bail!(
+            "{} is {} bytes, over the attachment limit of {} bytes.\n Raise attachments.max_size in {} to allow it.",
+            file,
+            size,
+            config.attachments.max_size,
+            Config::config_path()?.display()
+        );
+    }
+    let data = std::fs::read(path).with_context(|| format!("Failed to read file: {}", file))?;
+    let file_path = entries::stored_path(path)?;
+    let mime_type = mime_guess::from_path(path).first_or_octet_stream().to_string();
 
-    tokio::spawn(async move {
-        if let Err(e) = connection.await {
-            eprintln!("Database connection error: {}", e);
+    let description = match description {
+        Some(description) => description,
+        None if !io::stdin().is_terminal() => read_stdin()?,
+        None => {
+            let registry = Registry::default();
+            match registry.detect(path, &data) {
+                Some(extractor) => extractor.extract(&data)?.content,
+                None => String::new(),
+            }
         }
-    });
+    };
+    if description.trim().is_empty() {
+        bail!("Describe the attachment with -c \"...\" or on stdin so it can be found by search");
+    }
 
-    // Parse metadata JSON if provided
-    let metadata_json: serde_json::Value = match &metadata {
-        Some(m) => serde_json::from_str(m).context("Invalid JSON in metadata")?,
-        None => serde_json::json!({}),
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string());
+    let metadata_json = with_explicit_metadata(
+        serde_json::json!({ "attachment": { "file_name": file_name, "size": size } }),
+        metadata.as_deref(),
+    )?;
+
+    let stored;
+    let attachment = if config.attachments.store_threshold.is_some_and(|t| size > t) {
+        stored = blobs::store(&config, &data)?;
+        Attachment::Stored { sha256: &stored, mime_type: &mime_type }
+    } else {
+        Attachment::Inline(Blob { data: &data, mime_type: &mime_type })
     };
 
-    let row = client
-        .query_one(
-            r#"
-            INSERT INTO entries (entry_type, content, file_path, metadata)
-            VALUES ($1, $2, $3, $4)
-            RETURNING id
-            "#,
-            &[&entry_type, &content, &file_path, &metadata_json],
-        )
-        .await
-        .context("Failed to insert entry")?;
-
-    let id: i32 = row.get(0);
+    let client = db::connect(&config).await?;
+    if let Some(lang) = &lang {
+        language::check(&client, lang).await?;
+    }
+    let id = entries::insert_attachment(
+        &client,
+        &entry_type,
+        &description,
+        Some(&file_path),
+        &metadata_json,
+        attachment,
+        lang.as_deref(),
+    )
+    .await?;
     println!("Inserted entry with id: {}", id);
 
     Ok(())
 }
 
+/// Merge the -m JSON over `metadata`; explicit keys win.
+fn with_explicit_metadata(mut metadata: serde_json::Value, explicit: Option<&str>) -> Result<serde_json::Value> {
+    let Some(explicit) = explicit else {
+        return Ok(metadata);
+    };
+    let explicit: serde_json::Value = serde_json::from_str(explicit).context("Invalid JSON in metadata")?;
+    match (metadata.as_object_mut(), explicit.as_object()) {
+        (Some(target), Some(explicit)) => {
+            target.extend(explicit.clone());
+            Ok(metadata)
+        }
+        _ => Ok(explicit),
+    }
+}
+
 fn read_stdin() -> Result<String> {
     let mut buffer = String::new();
     io::stdin().read_to_string(&mut buffer).context("Failed to read from stdin")?;
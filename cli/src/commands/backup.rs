@@ -0,0 +1,181 @@
+use anyhow::{bail, Context, Result};
+use bollard::Docker;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use crate::config::Config;
+use crate::{docker, systemd};
+
+const BACKUP_PREFIX: &str = "elastic-claude-";
+const BACKUP_EXTENSION: &str = "dump";
+const SERVICE_UNIT: &str = "elastic-claude-backup.service";
+const TIMER_UNIT: &str = "elastic-claude-backup.timer";
+
+pub async fn run(list: bool, schedule: Option<String>) -> Result<()> {
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+
+    if list {
+        return list_backups(&config);
+    }
+
+    if let Some(schedule) = schedule {
+        return install_schedule(&schedule);
+    }
+
+    let docker_client = docker::connect().await?;
+    if docker::get_container_status(&docker_client).await? != docker::ContainerStatus::Running {
+        bail!("elastic-claude is not running. Run 'elastic-claude start' first.");
+    }
+
+    let path = create_backup(&docker_client, &config).await?;
+    println!("✓ Backup written to {}", path.display());
+
+    prune(&config, &path)?;
+
+    Ok(())
+}
+
+/// Dump the database with `pg_dump` inside the container and write it to the backup directory.
+/// The custom format is compressed and can be read back with `pg_restore`.
+pub async fn create_backup(docker_client: &Docker, config: &Config) -> Result<PathBuf> {
+    println!("Dumping database {}...", config.database.name);
+    let dump = docker::exec(
+        docker_client,
+        vec![
+            "pg_dump",
+            "-U",
+            &config.database.user,
+            "-d",
+            &config.database.name,
+            "--format=custom",
+            "--compress=9",
+        ],
+        None,
+    )
+    .await
+    .context("pg_dump failed")?;
+
+    write_backup(config, &dump, None)
+}
+
+/// Write a dump into the backup directory; `label` is appended to the timestamped name.
+pub fn write_backup(config: &Config, dump: &[u8], label: Option<&str>) -> Result<PathBuf> {
+    let dir = config.backup_dir()?;
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Could not create backup directory: {}", dir.display()))?;
+
+    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let name = match label {
+        Some(label) => format!("{}{}-{}.{}", BACKUP_PREFIX, timestamp, label, BACKUP_EXTENSION),
+        None => format!("{}{}.{}", BACKUP_PREFIX, timestamp, BACKUP_EXTENSION),
+    };
+
+    let path = dir.join(name);
+    std::fs::write(&path, dump)
+        .with_context(|| format!("Failed to write backup: {}", path.display()))?;
+
+    Ok(path)
+}
+
+/// Backups in `dir`, oldest first (timestamps in the file name sort chronologically).
+fn find_backups(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("Could not read backup directory: {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.extension().is_some_and(|ext| ext == BACKUP_EXTENSION)
+                && p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(BACKUP_PREFIX))
+        })
+        .collect();
+
+    backups.sort();
+    Ok(backups)
+}
+
+/// Apply the retention rules from config. The backup just written is never removed.
+fn prune(config: &Config, latest: &Path) -> Result<()> {
+    let backups = find_backups(&config.backup_dir()?)?;
+    let mut expired = Vec::new();
+
+    let keep_last = config.backup.keep_last;
+    if keep_last > 0 && backups.len() > keep_last {
+        expired.extend(backups[..backups.len() - keep_last].iter().cloned());
+    }
+
+    if let Some(days) = config.backup.max_age_days {
+        let cutoff = SystemTime::now() - Duration::from_secs(days * 24 * 60 * 60);
+        for backup in &backups {
+            let modified = std::fs::metadata(backup).and_then(|m| m.modified()).ok();
+            if modified.is_some_and(|m| m < cutoff) && !expired.contains(backup) {
+                expired.push(backup.clone());
+            }
+        }
+    }
+
+    for backup in expired.iter().filter(|b| b.as_path() != latest) {
+        std::fs::remove_file(backup)
+            .with_context(|| format!("Failed to remove old backup: {}", backup.display()))?;
+        println!("Removed old backup {}", backup.display());
+    }
+
+    Ok(())
+}
+
+fn list_backups(config: &Config) -> Result<()> {
+    let dir = config.backup_dir()?;
+    let backups = find_backups(&dir)?;
+
+    if backups.is_empty() {
+        println!("No backups found in {}", dir.display());
+        return Ok(());
+    }
+
+    println!("Backups in {}:\n", dir.display());
+    for backup in backups.iter().rev() {
+        let size = std::fs::metadata(backup).map(|m| m.len()).unwrap_or(0);
+        println!("  {}  ({} KB)", backup.display(), size.div_ceil(1024));
+    }
+
+    Ok(())
+}
+
+fn install_schedule(schedule: &str) -> Result<()> {
+    if schedule == "off" {
+        systemd::uninstall(&[SERVICE_UNIT, TIMER_UNIT], TIMER_UNIT)?;
+        println!("✓ Scheduled backups disabled");
+        return Ok(());
+    }
+
+    let service = format!(
+        "[Unit]\n\
+         Description=elastic-claude database backup\n\
+         \n\
+         [Service]\n\
+         Type=oneshot\n\
+         ExecStart={} backup\n",
+        systemd::quote(&systemd::exec_path()?)
+    );
+
+    let timer = format!(
+        "[Unit]\n\
+         Description=Scheduled elastic-claude database backups\n\
+         \n\
+         [Timer]\n\
+         OnCalendar={}\n\
+         Persistent=true\n\
+         \n\
+         [Install]\n\
+         WantedBy=timers.target\n",
+        schedule
+    );
+
+    systemd::install(&[(SERVICE_UNIT, service), (TIMER_UNIT, timer)], TIMER_UNIT)?;
+    println!("Backups will run {}", schedule);
+    Ok(())
+}
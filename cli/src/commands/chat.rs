@@ -1,9 +1,11 @@
 use anyhow::{bail, Context, Result};
 use std::path::PathBuf;
 use crate::config::Config;
+use crate::summary;
+use crate::{db, entries};
 use super::current_chat::extract_text_from_jsonl;
 
-pub async fn run(session_file: PathBuf) -> Result<()> {
+pub async fn run(session_file: PathBuf, summarize: bool) -> Result<()> {
     if !session_file.exists() {
         bail!("Session file not found: {}", session_file.display());
     }
@@ -24,32 +26,21 @@ pub async fn run(session_file: PathBuf) -> Result<()> {
     // Connect to database and insert
     let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
 
-    let (client, connection) =
-        tokio_postgres::connect(&config.connection_string(), tokio_postgres::NoTls)
-            .await
-            .context("Failed to connect to database")?;
+    let client = db::connect(&config).await?;
 
-    tokio::spawn(async move {
-        if let Err(e) = connection.await {
-            eprintln!("Database connection error: {}", e);
-        }
-    });
-
-    let row = client
-        .query_one(
-            r#"
-            INSERT INTO entries (entry_type, content, file_path, metadata)
-            VALUES ($1, $2, $3, $4)
-            RETURNING id
-            "#,
-            &[&"chat", &content, &Some(&file_path), &serde_json::json!({})],
-        )
+    let id = entries::insert(&client, "chat", &content, Some(&file_path), &serde_json::json!({}), None, None)
         .await
         .context("Failed to insert chat entry")?;
-
-    let id: i32 = row.get(0);
     println!("Inserted chat with id: {}", id);
     println!("Chat file: {}", file_path);
 
+    if summarize {
+        let summarizer = summary::from_config(&config.summarizer, None, summary::DEFAULT_SENTENCES)?;
+        match super::summarize::summarize_id(&client, summarizer.as_ref(), id).await? {
+            Some(summary_id) => println!("✓ Summary saved as entry {}", summary_id),
+            None => println!("Chat has no prose to summarize"),
+        }
+    }
+
     Ok(())
 }
@@ -0,0 +1,448 @@
+//! `elastic-claude context`: the most relevant knowledge for a task that fits in a
+//! token budget, as one Markdown document with citations.
+//!
+//! Entries matching any word of the task are split into passages, which Postgres
+//! ranks against the same query: first by which of the task's words they contain,
+//! rarer words counting for more (IDF), then by `ts_rank`. Passages are taken best first while they fit,
+//! skipping near-duplicates of ones already taken; an entry with no passage that
+//! fits may still contribute its search snippet.
+
+use anyhow::{bail, Context, Result};
+use std::collections::{BTreeSet, HashSet};
+use tokio_postgres::types::ToSql;
+use tokio_postgres::Client;
+use crate::config::Config;
+use crate::db;
+use super::search::{self, sql_params, Filters, LanguageTerms};
+
+/// Passages are built up to about this many tokens
+const PASSAGE_TOKENS: usize = 300;
+
+/// Passages sharing this fraction of their words with a selected one are skipped
+const DUPLICATE_OVERLAP: f64 = 0.7;
+
+pub struct ContextOptions {
+    pub task: String,
+    pub budget: usize,
+    /// Entries considered
+    pub limit: i64,
+    pub filters: Filters,
+}
+
+struct Hit {
+    id: i32,
+    entry_type: String,
+    file_path: Option<String>,
+    title: Option<String>,
+    language: Option<String>,
+    /// Text search configuration of the entry
+    text_language: String,
+    snippet: String,
+    /// IDF-weighted count of the task words the entry contains
+    coverage: f64,
+    passages: Vec<String>,
+}
+
+/// A passage (or snippet) picked for the bundle
+struct Excerpt {
+    hit: usize,
+    /// Position in the entry, so excerpts print in document order
+    order: usize,
+    text: String,
+}
+
+pub async fn run(options: ContextOptions) -> Result<()> {
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+    let client = db::connect(&config).await?;
+
+    let bundle = build(&client, &options).await?;
+    print!("{}", bundle);
+    Ok(())
+}
+
+/// The Markdown bundle for `options.task`
+pub async fn build(client: &Client, options: &ContextOptions) -> Result<String> {
+    let languages = search::languages(client, options.filters.language.as_deref()).await?;
+    let weights = term_weights(client, &options.task, &languages).await?;
+    let hits = find_hits(client, &options.task, &weights, &languages, &options.filters, options.limit).await?;
+    if hits.is_empty() {
+        return Ok(format!("# Context: {}\n\nNo matching entries.\n", options.task.trim()));
+    }
+
+    // Score every passage by its own coverage and rank, weighted by its entry's
+    let best_coverage = hits.iter().map(|h| h.coverage).fold(f64::MIN_POSITIVE, f64::max);
+    let mut candidates = Vec::new();
+    for (index, hit) in hits.iter().enumerate() {
+        let weight = 0.5 + 0.5 * hit.coverage / best_coverage;
+        let ranked = rank_passages(client, &options.task, &weights, &hit.text_language, &hit.passages).await?;
+        for (order, (coverage, rank)) in ranked.into_iter().enumerate() {
+            if coverage > 0.0 {
+                candidates.push(((coverage + rank.min(1.0) as f64) * weight, index, order));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let selected = select(&hits, &candidates, options.budget.saturating_sub(header_tokens(&options.task)));
+    Ok(render(&options.task, options.budget, &hits, selected))
+}
+
+/// Inverse document frequency of each word of the task among live entries, each
+/// entry matched in its own language
+async fn term_weights(client: &Client, task: &str, languages: &[String]) -> Result<Vec<f64>> {
+    let terms = LanguageTerms::new(task, 1, languages);
+    if terms.params.is_empty() {
+        bail!("Task description is empty");
+    }
+    let counts: Vec<String> = (0..terms.len())
+        .map(|i| format!("count(*) FILTER (WHERE {})", terms.matching(|t| format!("content_tsv @@ {}", t[i]))))
+        .collect();
+    let sql = format!("SELECT count(*), {} FROM entries WHERE deleted_at IS NULL", counts.join(", "));
+    let row = client
+        .query_one(&sql, &sql_params(&terms.params))
+        .await
+        .context("Failed to count term frequencies")?;
+
+    let total = row.get::<_, i64>(0) as f64;
+    Ok((1..=counts.len())
+        .map(|i| (1.0 + total / (1.0 + row.get::<_, i64>(i) as f64)).ln())
+        .collect())
+}
+
+/// `w1 * (v @@ term1)::int + w2 * (v @@ term2)::int + ...`
+fn coverage_sql(terms: &[String], weights: &[f64], vector: &str) -> String {
+    terms
+        .iter()
+        .zip(weights)
+        .map(|(term, weight)| format!("{:.4}::float8 * ({} @@ {})::int", weight, vector, term))
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+async fn find_hits(
+    client: &Client,
+    task: &str,
+    weights: &[f64],
+    languages: &[String],
+    filters: &Filters,
+    limit: i64,
+) -> Result<Vec<Hit>> {
+    let terms = LanguageTerms::new(task, 4, languages);
+    let sql = format!(
+        r#"
+        SELECT id, entry_type, file_path, content, metadata->>'title' AS title,
+               metadata->>'language' AS code_language, language::text AS text_language,
+               ts_headline(language, content, query,
+                   'MaxFragments=2, MaxWords=30, MinWords=15, FragmentDelimiter= … ') AS snippet,
+               {} AS coverage
+        FROM entries, LATERAL (SELECT {} AS query) q
+        WHERE {} AND deleted_at IS NULL
+          AND ($2::text IS NULL OR entry_type = $2)
+          AND ($3::text IS NULL OR metadata->>'project' = $3)
+        ORDER BY coverage DESC, ts_rank(content_tsv, query) DESC
+        LIMIT $1
+        "#,
+        terms.per_language(|t| coverage_sql(t, weights, "content_tsv")),
+        terms.per_language(|t| t.join(" || ")),
+        terms.matching(|t| format!("content_tsv @@ ({})", t.join(" || ")))
+    );
+    let mut args: Vec<&(dyn ToSql + Sync)> = vec![&limit, &filters.entry_type, &filters.project];
+    args.extend(sql_params(&terms.params));
+    let rows = client.query(&sql, &args).await.context("Search query failed")?;
+
+    Ok(rows
+        .iter()
+        .map(|row| Hit {
+            id: row.get("id"),
+            entry_type: row.get("entry_type"),
+            file_path: row.get("file_path"),
+            title: row.get("title"),
+            language: row.get("code_language"),
+            text_language: row.get("text_language"),
+            snippet: row.get::<_, String>("snippet").replace("<b>", "**").replace("</b>", "**"),
+            coverage: row.get("coverage"),
+            passages: passages(row.get("content"), PASSAGE_TOKENS),
+        })
+        .collect())
+}
+
+/// Coverage and `ts_rank` of each passage against the task, in passage order.
+/// Passages are parsed in their entry's text search `language`.
+async fn rank_passages(
+    client: &Client,
+    task: &str,
+    weights: &[f64],
+    language: &str,
+    passages: &[String],
+) -> Result<Vec<(f64, f32)>> {
+    let (terms, params) = search::tsquery_terms(task, 3, language);
+    let sql = format!(
+        r#"
+        SELECT {} AS coverage, ts_rank(p.tsv, q.query) AS rank
+        FROM (SELECT ord, to_tsvector($2::text::regconfig, text) AS tsv
+              FROM unnest($1::text[]) WITH ORDINALITY AS u(text, ord)) p,
+             (SELECT {} AS query) q
+        ORDER BY p.ord
+        "#,
+        coverage_sql(&terms, weights, "p.tsv"),
+        terms.join(" || ")
+    );
+    let mut args: Vec<&(dyn ToSql + Sync)> = vec![&passages, &language];
+    args.extend(sql_params(&params));
+    let rows = client.query(&sql, &args).await.context("Failed to rank passages")?;
+    Ok(rows.iter().map(|row| (row.get("coverage"), row.get("rank"))).collect())
+}
+
+/// Greedy selection of the best passages within `budget` tokens. Entries left
+/// without a passage (none matched, or none fit) fall back to their snippet.
+fn select(hits: &[Hit], candidates: &[(f64, usize, usize)], budget: usize) -> Vec<Excerpt> {
+    let mut selected: Vec<Excerpt> = Vec::new();
+    let mut selected_words: Vec<HashSet<String>> = Vec::new();
+    let mut cited = HashSet::new();
+    let mut used = 0;
+
+    let mut take = |hit: usize, order: usize, text: &str, selected: &mut Vec<Excerpt>| {
+        let words = word_set(text);
+        if selected_words.iter().any(|other| overlap(&words, other) >= DUPLICATE_OVERLAP) {
+            return;
+        }
+        let mut cost = approx_tokens(text);
+        if !cited.contains(&hit) {
+            cost += citation_tokens(&hits[hit]);
+        }
+        if used + cost > budget {
+            return;
+        }
+        used += cost;
+        cited.insert(hit);
+        selected_words.push(words);
+        selected.push(Excerpt { hit, order, text: text.to_string() });
+    };
+
+    for &(_, hit, order) in candidates {
+        take(hit, order, &hits[hit].passages[order], &mut selected);
+    }
+    for (index, hit) in hits.iter().enumerate() {
+        if !selected.iter().any(|e| e.hit == index) && !hit.snippet.trim().is_empty() {
+            take(index, 0, hit.snippet.trim(), &mut selected);
+        }
+    }
+    selected
+}
+
+fn render(task: &str, budget: usize, hits: &[Hit], mut excerpts: Vec<Excerpt>) -> String {
+    // Entries in rank order (hits are ranked), excerpts in document order
+    excerpts.sort_by_key(|e| (e.hit, e.order));
+    let entries: Vec<usize> = excerpts.iter().map(|e| e.hit).collect::<BTreeSet<_>>().into_iter().collect();
+    if entries.is_empty() {
+        return format!("# Context: {}\n\nNo excerpt fits in {} tokens.\n", task.trim(), budget);
+    }
+
+    let mut body = String::new();
+    for (number, &index) in entries.iter().enumerate() {
+        let hit = &hits[index];
+        body.push_str(&format!("\n## [{}] {}\n\n{}\n", number + 1, label(hit), citation(hit)));
+        let texts: Vec<&str> = excerpts.iter().filter(|e| e.hit == index).map(|e| e.text.as_str()).collect();
+        for (i, text) in texts.iter().enumerate() {
+            if i > 0 {
+                body.push_str("\n…\n");
+            }
+            if hit.entry_type == "code" {
+                body.push_str(&format!("\n```{}\n{}\n```\n", hit.language.as_deref().unwrap_or(""), text));
+            } else {
+                body.push_str(&format!("\n{}\n", text));
+            }
+        }
+    }
+
+    body.push_str("\n## Sources\n\n");
+    for (number, &index) in entries.iter().enumerate() {
+        let hit = &hits[index];
+        body.push_str(&format!("{}. {} ({})\n", number + 1, label(hit), citation(hit).trim_start_matches("Source: ")));
+    }
+
+    format!(
+        "# Context: {}\n\n_{} excerpts from {} entries, about {} of {} tokens._\n{}",
+        task.trim(),
+        excerpts.len(),
+        entries.len(),
+        approx_tokens(&body) + header_tokens(task),
+        budget,
+        body
+    )
+}
+
+fn label(hit: &Hit) -> String {
+    hit.title
+        .clone()
+        .or_else(|| hit.file_path.as_ref().map(|p| p.rsplit('/').next().unwrap_or(p).to_string()))
+        .unwrap_or_else(|| format!("{} #{}", hit.entry_type, hit.id))
+}
+
+fn citation(hit: &Hit) -> String {
+    match &hit.file_path {
+        Some(path) => format!("Source: entry #{} ({}), `{}`", hit.id, hit.entry_type, path),
+        None => format!("Source: entry #{} ({})", hit.id, hit.entry_type),
+    }
+}
+
+/// Heading, source line and list entry for one cited entry
+fn citation_tokens(hit: &Hit) -> usize {
+    2 * (approx_tokens(&label(hit)) + approx_tokens(&citation(hit))) + 8
+}
+
+fn header_tokens(task: &str) -> usize {
+    approx_tokens(task) + 30
+}
+
+/// Approximate token count for BPE tokenizers: a word costs one token per four
+/// characters (rounded up), and each punctuation character costs one.
+pub fn approx_tokens(text: &str) -> usize {
+    let mut tokens = 0;
+    let mut word = 0usize;
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            word += 1;
+            continue;
+        }
+        tokens += word.div_ceil(4);
+        word = 0;
+        if !c.is_whitespace() {
+            tokens += 1;
+        }
+    }
+    tokens + word.div_ceil(4)
+}
+
+/// Split `content` into passages of about `target` tokens: paragraphs are joined
+/// until the next would go over, and longer paragraphs are split by lines, then
+/// words.
+pub fn passages(content: &str, target: usize) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let mut current_tokens = 0;
+
+    for paragraph in content.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        for piece in split_long(paragraph, target) {
+            let tokens = approx_tokens(&piece);
+            if current_tokens + tokens > target && !current.is_empty() {
+                out.push(std::mem::take(&mut current));
+                current_tokens = 0;
+            }
+            if !current.is_empty() {
+                current.push_str("\n\n");
+            }
+            current.push_str(&piece);
+            current_tokens += tokens;
+        }
+    }
+    if !current.is_empty() {
+        out.push(current);
+    }
+    out
+}
+
+fn split_long(paragraph: &str, target: usize) -> Vec<String> {
+    if approx_tokens(paragraph) <= target {
+        return vec![paragraph.to_string()];
+    }
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let multiline = paragraph.lines().nth(1).is_some();
+    let units: Vec<&str> = if multiline {
+        paragraph.lines().collect()
+    } else {
+        paragraph.split_inclusive(' ').collect()
+    };
+    for unit in units {
+        let separator = if multiline && !current.is_empty() { "\n" } else { "" };
+        if approx_tokens(&current) + approx_tokens(unit) > target && !current.is_empty() {
+            out.push(std::mem::take(&mut current));
+        } else {
+            current.push_str(separator);
+        }
+        if approx_tokens(unit) > target && unit.contains(' ') {
+            out.extend(split_long(unit, target));
+        } else {
+            current.push_str(unit);
+        }
+    }
+    if !current.trim().is_empty() {
+        out.push(current);
+    }
+    out
+}
+
+fn word_set(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 2)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Share of the smaller set's words found in the other
+fn overlap(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let smaller = a.len().min(b.len());
+    if smaller == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / smaller as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn approximates_tokens() {
+        assert_eq!(approx_tokens(""), 0);
+        assert_eq!(approx_tokens("the retry"), 3);
+        assert_eq!(approx_tokens("fn main() {}"), 6);
+        // Roughly four characters per token for prose
+        let prose = "The sync worker retries failed uploads with exponential backoff and jitter. ".repeat(20);
+        let tokens = approx_tokens(&prose);
+        assert!(tokens > prose.len() / 6 && tokens < prose.len() / 3);
+    }
+
+    #[test]
+    fn splits_into_passages_within_target() {
+        let paragraph = "word ".repeat(40);
+        let content = [paragraph.trim(); 10].join("\n\n");
+        let passages = passages(&content, 100);
+        assert!(passages.len() > 1);
+        assert!(passages.iter().all(|p| approx_tokens(p) <= 100));
+        assert_eq!(passages.join(" ").split_whitespace().count(), 400);
+
+        // A single long line is split by words
+        let line = "token ".repeat(500);
+        let passages = super::passages(&line, 50);
+        assert!(passages.iter().all(|p| approx_tokens(p) <= 50));
+        assert_eq!(passages.join(" ").split_whitespace().count(), 500);
+    }
+
+    #[test]
+    fn skips_near_duplicates_and_respects_budget() {
+        let hit = |id, passages: Vec<&str>| Hit {
+            id,
+            entry_type: "document".to_string(),
+            file_path: None,
+            title: Some(format!("Doc {}", id)),
+            language: None,
+            text_language: "english".to_string(),
+            snippet: String::new(),
+            coverage: 1.0,
+            passages: passages.into_iter().map(str::to_string).collect(),
+        };
+        let text = "Uploads are retried with exponential backoff and random jitter between attempts.";
+        let hits = vec![
+            hit(1, vec![text, "Unrelated paragraph about lunch options."]),
+            hit(2, vec![text]),
+            hit(3, vec![&"long passage ".repeat(200)]),
+        ];
+        let candidates = vec![(1.0, 0, 0), (0.9, 1, 0), (0.8, 2, 0), (0.1, 0, 1)];
+
+        let selected = select(&hits, &candidates, 100);
+        let picked: Vec<(usize, usize)> = selected.iter().map(|e| (e.hit, e.order)).collect();
+        assert_eq!(picked, vec![(0, 0), (0, 1)]);
+    }
+}
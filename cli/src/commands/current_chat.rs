@@ -1,7 +1,9 @@
 use anyhow::{bail, Context, Result};
+use serde::Serialize;
 use serde_json::Value;
 use std::path::PathBuf;
 use crate::config::Config;
+use crate::{db, entries};
 
 /// Extract plain text from JSONL chat content for proper full-text indexing.
 /// Each line is a JSON object with message.content[].text structure.
@@ -42,15 +44,89 @@ pub fn extract_text_from_jsonl(raw_content: &str) -> String {
     texts.join("\n\n")
 }
 
-pub fn get_current_chat_path() -> Result<PathBuf> {
-    let home = dirs::home_dir().context("Could not find home directory")?;
+/// One block of a chat session, for showing the conversation with its speakers
+#[derive(Serialize)]
+pub struct Turn {
+    /// `user` or `assistant` (`unknown` when only the indexed text is left)
+    pub role: String,
+    /// `text`, `thinking`, `tool_use` or `tool_result`
+    pub kind: String,
+    pub text: String,
+}
 
+/// The session's messages in order. Unlike `extract_text_from_jsonl`, this keeps
+/// who said what, plain-string messages and tool calls.
+pub fn extract_turns_from_jsonl(raw_content: &str) -> Vec<Turn> {
+    let mut turns = Vec::new();
+    let mut push = |role: &str, kind: &str, text: &str| {
+        if !text.trim().is_empty() {
+            turns.push(Turn {
+                role: role.to_string(),
+                kind: kind.to_string(),
+                text: text.trim().to_string(),
+            });
+        }
+    };
+
+    for json in raw_content.lines().filter_map(|line| serde_json::from_str::<Value>(line).ok()) {
+        let Some(message) = json.get("message") else {
+            if let Some(tool_result) = json.get("tool_result").and_then(|t| t.as_str()) {
+                push("user", "tool_result", tool_result);
+            }
+            continue;
+        };
+        let role = message
+            .get("role")
+            .or_else(|| json.get("type"))
+            .and_then(|r| r.as_str())
+            .unwrap_or("assistant");
+
+        match message.get("content") {
+            Some(Value::String(text)) => push(role, "text", text),
+            Some(Value::Array(items)) => {
+                for item in items {
+                    match item.get("type").and_then(|t| t.as_str()) {
+                        Some("thinking") => push(role, "thinking", item["thinking"].as_str().unwrap_or_default()),
+                        Some("tool_use") => {
+                            let name = item["name"].as_str().unwrap_or("tool");
+                            push(role, "tool_use", &format!("{} {}", name, item["input"]));
+                        }
+                        Some("tool_result") => push(role, "tool_result", &tool_result_text(&item["content"])),
+                        _ => push(role, "text", item["text"].as_str().unwrap_or_default()),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    turns
+}
+
+/// Tool results are a string or a list of content blocks
+fn tool_result_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|item| item.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// Where Claude Code keeps session files, one directory per project
+pub fn projects_dir() -> Result<PathBuf> {
+    Ok(dirs::home_dir().context("Could not find home directory")?.join(".claude").join("projects"))
+}
+
+pub fn get_current_chat_path() -> Result<PathBuf> {
     // Get project key from current directory
     let current_dir = std::env::current_dir().context("Could not get current directory")?;
     let project_key = current_dir.to_string_lossy().replace("/", "-");
 
     // Look in the project directory for the most recent .jsonl file
-    let project_dir = home.join(".claude").join("projects").join(&project_key);
+    let project_dir = projects_dir()?.join(&project_key);
 
     if !project_dir.exists() {
         bail!(
@@ -71,7 +147,7 @@ fn find_most_recent_jsonl(dir: &PathBuf) -> Result<PathBuf> {
         .with_context(|| format!("Could not read directory: {}", dir.display()))?
         .filter_map(|e| e.ok())
         .filter(|e| {
-            e.path().extension().map_or(false, |ext| ext == "jsonl")
+            e.path().extension().is_some_and(|ext| ext == "jsonl")
         })
         .collect();
 
@@ -109,19 +185,10 @@ pub async fn run(path_only: bool, metadata: Option<String>) -> Result<()> {
 
     let file_path = chat_path.to_string_lossy().to_string();
 
-    // Connect to database and insert directly (to include file_path)
+    // Connect to database and insert
     let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
 
-    let (client, connection) =
-        tokio_postgres::connect(&config.connection_string(), tokio_postgres::NoTls)
-            .await
-            .context("Failed to connect to database")?;
-
-    tokio::spawn(async move {
-        if let Err(e) = connection.await {
-            eprintln!("Database connection error: {}", e);
-        }
-    });
+    let client = db::connect(&config).await?;
 
     // Parse metadata JSON if provided
     let metadata_json: serde_json::Value = match &metadata {
@@ -129,19 +196,9 @@ pub async fn run(path_only: bool, metadata: Option<String>) -> Result<()> {
         None => serde_json::json!({}),
     };
 
-    let row = client
-        .query_one(
-            r#"
-            INSERT INTO entries (entry_type, content, file_path, metadata)
-            VALUES ($1, $2, $3, $4)
-            RETURNING id
-            "#,
-            &[&"chat", &content, &Some(&file_path), &metadata_json],
-        )
+    let id = entries::insert(&client, "chat", &content, Some(&file_path), &metadata_json, None, None)
         .await
         .context("Failed to insert chat entry")?;
-
-    let id: i32 = row.get(0);
     println!("Inserted chat with id: {}", id);
     println!("Chat file: {}", file_path);
 
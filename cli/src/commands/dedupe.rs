@@ -0,0 +1,167 @@
+//! `elastic-claude dedupe`: report groups of near-duplicate entries.
+//!
+//! Entries of the same type are compared by their MinHash fingerprints. Two are
+//! near-duplicates when most of the smaller one's text also occurs in the larger,
+//! which covers both edited copies and a chat saved again after more turns.
+
+use anyhow::{Context, Result};
+use tokio_postgres::Client;
+use crate::config::Config;
+use crate::fingerprint::{self, Fingerprint};
+use crate::db;
+use super::search::Filters;
+
+/// Entries fingerprinted per batch when filling in older entries
+const BACKFILL_BATCH: i64 = 200;
+
+struct Candidate {
+    id: i32,
+    entry_type: String,
+    label: String,
+    created_at: chrono::NaiveDateTime,
+    fingerprint: Fingerprint,
+}
+
+pub async fn run(threshold: f64, filters: Filters) -> Result<()> {
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+    let client = db::connect(&config).await?;
+
+    let filled = backfill(&client).await?;
+    if filled > 0 {
+        println!("Fingerprinted {} older entries\n", filled);
+    }
+
+    let candidates = candidates(&client, &filters).await?;
+    let groups = groups(&candidates, threshold);
+    if groups.is_empty() {
+        println!("No near-duplicates found (threshold {:.2})", threshold);
+        return Ok(());
+    }
+
+    for (n, group) in groups.iter().enumerate() {
+        let keep = group[0];
+        let kept = &candidates[keep];
+
+        println!("Group {} ({} {} entries):", n + 1, group.len(), kept.entry_type);
+        for &i in group {
+            let c = &candidates[i];
+            let similarity = if i == keep {
+                "  keep".to_string()
+            } else {
+                format!("  {:.2}", fingerprint::containment(&c.fingerprint, &kept.fingerprint))
+            };
+            println!("{}  #{:<6} {}  {}", similarity, c.id, c.created_at.format("%Y-%m-%d %H:%M"), c.label);
+        }
+        let drop: Vec<String> = group[1..].iter().map(|&i| candidates[i].id.to_string()).collect();
+        println!("  elastic-claude merge {} {}\n", kept.id, drop.join(" "));
+    }
+    println!("{} groups of near-duplicates", groups.len());
+    Ok(())
+}
+
+/// Fingerprint live entries written before fingerprints existed. Returns how many
+/// were filled in.
+async fn backfill(client: &Client) -> Result<usize> {
+    let mut filled = 0;
+    let mut after = 0;
+    loop {
+        let rows = client
+            .query(
+                r#"
+                SELECT id, content FROM entries
+                WHERE minhash IS NULL AND deleted_at IS NULL AND id > $1
+                ORDER BY id
+                LIMIT $2
+                "#,
+                &[&after, &BACKFILL_BATCH],
+            )
+            .await
+            .context("Failed to query entries without fingerprints")?;
+        let Some(last) = rows.last() else {
+            return Ok(filled);
+        };
+        after = last.get("id");
+
+        for row in &rows {
+            let Some(f) = fingerprint::compute(row.get("content")) else {
+                continue;
+            };
+            client
+                .execute(
+                    "UPDATE entries SET minhash = $2, shingle_count = $3 WHERE id = $1",
+                    &[&row.get::<_, i32>("id"), &f.minhash, &f.shingles],
+                )
+                .await
+                .context("Failed to store fingerprint")?;
+            filled += 1;
+        }
+    }
+}
+
+async fn candidates(client: &Client, filters: &Filters) -> Result<Vec<Candidate>> {
+    let rows = client
+        .query(
+            r#"
+            SELECT id, entry_type, created_at, minhash, shingle_count,
+                   COALESCE(metadata->>'title', file_path, left(regexp_replace(content, '\s+', ' ', 'g'), 60)) AS label
+            FROM entries
+            WHERE minhash IS NOT NULL
+              AND deleted_at IS NULL
+              AND ($1::text IS NULL OR entry_type = $1)
+              AND ($2::text IS NULL OR metadata->>'project' = $2)
+            "#,
+            &[&filters.entry_type, &filters.project],
+        )
+        .await
+        .context("Failed to query fingerprints")?;
+
+    Ok(rows
+        .iter()
+        .map(|row| Candidate {
+            id: row.get("id"),
+            entry_type: row.get("entry_type"),
+            label: row.get("label"),
+            created_at: row.get("created_at"),
+            fingerprint: Fingerprint {
+                minhash: row.get("minhash"),
+                shingles: row.get("shingle_count"),
+            },
+        })
+        .collect())
+}
+
+/// Indexes into `candidates` of each group with two or more members, the entry to
+/// keep first. Within each type the most complete entries (then the newest) take
+/// the remaining ones they are similar to, so every member is compared with the
+/// kept entry directly rather than through a chain of pairs.
+fn groups(candidates: &[Candidate], threshold: f64) -> Vec<Vec<usize>> {
+    let mut order: Vec<usize> = (0..candidates.len()).collect();
+    order.sort_by(|&a, &b| {
+        let (a, b) = (&candidates[a], &candidates[b]);
+        a.entry_type
+            .cmp(&b.entry_type)
+            .then(b.fingerprint.shingles.cmp(&a.fingerprint.shingles))
+            .then(b.id.cmp(&a.id))
+    });
+
+    let mut grouped = vec![false; candidates.len()];
+    let mut groups = Vec::new();
+    for (n, &keep) in order.iter().enumerate() {
+        if grouped[keep] {
+            continue;
+        }
+        let kept = &candidates[keep];
+        let mut group = vec![keep];
+        for &other in order[n + 1..].iter().take_while(|&&o| candidates[o].entry_type == kept.entry_type) {
+            if !grouped[other] && fingerprint::containment(&candidates[other].fingerprint, &kept.fingerprint) >= threshold {
+                grouped[other] = true;
+                group.push(other);
+            }
+        }
+        if group.len() > 1 {
+            group[1..].sort_by_key(|&i| candidates[i].id);
+            groups.push(group);
+        }
+    }
+    groups
+}
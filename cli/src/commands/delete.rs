@@ -0,0 +1,23 @@
+use anyhow::{Context, Result};
+use crate::config::Config;
+use crate::{db, entries};
+
+/// Move entries to the trash; `trash restore` brings them back.
+pub async fn run(ids: Vec<i32>) -> Result<()> {
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+    let client = db::connect(&config).await?;
+
+    let trashed = entries::trash(&client, &ids).await?;
+    for id in ids.iter().filter(|id| !trashed.contains(id)) {
+        println!("Entry {} not found or already in trash", id);
+    }
+    if !trashed.is_empty() {
+        let list: Vec<String> = trashed.iter().map(|id| id.to_string()).collect();
+        println!(
+            "✓ Moved {} entries to trash (restore with: elastic-claude trash restore {})",
+            trashed.len(),
+            list.join(" ")
+        );
+    }
+    Ok(())
+}
@@ -1,9 +1,10 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::io::{self, Write};
 use crate::config::Config;
 use crate::docker;
+use super::backup;
 
-pub async fn run(include_data: bool) -> Result<()> {
+pub async fn run(include_data: bool, no_backup: bool) -> Result<()> {
     let docker_client = docker::connect().await?;
     let status = docker::get_container_status(&docker_client).await?;
 
@@ -18,6 +19,14 @@ pub async fn run(include_data: bool) -> Result<()> {
         print!(" and all data");
     }
     println!(".");
+    let backup = include_data && !no_backup;
+    let config = Config::load().unwrap_or_default();
+    if backup {
+        println!(
+            "A final backup will be written to {} first (skip with --no-backup).",
+            config.backup_dir()?.display()
+        );
+    }
     print!("Are you sure? [y/N] ");
     io::stdout().flush()?;
 
@@ -29,20 +38,38 @@ pub async fn run(include_data: bool) -> Result<()> {
         return Ok(());
     }
 
+    // Take a final backup while the data still exists
+    if backup {
+        if status != docker::ContainerStatus::Running {
+            println!("Starting container for final backup...");
+            docker::start_container(&docker_client).await?;
+            docker::wait_for_postgres(&config).await?;
+        }
+        let path = backup::create_backup(&docker_client, &config)
+            .await
+            .context("Final backup failed; nothing was removed. Use --no-backup to skip it.")?;
+        println!("Backup written to {}", path.display());
+    }
+
     // Stop if running
-    if status == docker::ContainerStatus::Running {
+    if status == docker::ContainerStatus::Running || backup {
         println!("Stopping container...");
         docker::stop_container(&docker_client).await?;
     }
 
+    // Look up the data volume before the container that mounts it is gone
+    let volume = docker::mounted_volume(&docker_client)
+        .await?
+        .unwrap_or_else(|| docker::volume_name(docker::POSTGRES_MAJOR));
+
     // Remove container
     println!("Removing container...");
     docker::remove_container(&docker_client).await?;
 
     // Remove volume if requested
     if include_data {
-        println!("Removing data volume...");
-        docker::remove_volume(&docker_client).await?;
+        println!("Removing data volume {}...", volume);
+        docker::remove_volume(&docker_client, &volume).await?;
     }
 
     // Remove config file
@@ -0,0 +1,41 @@
+use anyhow::{bail, Context, Result};
+use similar::TextDiff;
+use crate::config::Config;
+use crate::{db, versions};
+
+/// Unified diff of an entry between version `from` and `to`. Without versions the
+/// current content is compared with the previous version.
+pub async fn run(id: i32, from: Option<i32>, to: Option<i32>) -> Result<()> {
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+    let client = db::connect(&config).await?;
+
+    let history = versions::list(&client, id).await?;
+    let Some(current) = history.first() else {
+        bail!("Entry {} not found", id);
+    };
+    let to = to.unwrap_or(current.version);
+    let from = from.unwrap_or(to - 1);
+    if from < 1 {
+        bail!("Entry {} has no earlier version to compare with", id);
+    }
+
+    let find = |version: i32| {
+        history
+            .iter()
+            .find(|v| v.version == version)
+            .with_context(|| format!("Entry {} has no version {} (see: elastic-claude get {} --history)", id, version, id))
+    };
+    let (old, new) = (find(from)?, find(to)?);
+
+    let diff = TextDiff::from_lines(&old.content, &new.content);
+    let old_label = format!("#{} v{}", id, old.version);
+    let new_label = format!("#{} v{}{}", id, new.version, if new.current { " (current)" } else { "" });
+    let unified = diff.unified_diff().context_radius(3).header(&old_label, &new_label).to_string();
+
+    if unified.is_empty() {
+        println!("No content changes between v{} and v{}", old.version, new.version);
+    } else {
+        print!("{}", unified);
+    }
+    Ok(())
+}
@@ -0,0 +1,483 @@
+use anyhow::{bail, Result};
+use bollard::Docker;
+use std::time::Duration;
+use tokio_postgres::Client;
+use crate::config::{Config, DatabaseConfig};
+use crate::{db, docker};
+use super::init::{SCHEMA_MD, SKILL_MD};
+
+/// GIN indexes that search and metadata filtering depend on: (index name, column)
+const REQUIRED_INDEXES: &[(&str, &str)] = &[
+    ("idx_content_tsv", "content_tsv"),
+    ("idx_metadata", "metadata"),
+];
+
+enum Outcome {
+    Pass(String),
+    Warn(String, String),
+    Fail(String, String),
+}
+
+#[derive(Default)]
+struct Report {
+    failures: usize,
+    warnings: usize,
+}
+
+impl Report {
+    fn record(&mut self, name: &str, outcome: Outcome) {
+        match outcome {
+            Outcome::Pass(detail) => println!("✓ {}: {}", name, detail),
+            Outcome::Warn(detail, fix) => {
+                self.warnings += 1;
+                println!("! {}: {}", name, detail);
+                println!("    Fix: {}", fix);
+            }
+            Outcome::Fail(detail, fix) => {
+                self.failures += 1;
+                println!("✗ {}: {}", name, detail);
+                println!("    Fix: {}", fix);
+            }
+        }
+    }
+
+    fn skip(&self, name: &str, reason: &str) {
+        println!("- {}: skipped ({})", name, reason);
+    }
+}
+
+pub async fn run() -> Result<()> {
+    let mut report = Report::default();
+
+    // Config is checked first because the port and DB checks depend on it
+    let (config, outcome) = check_config();
+    report.record("Config", outcome);
+    let config = config.unwrap_or_default();
+
+    let (docker_client, outcome) = check_docker().await;
+    report.record("Docker", outcome);
+
+    let mut running = false;
+    match &docker_client {
+        Some(client) => {
+            let (is_running, outcome) = check_container(client).await;
+            running = is_running;
+            report.record("Container", outcome);
+
+            match check_port(client, &config, running).await {
+                Some(outcome) => report.record("Port", outcome),
+                None => report.skip("Port", "container not found"),
+            }
+        }
+        None => {
+            report.skip("Container", "Docker unavailable");
+            report.skip("Port", "Docker unavailable");
+        }
+    }
+
+    let client = if running || docker_client.is_none() {
+        let (client, outcome) = check_database(&config).await;
+        report.record("Database", outcome);
+        client
+    } else {
+        report.skip("Database", "container not running");
+        None
+    };
+
+    match &client {
+        Some(client) => {
+            report.record("Schema", check_schema(client).await);
+            for outcome in check_indexes(client, &config.database).await {
+                report.record("Index", outcome);
+            }
+        }
+        None => {
+            report.skip("Schema", "database unavailable");
+            report.skip("Index", "database unavailable");
+        }
+    }
+
+    report.record("Skill", check_skill());
+    report.record("Claude projects", check_claude_projects());
+
+    println!();
+    if report.failures > 0 {
+        bail!(
+            "{} check(s) failed, {} warning(s). Apply the fixes above and re-run 'elastic-claude doctor'.",
+            report.failures,
+            report.warnings
+        );
+    }
+
+    if report.warnings > 0 {
+        println!("All checks passed with {} warning(s).", report.warnings);
+    } else {
+        println!("All checks passed.");
+    }
+
+    Ok(())
+}
+
+fn check_config() -> (Option<Config>, Outcome) {
+    let path = match Config::config_path() {
+        Ok(path) => path,
+        Err(e) => {
+            return (
+                None,
+                Outcome::Fail(e.to_string(), "Set the HOME environment variable.".to_string()),
+            )
+        }
+    };
+
+    if !path.exists() {
+        return (
+            None,
+            Outcome::Fail(
+                format!("{} not found", path.display()),
+                "Run 'elastic-claude init' to create it.".to_string(),
+            ),
+        );
+    }
+
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            return (
+                None,
+                Outcome::Fail(
+                    format!("{:#}", e),
+                    format!(
+                        "Fix the YAML in {} or delete it and run 'elastic-claude init'.",
+                        path.display()
+                    ),
+                ),
+            )
+        }
+    };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(meta) = std::fs::metadata(&path) {
+            let mode = meta.permissions().mode() & 0o777;
+            if mode & 0o077 != 0 {
+                return (
+                    Some(config),
+                    Outcome::Warn(
+                        format!("{} is accessible by other users (mode {:o})", path.display(), mode),
+                        format!("chmod 600 {}", path.display()),
+                    ),
+                );
+            }
+        }
+    }
+
+    (Some(config), Outcome::Pass(format!("{}", path.display())))
+}
+
+async fn check_docker() -> (Option<Docker>, Outcome) {
+    let fix = "Start Docker (e.g. 'sudo systemctl start docker' or launch Docker Desktop) \
+               and make sure your user can access the Docker socket."
+        .to_string();
+
+    let client = match docker::connect().await {
+        Ok(client) => client,
+        Err(e) => return (None, Outcome::Fail(format!("{:#}", e).replace('\n', " "), fix)),
+    };
+
+    match client.version().await {
+        Ok(version) => {
+            let detail = format!(
+                "connected (Docker {})",
+                version.version.as_deref().unwrap_or("unknown version")
+            );
+            (Some(client), Outcome::Pass(detail))
+        }
+        Err(e) => (None, Outcome::Fail(format!("socket not reachable: {}", e), fix)),
+    }
+}
+
+async fn check_container(client: &Docker) -> (bool, Outcome) {
+    let info = match client.inspect_container(docker::CONTAINER_NAME, None).await {
+        Ok(info) => info,
+        Err(_) => {
+            return (
+                false,
+                Outcome::Fail(
+                    format!("{} not found", docker::CONTAINER_NAME),
+                    "Run 'elastic-claude init' to create it.".to_string(),
+                ),
+            )
+        }
+    };
+
+    let state = info.state.unwrap_or_default();
+    let status = state
+        .status
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    if state.running != Some(true) {
+        let mut detail = status;
+        if let Some(code) = state.exit_code.filter(|c| *c != 0) {
+            detail.push_str(&format!(" (exit code {})", code));
+        }
+        if state.oom_killed == Some(true) {
+            detail.push_str(", killed by OOM");
+        }
+        return (
+            false,
+            Outcome::Fail(
+                detail,
+                format!(
+                    "Run 'elastic-claude start'. If it keeps stopping, inspect 'docker logs {}'.",
+                    docker::CONTAINER_NAME
+                ),
+            ),
+        );
+    }
+
+    let health = state.health.and_then(|h| h.status);
+    match health {
+        Some(bollard::service::HealthStatusEnum::UNHEALTHY) => (
+            true,
+            Outcome::Fail(
+                "running but unhealthy".to_string(),
+                format!(
+                    "Inspect 'docker logs {}' and restart with 'elastic-claude stop && elastic-claude start'.",
+                    docker::CONTAINER_NAME
+                ),
+            ),
+        ),
+        Some(h @ bollard::service::HealthStatusEnum::STARTING)
+        | Some(h @ bollard::service::HealthStatusEnum::HEALTHY) => {
+            (true, Outcome::Pass(format!("running ({})", h)))
+        }
+        _ => (true, Outcome::Pass("running".to_string())),
+    }
+}
+
+/// Returns `None` when the container does not exist.
+async fn check_port(client: &Docker, config: &Config, running: bool) -> Option<Outcome> {
+    let info = client
+        .inspect_container(docker::CONTAINER_NAME, None)
+        .await
+        .ok()?;
+
+    // Live bindings are only reported for running containers
+    let bindings = if running {
+        info.network_settings.and_then(|n| n.ports)
+    } else {
+        info.host_config.and_then(|h| h.port_bindings)
+    };
+
+    let host_ports: Vec<String> = bindings
+        .and_then(|b| b.get("5432/tcp").cloned().flatten())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|b| b.host_port)
+        .collect();
+
+    let expected = config.database.port.to_string();
+    if !host_ports.contains(&expected) {
+        let bound = if host_ports.is_empty() {
+            "not published".to_string()
+        } else {
+            host_ports.join(", ")
+        };
+        return Some(Outcome::Fail(
+            format!("container port 5432 is {}, config expects {}", bound, expected),
+            format!(
+                "Set database.port in {} to the published port, or recreate the container \
+                 with 'elastic-claude destroy && elastic-claude init' (data is kept).",
+                Config::config_path().map(|p| p.display().to_string()).unwrap_or_default()
+            ),
+        ));
+    }
+
+    if running {
+        let addr = format!("{}:{}", config.database.host, config.database.port);
+        let reachable = tokio::time::timeout(
+            Duration::from_secs(3),
+            tokio::net::TcpStream::connect(&addr),
+        )
+        .await;
+        if !matches!(reachable, Ok(Ok(_))) {
+            return Some(Outcome::Fail(
+                format!("{} is not accepting connections", addr),
+                format!(
+                    "Check that nothing else is bound to port {} and that database.host is correct.",
+                    config.database.port
+                ),
+            ));
+        }
+    }
+
+    Some(Outcome::Pass(format!("5432/tcp published on {}", expected)))
+}
+
+async fn check_database(config: &Config) -> (Option<Client>, Outcome) {
+    let fix = format!(
+        "Check the credentials in {} and the logs from 'docker logs {}'.",
+        Config::config_path().map(|p| p.display().to_string()).unwrap_or_default(),
+        docker::CONTAINER_NAME
+    );
+
+    match tokio::time::timeout(Duration::from_secs(5), db::connect(config)).await {
+        Ok(Ok(client)) => {
+            let detail = format!(
+                "connected to {} @ {}:{}",
+                config.database.name, config.database.host, config.database.port
+            );
+            (Some(client), Outcome::Pass(detail))
+        }
+        Ok(Err(e)) => (None, Outcome::Fail(format!("{:#}", e), fix)),
+        Err(_) => (None, Outcome::Fail("connection timed out".to_string(), fix)),
+    }
+}
+
+async fn check_schema(client: &Client) -> Outcome {
+    let latest = db::latest_version();
+    match db::schema_version(client).await {
+        Ok(Some(version)) if version == latest => Outcome::Pass(format!("version {}", version)),
+        Ok(Some(version)) if version < latest => Outcome::Fail(
+            format!("version {}, this binary expects {}", version, latest),
+            "Run 'elastic-claude init' to apply pending migrations.".to_string(),
+        ),
+        Ok(Some(version)) => Outcome::Warn(
+            format!("version {} is newer than this binary supports ({})", version, latest),
+            "Upgrade elastic-claude to the latest release.".to_string(),
+        ),
+        Ok(None) => Outcome::Fail(
+            "entries table not found".to_string(),
+            "Run 'elastic-claude init' to create the schema.".to_string(),
+        ),
+        Err(e) => Outcome::Fail(
+            format!("could not read schema version: {}", e),
+            "Run 'elastic-claude init' to apply migrations.".to_string(),
+        ),
+    }
+}
+
+async fn check_indexes(client: &Client, database: &DatabaseConfig) -> Vec<Outcome> {
+    let rows = match client
+        .query(
+            r#"
+            SELECT c.relname, am.amname, i.indisvalid AND i.indisready, a.attname
+            FROM pg_index i
+            JOIN pg_class c ON c.oid = i.indexrelid
+            JOIN pg_am am ON am.oid = c.relam
+            LEFT JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = i.indkey[0]
+            WHERE i.indrelid = to_regclass('entries')
+            "#,
+            &[],
+        )
+        .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            return vec![Outcome::Fail(
+                format!("could not inspect indexes: {}", e),
+                "Run 'elastic-claude init' to create the schema.".to_string(),
+            )]
+        }
+    };
+
+    REQUIRED_INDEXES
+        .iter()
+        .map(|(name, column)| {
+            let index = rows.iter().find(|row| {
+                let method: &str = row.get(1);
+                let attname: Option<&str> = row.get(3);
+                method == "gin" && attname == Some(*column)
+            });
+
+            match index {
+                None => Outcome::Fail(
+                    format!("no GIN index on entries.{}", column),
+                    psql_fix(
+                        database,
+                        &format!("CREATE INDEX {} ON entries USING GIN({});", name, column),
+                    ),
+                ),
+                Some(row) => {
+                    let relname: &str = row.get(0);
+                    let valid: bool = row.get(2);
+                    if valid {
+                        Outcome::Pass(format!("{} (GIN on {})", relname, column))
+                    } else {
+                        Outcome::Fail(
+                            format!("{} is invalid", relname),
+                            psql_fix(database, &format!("REINDEX INDEX {};", relname)),
+                        )
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+/// A command running `sql` with psql inside the container, as the configured user
+/// on the configured database
+fn psql_fix(database: &DatabaseConfig, sql: &str) -> String {
+    format!(
+        "docker exec {} psql -U {} -d {} -c \"{}\"",
+        docker::CONTAINER_NAME,
+        database.user,
+        database.name,
+        sql
+    )
+}
+
+fn check_skill() -> Outcome {
+    let skill_dir = match Config::skill_dir() {
+        Ok(dir) => dir,
+        Err(e) => return Outcome::Fail(e.to_string(), "Set the HOME environment variable.".to_string()),
+    };
+
+    let fix = "Run 'elastic-claude init' to reinstall the skill.".to_string();
+    let files = [
+        (skill_dir.join("SKILL.md"), SKILL_MD),
+        (skill_dir.join("references").join("schema.md"), SCHEMA_MD),
+    ];
+
+    for (path, embedded) in &files {
+        match std::fs::read_to_string(path) {
+            Err(_) => return Outcome::Fail(format!("{} not installed", path.display()), fix),
+            Ok(installed) if installed != *embedded => {
+                return Outcome::Warn(
+                    format!("{} differs from this binary's version", path.display()),
+                    fix,
+                )
+            }
+            Ok(_) => {}
+        }
+    }
+
+    Outcome::Pass(format!("up to date ({})", skill_dir.display()))
+}
+
+fn check_claude_projects() -> Outcome {
+    let Some(home) = dirs::home_dir() else {
+        return Outcome::Fail(
+            "could not find home directory".to_string(),
+            "Set the HOME environment variable.".to_string(),
+        );
+    };
+
+    let projects_dir = home.join(".claude").join("projects");
+    if !projects_dir.is_dir() {
+        return Outcome::Warn(
+            format!("{} not found", projects_dir.display()),
+            "Run Claude Code at least once so it creates its project directory.".to_string(),
+        );
+    }
+
+    match super::current_chat::get_current_chat_path() {
+        Ok(path) => Outcome::Pass(format!("current session {}", path.display())),
+        Err(e) => Outcome::Warn(
+            e.to_string().replace('\n', "; "),
+            "Start a Claude Code session in this directory before using 'current-chat'.".to_string(),
+        ),
+    }
+}
@@ -0,0 +1,314 @@
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDateTime;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use tokio_postgres::Client;
+use crate::config::{Config, RetentionRule};
+use crate::{db, entries, versions};
+use super::{search, trash};
+
+/// Entries listed per section before the rest are summarised
+const MAX_LISTED: usize = 20;
+
+/// Apply the retention rules: entries matching a rule go to the trash, entries
+/// trashed longer than `retention.trash_days` are purged, versions beyond
+/// `versions.keep` are pruned and unreferenced blob store files are deleted.
+/// Without `apply`, only report what would happen.
+pub async fn run(apply: bool) -> Result<()> {
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+    let mut client = db::connect(&config).await?;
+
+    let mut to_trash: Vec<i32> = Vec::new();
+    if !config.retention.rules.is_empty() {
+        let (now, live) = candidates(&client).await?;
+        for (i, rule) in config.retention.rules.iter().enumerate() {
+            let selected = select(rule, &live, now).with_context(|| format!("Retention rule {} failed", i + 1))?;
+            println!("Rule {} ({}): {} entries", i + 1, describe(rule), selected.len());
+            let listed: Vec<_> = selected
+                .iter()
+                .map(|c| (c.id, c.entry_type.as_str(), c.created_at, c.label.as_str()))
+                .collect();
+            print_entries(&listed);
+            to_trash.extend(selected.iter().map(|c| c.id));
+        }
+    }
+    if config.retention.rules.is_empty() {
+        println!("No retention rules configured (retention.rules in {})", Config::config_path()?.display());
+    }
+    to_trash.sort_unstable();
+    to_trash.dedup();
+
+    let to_purge: Vec<i32> = match config.retention.trash_days {
+        Some(days) => {
+            let rows = trash::trashed(&client, Some(days)).await?;
+            println!("\nTrash: {} entries deleted more than {} days ago", rows.len(), days);
+            let listed: Vec<_> = rows
+                .iter()
+                .map(|r| (r.get("id"), r.get("entry_type"), r.get("deleted_at"), r.get("label")))
+                .collect();
+            print_entries(&listed);
+            rows.iter().map(|r| r.get("id")).collect()
+        }
+        None => Vec::new(),
+    };
+
+    let keep = config.versions.keep;
+    let old_versions = versions::excess(&client, keep).await?;
+    if old_versions > 0 {
+        println!("\nVersions: {} beyond versions.keep ({})", old_versions, keep);
+    }
+
+    let orphans = orphaned_blobs(&config, &client, &to_purge).await?;
+    if !orphans.is_empty() {
+        let size: u64 = orphans.iter().filter_map(|p| p.metadata().ok()).map(|m| m.len()).sum();
+        println!("\nBlob store: {} unreferenced files ({} bytes)", orphans.len(), size);
+    }
+
+    if !apply {
+        println!("\nNothing changed (dry run). Run 'elastic-claude gc --apply' to apply.");
+        return Ok(());
+    }
+
+    // All or nothing: a failing step leaves the entries as they were
+    let tx = client.transaction().await?;
+    let trashed = entries::trash(&tx, &to_trash).await?;
+    let purged = entries::purge(&tx, &to_purge).await?;
+    let pruned = versions::prune(&tx, None, keep).await?;
+    tx.commit().await.context("Failed to commit gc")?;
+    for path in &orphans {
+        std::fs::remove_file(path).with_context(|| format!("Failed to remove {}", path.display()))?;
+    }
+    search::refresh_vocabulary(&client).await?;
+    println!(
+        "\n✓ {} moved to trash, {} purged, {} versions pruned, {} blobs removed",
+        trashed.len(),
+        purged,
+        pruned,
+        orphans.len()
+    );
+    Ok(())
+}
+
+/// A live entry retention rules are evaluated against
+struct Candidate {
+    id: i32,
+    entry_type: String,
+    project: Option<String>,
+    created_at: NaiveDateTime,
+    pinned: bool,
+    label: String,
+}
+
+/// The database's current time and every live entry with a creation time.
+async fn candidates(client: &Client) -> Result<(NaiveDateTime, Vec<Candidate>)> {
+    let now = client.query_one("SELECT LOCALTIMESTAMP", &[]).await?.get(0);
+    let rows = client
+        .query(
+            r#"
+            SELECT id, entry_type, metadata->>'project' AS project, created_at,
+                   COALESCE(metadata @> '{"pinned": true}', false) AS pinned,
+                   COALESCE(metadata->>'title', file_path, left(regexp_replace(content, '\s+', ' ', 'g'), 60)) AS label
+            FROM entries
+            WHERE deleted_at IS NULL AND created_at IS NOT NULL
+            "#,
+            &[],
+        )
+        .await
+        .context("Failed to query entries")?;
+    let candidates = rows
+        .iter()
+        .map(|row| Candidate {
+            id: row.get("id"),
+            entry_type: row.get("entry_type"),
+            project: row.get("project"),
+            created_at: row.get("created_at"),
+            pinned: row.get("pinned"),
+            label: row.get("label"),
+        })
+        .collect();
+    Ok((now, candidates))
+}
+
+/// The unpinned entries of the rule's type and project that it removes, oldest
+/// first: older than `older_than_days` and/or beyond the newest `keep_last` of
+/// their project. With both set, an entry must meet both.
+fn select<'a>(rule: &RetentionRule, candidates: &'a [Candidate], now: NaiveDateTime) -> Result<Vec<&'a Candidate>> {
+    if rule.older_than_days.is_none() && rule.keep_last.is_none() {
+        bail!("Set older_than_days and/or keep_last");
+    }
+    let mut matching: Vec<&Candidate> = candidates
+        .iter()
+        .filter(|c| !c.pinned)
+        .filter(|c| rule.entry_type.as_ref().is_none_or(|t| *t == c.entry_type))
+        .filter(|c| rule.project.is_none() || c.project == rule.project)
+        .collect();
+
+    // Newest first, so the position within a project is its rank
+    matching.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+    let mut seen: HashMap<Option<&str>, u32> = HashMap::new();
+    let cutoff = rule.older_than_days.map(|days| now - chrono::Duration::days(days.into()));
+    let mut selected: Vec<&Candidate> = matching
+        .into_iter()
+        .filter(|c| {
+            let rank = seen.entry(c.project.as_deref()).or_default();
+            *rank += 1;
+            cutoff.is_none_or(|cutoff| c.created_at < cutoff) && rule.keep_last.is_none_or(|keep| *rank > keep)
+        })
+        .collect();
+    selected.sort_by_key(|c| (c.created_at, c.id));
+    Ok(selected)
+}
+
+fn describe(rule: &RetentionRule) -> String {
+    let mut parts = vec![format!("type {}", rule.entry_type.as_deref().unwrap_or("any"))];
+    if let Some(project) = &rule.project {
+        parts.push(format!("project {}", project));
+    }
+    if let Some(days) = rule.older_than_days {
+        parts.push(format!("older than {} days", days));
+    }
+    if let Some(keep) = rule.keep_last {
+        parts.push(format!("beyond newest {} per project", keep));
+    }
+    parts.join(", ")
+}
+
+/// One line per (id, type, date, label), listing at most MAX_LISTED
+fn print_entries(entries: &[(i32, &str, NaiveDateTime, &str)]) {
+    for (id, entry_type, time, label) in entries.iter().take(MAX_LISTED) {
+        println!("  #{:<6} {:<10} {}  {}", id, entry_type, time.format("%Y-%m-%d"), label);
+    }
+    if entries.len() > MAX_LISTED {
+        println!("  ... and {} more", entries.len() - MAX_LISTED);
+    }
+}
+
+/// Blob store files no entry refers to once `purging` entries are gone.
+async fn orphaned_blobs(config: &Config, client: &Client, purging: &[i32]) -> Result<Vec<PathBuf>> {
+    let dir = config.blob_store_dir()?;
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let referenced: HashSet<String> = client
+        .query(
+            "SELECT DISTINCT blob_sha256 FROM entries WHERE blob_sha256 IS NOT NULL AND NOT id = ANY($1)",
+            &[&purging],
+        )
+        .await
+        .context("Failed to query blob references")?
+        .iter()
+        .map(|r| r.get(0))
+        .collect();
+
+    let mut orphans = Vec::new();
+    for prefix in std::fs::read_dir(&dir)?.filter_map(|e| e.ok()) {
+        if !prefix.path().is_dir() {
+            continue;
+        }
+        for file in std::fs::read_dir(prefix.path())?.filter_map(|e| e.ok()) {
+            let name = file.file_name().to_string_lossy().to_string();
+            // Interrupted writes (`.partial`) are left for the next store of that blob
+            if name.len() == 64 && !referenced.contains(&name) {
+                orphans.push(file.path());
+            }
+        }
+    }
+    orphans.sort();
+    Ok(orphans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2025, 6, 30).unwrap().and_hms_opt(12, 0, 0).unwrap()
+    }
+
+    /// An entry of `project` created `age_days` ago
+    fn entry(id: i32, entry_type: &str, project: Option<&str>, age_days: i64) -> Candidate {
+        Candidate {
+            id,
+            entry_type: entry_type.to_string(),
+            project: project.map(str::to_string),
+            created_at: now() - chrono::Duration::days(age_days),
+            pinned: false,
+            label: format!("entry {}", id),
+        }
+    }
+
+    fn rule(entry_type: Option<&str>, older_than_days: Option<u32>, keep_last: Option<u32>) -> RetentionRule {
+        RetentionRule {
+            entry_type: entry_type.map(str::to_string),
+            project: None,
+            older_than_days,
+            keep_last,
+        }
+    }
+
+    fn selected(rule: &RetentionRule, candidates: &[Candidate]) -> Vec<i32> {
+        select(rule, candidates, now()).unwrap().iter().map(|c| c.id).collect()
+    }
+
+    #[test]
+    fn rule_needs_a_limit() {
+        assert!(select(&rule(Some("chat"), None, None), &[], now()).is_err());
+    }
+
+    #[test]
+    fn only_the_rule_type_and_project_are_selected() {
+        let candidates = [
+            entry(1, "chat", Some("web"), 100),
+            entry(2, "document", Some("web"), 100),
+            entry(3, "chat", Some("api"), 100),
+        ];
+        assert_eq!(selected(&rule(Some("chat"), Some(30), None), &candidates), [1, 3]);
+        let web = RetentionRule { project: Some("web".to_string()), ..rule(None, Some(30), None) };
+        assert_eq!(selected(&web, &candidates), [1, 2]);
+    }
+
+    #[test]
+    fn both_limits_must_hold() {
+        // Newest first: 1 (5 days), 2 (40 days), 3 (50 days), 4 (60 days)
+        let candidates = [
+            entry(1, "chat", None, 5),
+            entry(2, "chat", None, 40),
+            entry(3, "chat", None, 50),
+            entry(4, "chat", None, 60),
+        ];
+        assert_eq!(selected(&rule(None, Some(30), None), &candidates), [4, 3, 2]);
+        assert_eq!(selected(&rule(None, None, Some(2)), &candidates), [4, 3]);
+        // Old enough but among the newest two: 2 stays
+        assert_eq!(selected(&rule(None, Some(30), Some(2)), &candidates), [4, 3]);
+        // 2 is beyond the newest one but not 45 days old
+        assert_eq!(selected(&rule(None, Some(45), Some(1)), &candidates), [4, 3]);
+    }
+
+    #[test]
+    fn pinned_entries_are_kept_and_do_not_count_towards_keep_last() {
+        let mut candidates = [
+            entry(1, "chat", None, 1),
+            entry(2, "chat", None, 2),
+            entry(3, "chat", None, 3),
+        ];
+        candidates[0].pinned = true;
+        assert_eq!(selected(&rule(None, Some(0), None), &candidates), [3, 2]);
+        assert_eq!(selected(&rule(None, None, Some(1)), &candidates), [3]);
+    }
+
+    #[test]
+    fn keep_last_applies_per_project() {
+        let candidates = [
+            entry(1, "chat", Some("web"), 1),
+            entry(2, "chat", Some("web"), 2),
+            entry(3, "chat", Some("web"), 3),
+            entry(4, "chat", Some("api"), 4),
+            entry(5, "chat", Some("api"), 5),
+            entry(6, "chat", None, 6),
+            entry(7, "chat", None, 7),
+        ];
+        assert_eq!(selected(&rule(None, None, Some(1)), &candidates), [7, 5, 3, 2]);
+        assert_eq!(selected(&rule(None, None, Some(2)), &candidates), [3]);
+    }
+}
@@ -1,76 +1,264 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use tokio_postgres::Client;
 use crate::config::Config;
+use crate::{blobs, db, links, versions};
 
-pub async fn run(id: i32, content_only: bool, show_tsv: bool) -> Result<()> {
+pub struct GetOptions {
+    pub id: i32,
+    pub content_only: bool,
+    pub show_tsv: bool,
+    pub blob: bool,
+    pub output: Option<String>,
+    pub related: bool,
+    pub depth: i32,
+    pub history: bool,
+    pub version: Option<i32>,
+}
+
+pub async fn run(options: GetOptions) -> Result<()> {
+    let GetOptions { id, content_only, show_tsv, blob, output, related, depth, history, version } = options;
     let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
 
-    let (client, connection) =
-        tokio_postgres::connect(&config.connection_string(), tokio_postgres::NoTls)
-            .await
-            .context("Failed to connect to database")?;
+    let client = db::connect(&config).await?;
 
-    tokio::spawn(async move {
-        if let Err(e) = connection.await {
-            eprintln!("Database connection error: {}", e);
-        }
-    });
-
-    let query = if show_tsv {
-        r#"
-        SELECT id, entry_type, content, content_tsv::text, file_path, metadata, created_at
-        FROM entries
-        WHERE id = $1
-        "#
-    } else {
-        r#"
-        SELECT id, entry_type, content, NULL::text as content_tsv, file_path, metadata, created_at
-        FROM entries
-        WHERE id = $1
-        "#
+    if blob {
+        return write_blob(&config, &client, id, output.as_deref()).await;
+    }
+    if related {
+        return print_related(&client, id, depth).await;
+    }
+    if history {
+        return print_history(&client, id).await;
+    }
+    if let Some(version) = version {
+        return print_version(&client, id, version, content_only).await;
+    }
+
+    let Some(entry) = fetch(&client, id).await? else {
+        println!("Entry {} not found", id);
+        return Ok(());
     };
 
+    if content_only {
+        println!("{}", entry.content);
+        return Ok(());
+    }
+
+    println!("=== Entry {} ===", id);
+    println!("Type: {}", entry.entry_type);
+    if let Some(path) = &entry.file_path {
+        println!("File: {}", path);
+    }
+    println!("Language: {}", entry.language);
+    println!("Created: {}", entry.created_at);
+    if let Some(deleted_at) = entry.deleted_at {
+        println!("Deleted: {} (in trash; restore with: elastic-claude trash restore {})", deleted_at, id);
+    }
+    print_blob(&entry);
+    println!("Metadata: {}", serde_json::to_string_pretty(&entry.metadata)?);
+
+    if show_tsv {
+        let row = client
+            .query_one("SELECT content_tsv::text FROM entries WHERE id = $1", &[&id])
+            .await
+            .context("Failed to query entry")?;
+        let tsv: Option<&str> = row.get(0);
+        if let Some(tsv_text) = tsv {
+            println!("\n--- TSVector ---");
+            println!("{}", tsv_text);
+        }
+    }
+
+    println!("\n--- Content ---");
+    println!("{}", entry.content);
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+pub struct Entry {
+    pub id: i32,
+    pub entry_type: String,
+    pub content: String,
+    pub file_path: Option<String>,
+    pub metadata: serde_json::Value,
+    pub source_id: Option<i32>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: Option<chrono::NaiveDateTime>,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+    pub mime_type: Option<String>,
+    /// Size of the blob kept in the database
+    pub blob_size: Option<i32>,
+    /// Hash of a blob kept in the blob store
+    pub blob_sha256: Option<String>,
+    /// Text search configuration the content is indexed with
+    pub language: String,
+}
+
+/// An entry by id, including trashed ones. Used by the CLI and `serve`.
+pub async fn fetch(client: &Client, id: i32) -> Result<Option<Entry>> {
     let row = client
-        .query_opt(query, &[&id])
+        .query_opt(
+            r#"
+            SELECT id, entry_type, content, file_path, metadata, source_id, created_at, updated_at,
+                   deleted_at, mime_type, length(blob) AS blob_size, blob_sha256, language::text AS language
+            FROM entries
+            WHERE id = $1
+            "#,
+            &[&id],
+        )
         .await
         .context("Failed to query entry")?;
 
-    match row {
-        None => {
-            println!("Entry {} not found", id);
-        }
-        Some(row) => {
-            let content: &str = row.get("content");
+    Ok(row.map(|row| Entry {
+        id: row.get("id"),
+        entry_type: row.get("entry_type"),
+        content: row.get("content"),
+        file_path: row.get("file_path"),
+        metadata: row.get::<_, Option<serde_json::Value>>("metadata").unwrap_or_default(),
+        source_id: row.get("source_id"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+        deleted_at: row.get("deleted_at"),
+        mime_type: row.get("mime_type"),
+        blob_size: row.get("blob_size"),
+        blob_sha256: row.get("blob_sha256"),
+        language: row.get("language"),
+    }))
+}
 
-            if content_only {
-                println!("{}", content);
-                return Ok(());
-            }
+/// Linked entries, nearest first, with the link each was reached through.
+async fn print_related(client: &Client, id: i32, depth: i32) -> Result<()> {
+    let related = links::related(client, id, depth).await?;
+    if related.is_empty() {
+        println!("Entry {} has no linked entries", id);
+        return Ok(());
+    }
 
-            let entry_type: &str = row.get("entry_type");
-            let file_path: Option<&str> = row.get("file_path");
-            let metadata: serde_json::Value = row.get("metadata");
-            let created_at: chrono::NaiveDateTime = row.get("created_at");
+    println!("=== Entries related to {} (depth {}) ===", id, depth.clamp(1, links::MAX_DEPTH));
+    for entry in related {
+        let link = if entry.outgoing {
+            format!("#{} {} #{}", entry.via, entry.link_type, entry.id)
+        } else {
+            format!("#{} {} #{}", entry.id, entry.link_type, entry.via)
+        };
+        println!(
+            "{}#{} [{}] {}  ({})",
+            "  ".repeat(entry.depth as usize),
+            entry.id,
+            entry.entry_type,
+            entry.label,
+            link
+        );
+    }
+    Ok(())
+}
 
-            println!("=== Entry {} ===", id);
-            println!("Type: {}", entry_type);
-            if let Some(path) = file_path {
-                println!("File: {}", path);
-            }
-            println!("Created: {}", created_at);
-            println!("Metadata: {}", serde_json::to_string_pretty(&metadata)?);
-
-            if show_tsv {
-                let tsv: Option<&str> = row.get("content_tsv");
-                if let Some(tsv_text) = tsv {
-                    println!("\n--- TSVector ---");
-                    println!("{}", tsv_text);
-                }
-            }
+/// One line per version, newest first.
+async fn print_history(client: &Client, id: i32) -> Result<()> {
+    let history = versions::list(client, id).await?;
+    if history.is_empty() {
+        println!("Entry {} not found", id);
+        return Ok(());
+    }
 
-            println!("\n--- Content ---");
-            println!("{}", content);
-        }
+    println!("=== History of entry {} ===", id);
+    for version in &history {
+        let written = version.created_at.map(|t| t.to_string()).unwrap_or_else(|| "unknown".to_string());
+        let title = version.metadata.get("title").and_then(|t| t.as_str()).unwrap_or("");
+        println!(
+            "  v{:<4} {}  {:>8} chars  {}{}",
+            version.version,
+            written,
+            version.content.chars().count(),
+            title,
+            if version.current { "  (current)" } else { "" }
+        );
     }
+    if history.len() > 1 {
+        println!("\nShow one with: elastic-claude get {} --version <N>; compare with: elastic-claude diff {}", id, id);
+    }
+    Ok(())
+}
+
+async fn print_version(client: &Client, id: i32, version: i32, content_only: bool) -> Result<()> {
+    let Some(found) = versions::get(client, id, version).await? else {
+        bail!("Entry {} has no version {} (see: elastic-claude get {} --history)", id, version, id);
+    };
+
+    if content_only {
+        println!("{}", found.content);
+        return Ok(());
+    }
+
+    println!("=== Entry {}, version {}{} ===", id, found.version, if found.current { " (current)" } else { "" });
+    if let Some(path) = &found.file_path {
+        println!("File: {}", path);
+    }
+    if let Some(written) = found.created_at {
+        println!("Written: {}", written);
+    }
+    println!("Metadata: {}", serde_json::to_string_pretty(&found.metadata)?);
+    println!("\n--- Content ---");
+    println!("{}", found.content);
+    Ok(())
+}
+
+fn print_blob(entry: &Entry) {
+    let location = match (entry.blob_size, &entry.blob_sha256) {
+        (Some(size), _) => format!("{} bytes", size),
+        (None, Some(sha256)) => format!("blob store {}", sha256),
+        (None, None) => return,
+    };
+    println!(
+        "Blob: {}, {} (save with: elastic-claude get {} --blob -o <file>)",
+        entry.mime_type.as_deref().unwrap_or("application/octet-stream"),
+        location,
+        entry.id
+    );
+}
 
+/// Write an entry's original bytes, from the database or the blob store, to `output`
+/// or stdout.
+async fn write_blob(config: &Config, client: &Client, id: i32, output: Option<&str>) -> Result<()> {
+    let row = client
+        .query_opt("SELECT blob, blob_sha256, mime_type FROM entries WHERE id = $1", &[&id])
+        .await
+        .context("Failed to query entry")?;
+    let Some(row) = row else {
+        bail!("Entry {} not found", id);
+    };
+
+    let inline: Option<Vec<u8>> = row.get(0);
+    let sha256: Option<String> = row.get(1);
+    let mime_type: Option<String> = row.get(2);
+    let data = match (inline, sha256) {
+        (Some(data), _) => data,
+        (None, Some(sha256)) => blobs::load(config, &sha256)?,
+        (None, None) => bail!("Entry {} has no blob", id),
+    };
+
+    match output {
+        Some(path) if path != "-" => {
+            std::fs::write(Path::new(path), &data).with_context(|| format!("Failed to write {}", path))?;
+            println!(
+                "✓ Wrote {} bytes ({}) to {}",
+                data.len(),
+                mime_type.as_deref().unwrap_or("application/octet-stream"),
+                path
+            );
+        }
+        _ => {
+            let mut stdout = std::io::stdout();
+            if stdout.is_terminal() {
+                bail!("Refusing to write binary data to a terminal. Use -o <file> or redirect stdout.");
+            }
+            stdout.write_all(&data)?;
+            stdout.flush()?;
+        }
+    }
     Ok(())
 }
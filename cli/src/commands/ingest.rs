@@ -1,6 +1,33 @@
 use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use crate::config::Config;
+use crate::db;
+use crate::entries::{self, Upsert};
+use crate::extract::{self, Document, Registry};
+use crate::git::RepoInfo;
+use super::search;
+use crate::{language, versions};
+
+/// Project-specific ignore file, read alongside .gitignore and .ignore
+const IGNORE_FILE: &str = ".elastic-claude-ignore";
+
+/// Extensions indexed as "document" rather than "code" when no type is given
+const DOCUMENT_EXTENSIONS: &[&str] = &["md", "markdown", "mdx", "txt", "rst", "adoc", "org"];
+
+pub struct RepoOptions {
+    pub dir: PathBuf,
+    pub entry_type: Option<String>,
+    pub metadata: Option<String>,
+    pub max_size: u64,
+    /// Text search language for every entry instead of detecting it
+    pub language: Option<String>,
+}
+
+pub async fn run(patterns: Vec<String>, repo: Option<RepoOptions>) -> Result<()> {
+    if let Some(options) = repo {
+        return ingest_repo(options).await;
+    }
 
-pub async fn run(patterns: Vec<String>) -> Result<()> {
     // Resolve glob patterns to file list
     let mut files = Vec::new();
     for pattern in &patterns {
@@ -33,3 +60,176 @@ pub async fn run(patterns: Vec<String>) -> Result<()> {
 
     Ok(())
 }
+
+async fn ingest_repo(options: RepoOptions) -> Result<()> {
+    if !options.dir.is_dir() {
+        bail!("Not a directory: {}", options.dir.display());
+    }
+    let dir = std::fs::canonicalize(&options.dir)
+        .with_context(|| format!("Could not resolve {}", options.dir.display()))?;
+    let repo = RepoInfo::discover(&dir)?;
+
+    let extra_metadata: serde_json::Value = match &options.metadata {
+        Some(m) => serde_json::from_str(m).context("Invalid JSON in metadata")?,
+        None => serde_json::json!({}),
+    };
+
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+    let client = db::connect(&config).await?;
+    if let Some(lang) = &options.language {
+        language::check(&client, lang).await?;
+    }
+
+    println!(
+        "Ingesting {} ({})\n",
+        repo.name,
+        repo.branch.as_deref().unwrap_or("detached HEAD")
+    );
+
+    let walker = ignore::WalkBuilder::new(&dir)
+        .add_custom_ignore_filename(IGNORE_FILE)
+        .build();
+
+    let registry = Registry::default();
+    let (mut added, mut updated, mut unchanged, mut skipped) = (0, 0, 0, 0);
+
+    for result in walker {
+        let entry = match result {
+            Ok(entry) => entry,
+            Err(e) => {
+                eprintln!("Warning: {}", e);
+                continue;
+            }
+        };
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+
+        let path = entry.path();
+        let relative_path = repo.relative_path(path)?;
+        let document = match read_document(&registry, path, Path::new(&relative_path), options.max_size) {
+            Ok(document) => document,
+            Err(reason) => {
+                println!("  skipped {} ({})", path.display(), reason);
+                skipped += 1;
+                continue;
+            }
+        };
+
+        let entry_type = options
+            .entry_type
+            .clone()
+            .unwrap_or_else(|| default_entry_type(path, &document).to_string());
+
+        let mut metadata = serde_json::json!({
+            "project": repo.name,
+            "title": relative_path,
+        });
+        merge(&mut metadata, &document.metadata);
+        merge(&mut metadata, &extra_metadata);
+        metadata["git"] = git_metadata(&repo, &relative_path);
+
+        let file_path = path.to_string_lossy();
+        let upsert = entries::upsert_file(
+            &client,
+            &entry_type,
+            &file_path,
+            &document.content,
+            &metadata,
+            document.blob(),
+            options.language.as_deref(),
+        );
+        match upsert.await? {
+            Upsert::Inserted(id) => {
+                println!("  added #{} {}", id, relative_path);
+                added += 1;
+            }
+            Upsert::Updated(id) => {
+                println!("  updated #{} {}", id, relative_path);
+                updated += 1;
+            }
+            Upsert::Unchanged => unchanged += 1,
+            Upsert::Trashed(id) => {
+                println!("  skipped #{} {} (in the trash)", id, relative_path);
+                skipped += 1;
+            }
+        }
+    }
+
+    if updated > 0 {
+        versions::prune(&client, None, config.versions.keep).await?;
+    }
+    if added + updated > 0 {
+        search::refresh_vocabulary(&client).await?;
+    }
+
+    println!(
+        "\n✓ {} added, {} updated, {} unchanged, {} skipped",
+        added, updated, unchanged, skipped
+    );
+    Ok(())
+}
+
+/// Read a file as text, extracting it from known document formats and rejecting
+/// files over the size limit and other binary files.
+fn read_document(
+    registry: &Registry,
+    path: &Path,
+    relative_path: &Path,
+    max_size: u64,
+) -> std::result::Result<Document, String> {
+    let size = std::fs::metadata(path).map_err(|e| e.to_string())?.len();
+    if size > max_size {
+        return Err(format!("{} bytes exceeds --max-size", size));
+    }
+
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+
+    if registry.detect(path, &bytes).is_none() {
+        // Same heuristic as git: a NUL byte near the start means binary
+        if bytes.iter().take(8000).any(|b| *b == 0) {
+            return Err("binary".to_string());
+        }
+        if std::str::from_utf8(&bytes).is_err() {
+            return Err("not valid UTF-8".to_string());
+        }
+    }
+
+    extract::document_from_bytes(registry, relative_path, bytes).map_err(|e| format!("{:#}", e))
+}
+
+fn default_entry_type(path: &Path, document: &Document) -> &'static str {
+    if document.blob.is_some() {
+        return "document";
+    }
+    let is_document = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| DOCUMENT_EXTENSIONS.contains(&e.to_lowercase().as_str()));
+    if is_document {
+        "document"
+    } else {
+        "code"
+    }
+}
+
+fn git_metadata(repo: &RepoInfo, relative_path: &str) -> serde_json::Value {
+    let commit = repo.last_commit(relative_path);
+    serde_json::json!({
+        "repo": repo.name,
+        "path": relative_path,
+        "branch": repo.branch,
+        "commit": commit.as_ref().map(|c| &c.hash),
+        "author": commit.as_ref().map(|c| &c.author),
+        "date": commit.as_ref().map(|c| &c.date),
+        "dirty": repo.is_dirty(relative_path),
+    })
+}
+
+fn merge(target: &mut serde_json::Value, source: &serde_json::Value) {
+    if let (Some(target), Some(source)) = (target.as_object_mut(), source.as_object()) {
+        for (key, value) in source {
+            target.insert(key.clone(), value.clone());
+        }
+    }
+}
@@ -1,8 +1,9 @@
-use anyhow::{Context, Result};
+use anyhow::Result;
 use crate::config::Config;
-use crate::docker;
+use crate::{db, docker};
 
-const INIT_SQL: &str = include_str!("../../../docker/init.sql");
+pub const SKILL_MD: &str = include_str!("../../../skill/SKILL.md");
+pub const SCHEMA_MD: &str = include_str!("../../../skill/references/schema.md");
 
 pub async fn run() -> Result<()> {
     println!("Initializing elastic-claude...\n");
@@ -14,15 +15,26 @@ pub async fn run() -> Result<()> {
     let status = docker::get_container_status(&docker_client).await?;
     if status != docker::ContainerStatus::NotFound {
         println!("elastic-claude is already initialized.");
-        println!("Use 'elastic-claude start' to start the container.");
+
+        // Refresh the skill and schema so re-running init repairs an upgraded install
+        install_skill()?;
+        if status == docker::ContainerStatus::Running {
+            let config = Config::load()?;
+            let mut client = db::connect(&config).await?;
+            db::migrate(&mut client).await?;
+            println!("Database schema is at version {}", db::latest_version());
+        } else {
+            println!("Use 'elastic-claude start' to start the container.");
+        }
         return Ok(());
     }
 
     // Pull image
-    docker::pull_image(&docker_client).await?;
+    docker::pull_image(&docker_client, docker::IMAGE_NAME).await?;
 
     // Create volume
-    docker::create_volume(&docker_client).await?;
+    let volume = docker::volume_name(docker::POSTGRES_MAJOR);
+    docker::create_volume(&docker_client, &volume).await?;
 
     // Create config
     let config = Config::default();
@@ -30,6 +42,7 @@ pub async fn run() -> Result<()> {
     // Create and start container
     docker::create_container(
         &docker_client,
+        &volume,
         &config.database.password,
         &config.database.name,
     )
@@ -43,7 +56,9 @@ pub async fn run() -> Result<()> {
 
     // Run migrations
     println!("Running database migrations...");
-    run_migrations(&config).await?;
+    let mut client = db::connect(&config).await?;
+    db::migrate(&mut client).await?;
+    println!("Migrations completed successfully");
 
     // Save config
     config.save()?;
@@ -60,41 +75,18 @@ pub async fn run() -> Result<()> {
     Ok(())
 }
 
-async fn run_migrations(config: &Config) -> Result<()> {
-    let (client, connection) =
-        tokio_postgres::connect(&config.connection_string(), tokio_postgres::NoTls)
-            .await
-            .context("Failed to connect to database")?;
-
-    tokio::spawn(async move {
-        if let Err(e) = connection.await {
-            eprintln!("Database connection error: {}", e);
-        }
-    });
-
-    client
-        .batch_execute(INIT_SQL)
-        .await
-        .context("Failed to run migrations")?;
-
-    println!("Migrations completed successfully");
-    Ok(())
-}
-
 fn install_skill() -> Result<()> {
     let skill_dir = Config::skill_dir()?;
     std::fs::create_dir_all(&skill_dir)?;
 
     // Copy SKILL.md
-    let skill_content = include_str!("../../../skill/SKILL.md");
-    std::fs::write(skill_dir.join("SKILL.md"), skill_content)?;
+    std::fs::write(skill_dir.join("SKILL.md"), SKILL_MD)?;
 
     // Copy references
     let refs_dir = skill_dir.join("references");
     std::fs::create_dir_all(&refs_dir)?;
 
-    let schema_content = include_str!("../../../skill/references/schema.md");
-    std::fs::write(refs_dir.join("schema.md"), schema_content)?;
+    std::fs::write(refs_dir.join("schema.md"), SCHEMA_MD)?;
 
     println!("Skill installed to {:?}", skill_dir);
     Ok(())
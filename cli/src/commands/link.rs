@@ -0,0 +1,47 @@
+use anyhow::{bail, Context, Result};
+use crate::config::Config;
+use crate::{db, links};
+
+/// Link `from` to `to`, or with `detect` rescan every chat for mentioned files and
+/// tickets (as done automatically when a chat is ingested).
+pub async fn run(from: Option<i32>, to: Option<i32>, link_type: String, detect: bool) -> Result<()> {
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+    let client = db::connect(&config).await?;
+
+    if detect {
+        let chats = client
+            .query("SELECT id, content FROM entries WHERE entry_type = 'chat' AND deleted_at IS NULL ORDER BY id", &[])
+            .await
+            .context("Failed to query chats")?;
+        let mut total = 0;
+        for row in &chats {
+            let id: i32 = row.get(0);
+            let linked = links::detect_mentions(&client, id, row.get(1)).await?;
+            if !linked.is_empty() {
+                let ids: Vec<String> = linked.iter().map(|l| format!("#{}", l)).collect();
+                println!("  #{} references {}", id, ids.join(", "));
+                total += linked.len();
+            }
+        }
+        println!("\n✓ Scanned {} chats, {} references found", chats.len(), total);
+        return Ok(());
+    }
+
+    let (Some(from), Some(to)) = (from, to) else {
+        bail!("Specify two entry ids, or --detect");
+    };
+    let found = client
+        .query_one("SELECT count(*) FROM entries WHERE id = $1 OR id = $2", &[&from, &to])
+        .await
+        .context("Failed to query entries")?;
+    if found.get::<_, i64>(0) < 2 {
+        bail!("Entry {} or {} not found", from, to);
+    }
+
+    if links::add(&client, from, to, &link_type, false).await? {
+        println!("✓ Linked #{} {} #{}", from, link_type, to);
+    } else {
+        println!("#{} already {} #{}", from, link_type, to);
+    }
+    Ok(())
+}
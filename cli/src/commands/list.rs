@@ -0,0 +1,67 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tokio_postgres::Client;
+use crate::config::Config;
+use crate::db;
+
+pub async fn run(entry_type: Option<String>, project: Option<String>, limit: i64) -> Result<()> {
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+    let client = db::connect(&config).await?;
+
+    let listed = list(&client, entry_type.as_deref(), project.as_deref(), limit).await?;
+    if listed.is_empty() {
+        println!("No entries found");
+        return Ok(());
+    }
+
+    for entry in &listed {
+        println!(
+            "#{:<6} {:<10} {}  {}",
+            entry.id,
+            entry.entry_type,
+            entry.created_at.format("%Y-%m-%d %H:%M"),
+            entry.label
+        );
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+pub struct ListedEntry {
+    pub id: i32,
+    pub entry_type: String,
+    pub created_at: chrono::NaiveDateTime,
+    /// Title, file path, or the start of the content
+    pub label: String,
+}
+
+/// Most recent entries, optionally of one type or project. Trashed entries are
+/// left out (see `trash list`). Used by the CLI and `serve`.
+pub async fn list(client: &Client, entry_type: Option<&str>, project: Option<&str>, limit: i64) -> Result<Vec<ListedEntry>> {
+    let rows = client
+        .query(
+            r#"
+            SELECT id, entry_type, created_at,
+                   COALESCE(metadata->>'title', file_path, left(regexp_replace(content, '\s+', ' ', 'g'), 60)) AS label
+            FROM entries
+            WHERE deleted_at IS NULL
+              AND ($1::text IS NULL OR entry_type = $1)
+              AND ($2::text IS NULL OR metadata->>'project' = $2)
+            ORDER BY created_at DESC, id DESC
+            LIMIT $3
+            "#,
+            &[&entry_type, &project, &limit],
+        )
+        .await
+        .context("Failed to list entries")?;
+
+    Ok(rows
+        .iter()
+        .map(|row| ListedEntry {
+            id: row.get("id"),
+            entry_type: row.get("entry_type"),
+            created_at: row.get("created_at"),
+            label: row.get("label"),
+        })
+        .collect())
+}
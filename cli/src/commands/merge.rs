@@ -0,0 +1,57 @@
+use anyhow::{bail, Context, Result};
+use crate::config::Config;
+use crate::{db, entries};
+
+/// Fold `drop` into `keep` and move the dropped entries to the trash, or remove
+/// them for good with `purge`.
+pub async fn run(keep: i32, mut drop: Vec<i32>, purge: bool) -> Result<()> {
+    drop.sort_unstable();
+    drop.dedup();
+    if drop.contains(&keep) {
+        bail!("Entry {} cannot be both kept and dropped", keep);
+    }
+
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+    let mut client = db::connect(&config).await?;
+
+    let live: Vec<i32> = client
+        .query(
+            "SELECT id FROM entries WHERE (id = $1 OR id = ANY($2)) AND deleted_at IS NULL",
+            &[&keep, &drop],
+        )
+        .await
+        .context("Failed to look up entries")?
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+    let missing: Vec<String> = std::iter::once(&keep)
+        .chain(&drop)
+        .filter(|id| !live.contains(id))
+        .map(|id| id.to_string())
+        .collect();
+    if !missing.is_empty() {
+        bail!("Entries not found (or in trash): {}", missing.join(", "));
+    }
+
+    // All or nothing: a failure part way must not leave half-merged entries
+    let tx = client.transaction().await?;
+    entries::merge(&tx, keep, &drop).await?;
+    let trashed = entries::trash(&tx, &drop).await?;
+    if purge {
+        entries::purge(&tx, &trashed).await?;
+    }
+    tx.commit().await.context("Failed to commit merge")?;
+
+    if purge {
+        println!("✓ Merged {} entries into #{} and removed them permanently", trashed.len(), keep);
+    } else {
+        let list: Vec<String> = trashed.iter().map(|id| id.to_string()).collect();
+        println!(
+            "✓ Merged {} entries into #{} and moved them to trash (restore with: elastic-claude trash restore {})",
+            trashed.len(),
+            keep,
+            list.join(" ")
+        );
+    }
+    Ok(())
+}
@@ -1,11 +1,33 @@
 pub mod add;
+pub mod backup;
 pub mod chat;
+pub mod context;
 pub mod current_chat;
+pub mod dedupe;
+pub mod delete;
 pub mod destroy;
+pub mod diff;
+pub mod doctor;
+pub mod gc;
 pub mod get;
 pub mod ingest;
 pub mod init;
+pub mod link;
+pub mod list;
+pub mod merge;
+pub mod restore;
+pub mod retag;
 pub mod search;
+pub mod serve;
+pub mod similar;
 pub mod start;
 pub mod status;
 pub mod stop;
+pub mod summarize;
+pub mod synonyms;
+pub mod trash;
+pub mod tui;
+pub mod unlink;
+pub mod update;
+pub mod upgrade_db;
+pub mod watch;
@@ -0,0 +1,61 @@
+use anyhow::{bail, Context, Result};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use crate::config::Config;
+use crate::docker;
+
+pub async fn run(file: PathBuf, yes: bool) -> Result<()> {
+    if !file.exists() {
+        bail!("Backup file not found: {}", file.display());
+    }
+
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+
+    let docker_client = docker::connect().await?;
+    if docker::get_container_status(&docker_client).await? != docker::ContainerStatus::Running {
+        bail!("elastic-claude is not running. Run 'elastic-claude start' first.");
+    }
+
+    if !yes {
+        println!(
+            "This will replace all data in {} with the contents of {}.",
+            config.database.name,
+            file.display()
+        );
+        print!("Are you sure? [y/N] ");
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+
+        if !input.trim().eq_ignore_ascii_case("y") {
+            println!("Cancelled");
+            return Ok(());
+        }
+    }
+
+    let dump = std::fs::read(&file)
+        .with_context(|| format!("Failed to read backup: {}", file.display()))?;
+
+    println!("Restoring {}...", file.display());
+    docker::exec(
+        &docker_client,
+        vec![
+            "pg_restore",
+            "-U",
+            &config.database.user,
+            "-d",
+            &config.database.name,
+            "--clean",
+            "--if-exists",
+            "--no-owner",
+            "--single-transaction",
+        ],
+        Some(&dump),
+    )
+    .await
+    .context("pg_restore failed")?;
+
+    println!("✓ Database restored from {}", file.display());
+    Ok(())
+}
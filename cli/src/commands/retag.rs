@@ -0,0 +1,66 @@
+use anyhow::{Context, Result};
+use crate::config::Config;
+use crate::db;
+use crate::keywords::{self, Corpus};
+
+/// Recompute `metadata.keywords`. By default only entries without keywords are
+/// tagged; `all` also refreshes existing ones against the current corpus.
+pub async fn run(all: bool, entry_type: Option<String>, project: Option<String>) -> Result<()> {
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+    let client = db::connect(&config).await?;
+
+    // Code entries are skipped unless asked for by type; their symbols describe them
+    let ids: Vec<i32> = client
+        .query(
+            r#"
+            SELECT id FROM entries
+            WHERE ($1::text IS NULL AND entry_type <> 'code' OR entry_type = $1)
+              AND deleted_at IS NULL
+              AND ($2::text IS NULL OR metadata->>'project' = $2)
+              AND ($3 OR NOT COALESCE(metadata ? 'keywords', false))
+            ORDER BY id
+            "#,
+            &[&entry_type, &project, &all],
+        )
+        .await
+        .context("Failed to query entries")?
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+
+    if ids.is_empty() {
+        println!("No entries to tag{}", if all { "" } else { " (use --all to refresh existing keywords)" });
+        return Ok(());
+    }
+
+    println!("Tagging {} entries...\n", ids.len());
+
+    let mut corpus = Corpus::default();
+    let mut tagged = 0;
+    for id in ids {
+        let row = client
+            .query_one("SELECT content, language::text FROM entries WHERE id = $1", &[&id])
+            .await
+            .context("Failed to read entry")?;
+        let content: &str = row.get(0);
+        let language: &str = row.get(1);
+
+        let keywords = keywords::extract(&client, &mut corpus, content, Some(language)).await?;
+        if keywords.is_empty() {
+            continue;
+        }
+
+        client
+            .execute(
+                "UPDATE entries SET metadata = COALESCE(metadata, '{}') || jsonb_build_object('keywords', $2::jsonb) WHERE id = $1",
+                &[&id, &serde_json::json!(keywords)],
+            )
+            .await
+            .context("Failed to update keywords")?;
+        println!("  #{} {}", id, keywords.join(", "));
+        tagged += 1;
+    }
+
+    println!("\n✓ Updated keywords on {} entries", tagged);
+    Ok(())
+}
@@ -1,67 +1,561 @@
-use anyhow::{Context, Result};
-use crate::config::Config;
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::Client;
+use crate::config::{Config, RankingConfig};
+use crate::{code, db, language};
 
-pub async fn run(query: String) -> Result<()> {
+/// One tsquery expression per word of `query` in the text search configuration
+/// `language`, and the parameters they use, numbered from `$first`. Each word goes
+/// through `plainto_tsquery`, so punctuation cannot cause syntax errors, and also
+/// matches its synonyms; code identifiers also match their verbatim lexeme.
+pub fn tsquery_terms(query: &str, first: usize, language: &str) -> (Vec<String>, Vec<String>) {
+    let mut terms = Vec::new();
+    let mut params = Vec::new();
+    let language = regconfig_literal(language);
+
+    for word in query.split_whitespace() {
+        params.push(word.to_string());
+        let mut term = format!("synonym_tsquery({}, ${})", language, first + params.len() - 1);
+
+        if code::is_compound_identifier(word) {
+            params.push(code::normalize_identifier(word));
+            term = format!("({} || quote_literal(${})::tsquery)", term, first + params.len() - 1);
+        }
+        terms.push(term);
+    }
+
+    (terms, params)
+}
+
+pub fn sql_params(params: &[String]) -> Vec<&(dyn ToSql + Sync)> {
+    params.iter().map(|p| p as &(dyn ToSql + Sync)).collect()
+}
+
+fn regconfig_literal(language: &str) -> String {
+    format!("'{}'::regconfig", language.replace('\'', "''"))
+}
+
+/// The words of a query as tsquery terms in each language searched. An entry is
+/// matched with the terms of its own language, so each is stemmed the same way as
+/// its `content_tsv`. All languages share the same parameters.
+pub struct LanguageTerms {
+    languages: Vec<String>,
+    /// One tsquery expression per word, for each language
+    terms: Vec<Vec<String>>,
+    words: usize,
+    pub params: Vec<String>,
+}
+
+impl LanguageTerms {
+    pub fn new(query: &str, first: usize, languages: &[String]) -> Self {
+        // The parameters are the words themselves, whatever the language
+        let (words, params) = tsquery_terms(query, first, "simple");
+        Self {
+            words: words.len(),
+            languages: languages.to_vec(),
+            terms: languages.iter().map(|l| tsquery_terms(query, first, l).0).collect(),
+            params,
+        }
+    }
+
+    /// Number of words
+    pub fn len(&self) -> usize {
+        self.words
+    }
+
+    /// A condition that holds when the entry is in one of the languages and
+    /// `condition` holds for that language's terms. Written as one branch per
+    /// language so each can use the `content_tsv` index.
+    pub fn matching(&self, condition: impl Fn(&[String]) -> String) -> String {
+        if self.languages.is_empty() {
+            return "false".to_string();
+        }
+        let branches: Vec<String> = self
+            .languages
+            .iter()
+            .zip(&self.terms)
+            .map(|(language, terms)| format!("(language = {} AND {})", regconfig_literal(language), condition(terms)))
+            .collect();
+        format!("({})", branches.join(" OR "))
+    }
+
+    /// `expression` of the terms of the entry's language
+    pub fn per_language(&self, expression: impl Fn(&[String]) -> String) -> String {
+        match self.languages.len() {
+            0 => "NULL".to_string(),
+            1 => expression(&self.terms[0]),
+            _ => {
+                let cases: Vec<String> = self
+                    .languages
+                    .iter()
+                    .zip(&self.terms)
+                    .map(|(language, terms)| format!("WHEN {} THEN {}", regconfig_literal(language), expression(terms)))
+                    .collect();
+                format!("CASE language {} END", cases.join(" "))
+            }
+        }
+    }
+}
+
+/// Languages to match a query in: the chosen one, or each language live entries
+/// are written in.
+pub async fn languages(client: &Client, chosen: Option<&str>) -> Result<Vec<String>> {
+    if let Some(chosen) = chosen {
+        language::check(client, chosen).await?;
+        return Ok(vec![chosen.to_string()]);
+    }
+    let rows = client
+        .query("SELECT DISTINCT language::text FROM entries WHERE deleted_at IS NULL ORDER BY 1", &[])
+        .await
+        .context("Failed to list entry languages")?;
+    Ok(rows.iter().map(|row| row.get(0)).collect())
+}
+
+pub struct SearchOptions {
+    pub query: Option<String>,
+    pub symbol: Option<String>,
+    pub fuzzy: bool,
+    /// Rebuild the vocabulary behind suggestions first, if it is out of date
+    pub refresh: bool,
+    pub filters: Filters,
+    pub ranking: RankingOverrides,
+    pub limit: i64,
+}
+
+pub async fn run(options: SearchOptions) -> Result<()> {
     let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+    let ranking = options.ranking.apply(&config.search.ranking)?;
+    let client = db::connect(&config).await?;
 
-    let (client, connection) =
-        tokio_postgres::connect(&config.connection_string(), tokio_postgres::NoTls)
-            .await
-            .context("Failed to connect to database")?;
+    if let Some(symbol) = options.symbol {
+        return find_symbol(&client, &symbol).await;
+    }
+    if options.refresh && refresh_vocabulary(&client).await? {
+        println!("Search vocabulary refreshed");
+    }
+    let (query, filters, fuzzy) = (options.query.unwrap_or_default(), options.filters, options.fuzzy);
+    let hits = if fuzzy {
+        fuzzy_search(&client, &query, &filters, options.limit).await?
+    } else {
+        search(&client, &query, &filters, &ranking, options.limit).await?
+    };
+
+    if hits.is_empty() {
+        println!("No results found for: {}", query);
+        if let Some(suggestion) = did_you_mean(&client, &query, &filters).await? {
+            println!("Did you mean: elastic-claude search \"{}\"", suggestion);
+        } else if !fuzzy {
+            println!("Try: elastic-claude search --fuzzy \"{}\"", query);
+        }
+        return Ok(());
+    }
+
+    println!("Found {} results:\n", hits.len());
 
-    tokio::spawn(async move {
-        if let Err(e) = connection.await {
-            eprintln!("Database connection error: {}", e);
+    for hit in hits {
+        println!("--- Entry {} (score: {:.2}) ---", hit.id, hit.rank);
+        println!("Type: {}", hit.entry_type);
+        if let Some(path) = &hit.file_path {
+            println!("File: {}", path);
+        }
+        if let Some(title) = hit.metadata.get("title").and_then(|t| t.as_str()) {
+            println!("Title: {}", title);
+        }
+        if let Some(git) = hit.metadata.get("git") {
+            print_git(git);
         }
-    });
+        println!("Snippet: {}...\n", hit.snippet.trim());
+    }
+
+    Ok(())
+}
 
-    // Convert query to tsquery format (space-separated words become AND)
-    let tsquery = query
-        .split_whitespace()
-        .collect::<Vec<_>>()
-        .join(" & ");
+/// Restricts search to one entry type, `metadata.project` and/or text search language
+#[derive(Default)]
+pub struct Filters {
+    pub entry_type: Option<String>,
+    pub project: Option<String>,
+    pub language: Option<String>,
+}
+
+/// Per-query changes to `search.ranking`
+#[derive(Default)]
+pub struct RankingOverrides {
+    pub function: Option<String>,
+    pub normalization: Option<i32>,
+    /// 0 ignores age
+    pub half_life_days: Option<f64>,
+    /// `D,C,B,A`
+    pub weights: Option<String>,
+    /// `type=weight` pairs, added to `type_weights`
+    pub boosts: Vec<String>,
+}
+
+impl RankingOverrides {
+    /// `base` with these changes, checked
+    pub fn apply(&self, base: &RankingConfig) -> Result<RankingConfig> {
+        let mut ranking = base.clone();
+        if let Some(function) = &self.function {
+            ranking.function = function.clone();
+        }
+        if let Some(normalization) = self.normalization {
+            ranking.normalization = normalization;
+        }
+        if let Some(days) = self.half_life_days {
+            ranking.half_life_days = (days > 0.0).then_some(days);
+        }
+        if let Some(weights) = &self.weights {
+            let parsed: Vec<f32> = weights
+                .split(',')
+                .map(|w| w.trim().parse())
+                .collect::<std::result::Result<_, _>>()
+                .ok()
+                .filter(|w: &Vec<f32>| w.len() == 4)
+                .with_context(|| format!("Invalid weights '{}' (expected four numbers: D,C,B,A)", weights))?;
+            ranking.weights.copy_from_slice(&parsed);
+        }
+        for boost in &self.boosts {
+            let parsed = boost
+                .split_once('=')
+                .and_then(|(entry_type, weight)| Some((entry_type.trim(), weight.trim().parse::<f64>().ok()?)));
+            let Some((entry_type, weight)) = parsed else {
+                bail!("Invalid boost '{}' (expected type=weight)", boost);
+            };
+            ranking.type_weights.insert(entry_type.to_string(), weight);
+        }
+        check_ranking(&ranking)?;
+        Ok(ranking)
+    }
+}
+
+fn check_ranking(ranking: &RankingConfig) -> Result<()> {
+    if !matches!(ranking.function.as_str(), "ts_rank" | "ts_rank_cd") {
+        bail!("Unknown rank function '{}' (expected ts_rank or ts_rank_cd)", ranking.function);
+    }
+    if !(0..=63).contains(&ranking.normalization) {
+        bail!("Invalid normalization {} (a bit mask from 0 to 63)", ranking.normalization);
+    }
+    if ranking.weights.iter().any(|w| !(0.0..=1.0).contains(w)) {
+        bail!("Section weights must be between 0 and 1");
+    }
+    if ranking.half_life_days.is_some_and(|days| days.is_nan() || days <= 0.0) {
+        bail!("half_life_days must be positive");
+    }
+    if ranking.type_weights.values().any(|w| !w.is_finite() || *w < 0.0) {
+        bail!("Type weights must not be negative");
+    }
+    Ok(())
+}
+
+/// An entry's score for `query`: `ts_rank` or `ts_rank_cd` over the weighted
+/// sections, halved every `half_life_days` of age and multiplied by its type's
+/// weight. Uses five parameters from `$first`, given by `RankingParams`.
+fn score_sql(ranking: &RankingConfig, first: usize) -> Result<String> {
+    check_ranking(ranking)?;
+    Ok(format!(
+        r#"{}(${}::float4[], content_tsv, query, ${}::int)
+               * COALESCE(power(0.5, EXTRACT(EPOCH FROM LOCALTIMESTAMP - created_at)::float8 / 86400 / ${}::float8), 1)
+               * COALESCE((SELECT t.w FROM unnest(${}::text[], ${}::float8[]) AS t(type, w) WHERE t.type = entry_type), 1)"#,
+        ranking.function,
+        first,
+        first + 1,
+        first + 2,
+        first + 3,
+        first + 4
+    ))
+}
+
+/// The values `score_sql` refers to
+struct RankingParams {
+    weights: Vec<f32>,
+    types: Vec<String>,
+    type_weights: Vec<f64>,
+}
+
+impl RankingParams {
+    fn new(ranking: &RankingConfig) -> Self {
+        Self {
+            weights: ranking.weights.to_vec(),
+            types: ranking.type_weights.keys().cloned().collect(),
+            type_weights: ranking.type_weights.values().copied().collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct SearchHit {
+    pub id: i32,
+    pub entry_type: String,
+    pub file_path: Option<String>,
+    pub metadata: serde_json::Value,
+    pub snippet: String,
+    pub rank: f32,
+}
+
+/// Live entries matching all words of `query`, best first by `ranking`. Each entry
+/// is matched in its own language, or only entries in `filters.language` are
+/// searched. Used by the CLI and `serve`.
+pub async fn search(
+    client: &Client,
+    query: &str,
+    filters: &Filters,
+    ranking: &RankingConfig,
+    limit: i64,
+) -> Result<Vec<SearchHit>> {
+    let score = score_sql(ranking, 4)?;
+    let languages = languages(client, filters.language.as_deref()).await?;
+    // Space-separated words become AND
+    let terms = LanguageTerms::new(query, 9, &languages);
+    if terms.params.is_empty() {
+        bail!("Search query is empty");
+    }
+
+    let sql = format!(
+        r#"
+        SELECT id, entry_type, file_path, metadata,
+               ts_headline(language, content, query,
+                   'MaxFragments=3, MaxWords=30, MinWords=15, FragmentDelimiter= ... ') as snippet,
+               ({})::real as rank
+        FROM entries, LATERAL (SELECT {} AS query) q
+        WHERE {} AND deleted_at IS NULL
+          AND ($2::text IS NULL OR entry_type = $2)
+          AND ($3::text IS NULL OR metadata->>'project' = $3)
+        ORDER BY rank DESC, created_at DESC
+        LIMIT $1
+        "#,
+        score,
+        terms.per_language(|t| t.join(" && ")),
+        terms.matching(|t| format!("content_tsv @@ ({})", t.join(" && ")))
+    );
+
+    let params = RankingParams::new(ranking);
+    let mut sql_args: Vec<&(dyn ToSql + Sync)> = vec![
+        &limit,
+        &filters.entry_type,
+        &filters.project,
+        &params.weights,
+        &ranking.normalization,
+        &ranking.half_life_days,
+        &params.types,
+        &params.type_weights,
+    ];
+    sql_args.extend(sql_params(&terms.params));
+    let rows = client
+        .query(&sql, &sql_args)
+        .await
+        .context("Search query failed")?;
+
+    Ok(rows
+        .iter()
+        .map(|row| SearchHit {
+            id: row.get("id"),
+            entry_type: row.get("entry_type"),
+            file_path: row.get("file_path"),
+            metadata: row.get("metadata"),
+            snippet: row.get("snippet"),
+            rank: row.get("rank"),
+        })
+        .collect())
+}
+
+/// Live entries whose title, file path or content contains something close to
+/// `query` by trigram word similarity, so misspellings and parts of identifiers
+/// still match. Snippets mark the vocabulary words closest to the query words.
+pub async fn fuzzy_search(client: &Client, query: &str, filters: &Filters, limit: i64) -> Result<Vec<SearchHit>> {
+    if !query.chars().any(char::is_alphanumeric) {
+        bail!("Search query is empty");
+    }
 
     let rows = client
         .query(
             r#"
+            WITH words AS (
+                SELECT lower(w) AS w FROM regexp_split_to_table($4, '[^[:alnum:]_]+') w WHERE length(w) > 1
+            ),
+            marks AS (
+                SELECT quote_literal(w) || ':*' AS term FROM words
+                UNION
+                SELECT quote_literal(v.word) FROM words,
+                    LATERAL (SELECT word FROM search_vocabulary WHERE word % words.w
+                             ORDER BY similarity(word, words.w) DESC LIMIT 3) v
+            )
             SELECT id, entry_type, file_path, metadata,
-                   ts_headline('english', content, query,
-                       'MaxFragments=3, MaxWords=30, MinWords=15, FragmentDelimiter= ... ') as snippet,
-                   ts_rank(content_tsv, query) as rank
-            FROM entries, to_tsquery('english', $1) query
-            WHERE content_tsv @@ query
+                   COALESCE(ts_headline('simple', content, q.marks,
+                       'MaxFragments=3, MaxWords=30, MinWords=15, FragmentDelimiter= ... '), left(content, 200)) AS snippet,
+                   greatest(word_similarity($4, metadata->>'title'), word_similarity($4, file_path),
+                            word_similarity($4, content)) AS rank
+            FROM entries, (SELECT to_tsquery('simple', string_agg(term, ' | ')) AS marks FROM marks) q
+            WHERE ($4 <% content OR $4 <% (metadata->>'title') OR $4 <% file_path)
+              AND deleted_at IS NULL
+              AND ($2::text IS NULL OR entry_type = $2)
+              AND ($3::text IS NULL OR metadata->>'project' = $3)
+              AND ($5::text IS NULL OR language = $5::text::regconfig)
             ORDER BY rank DESC
-            LIMIT 10
+            LIMIT $1
             "#,
-            &[&tsquery],
+            &[&limit, &filters.entry_type, &filters.project, &query, &filters.language],
         )
         .await
-        .context("Search query failed")?;
+        .context("Fuzzy search failed")?;
+
+    Ok(rows
+        .iter()
+        .map(|row| SearchHit {
+            id: row.get("id"),
+            entry_type: row.get("entry_type"),
+            file_path: row.get("file_path"),
+            metadata: row.get("metadata"),
+            snippet: row.get("snippet"),
+            rank: row.get("rank"),
+        })
+        .collect())
+}
+
+/// `query` with each word missing from the vocabulary replaced by the closest
+/// word that is in it, if that query finds something.
+pub async fn did_you_mean(client: &Client, query: &str, filters: &Filters) -> Result<Option<String>> {
+    let mut changed = false;
+    let mut words = Vec::new();
+    for word in query.split_whitespace() {
+        let bare = word.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase();
+        if bare.chars().count() < 3 || code::is_compound_identifier(word) {
+            words.push(word.to_string());
+            continue;
+        }
+        let closest = client
+            .query_opt(
+                r#"
+                SELECT word FROM search_vocabulary
+                WHERE word % $1
+                ORDER BY word = $1 DESC, similarity(word, $1) DESC, ndoc DESC
+                LIMIT 1
+                "#,
+                &[&bare],
+            )
+            .await
+            .context("Vocabulary lookup failed")?;
+        match closest.map(|row| row.get::<_, String>(0)) {
+            Some(closest) if closest != bare => {
+                words.push(closest);
+                changed = true;
+            }
+            _ => words.push(word.to_string()),
+        }
+    }
+    if !changed {
+        return Ok(None);
+    }
+
+    let suggestion = words.join(" ");
+    let found = !search(client, &suggestion, filters, &RankingConfig::default(), 1).await?.is_empty();
+    Ok(found.then_some(suggestion))
+}
+
+/// Rebuild `search_vocabulary` if entries were written, trashed or restored since
+/// it was last built. Searches only read it: `ingest`, `gc`, `serve` and
+/// `search --refresh` keep it current. Returns whether it was rebuilt.
+pub async fn refresh_vocabulary(client: &Client) -> Result<bool> {
+    let stale: bool = client
+        .query_one(
+            r#"
+            WITH built AS (
+                SELECT max(refreshed_at) AS refreshed_at, max(live_entries) AS live_entries FROM search_vocabulary
+            )
+            SELECT COALESCE(built.refreshed_at, '-infinity')
+                     < COALESCE((SELECT max(GREATEST(created_at, updated_at, deleted_at)) FROM entries), '-infinity')
+                OR built.live_entries IS DISTINCT FROM (SELECT count(*) FROM entries WHERE deleted_at IS NULL)
+            FROM built
+            "#,
+            &[],
+        )
+        .await
+        .context("Failed to check search vocabulary")?
+        .get(0);
+    if stale {
+        client
+            .batch_execute("REFRESH MATERIALIZED VIEW CONCURRENTLY search_vocabulary")
+            .await
+            .context("Failed to refresh search vocabulary")?;
+    }
+    Ok(stale)
+}
+
+/// Print the revision an entry was ingested from, e.g. `repo@1a2b3c4d src/main.rs (main)`
+fn print_git(git: &serde_json::Value) {
+    let repo = git.get("repo").and_then(|v| v.as_str()).unwrap_or("?");
+    let path = git.get("path").and_then(|v| v.as_str()).unwrap_or("?");
+    let commit = git
+        .get("commit")
+        .and_then(|v| v.as_str())
+        .map(|c| &c[..c.len().min(8)])
+        .unwrap_or("untracked");
+
+    let mut line = format!("Git: {}@{} {}", repo, commit, path);
+    if let Some(branch) = git.get("branch").and_then(|v| v.as_str()) {
+        line.push_str(&format!(" ({})", branch));
+    }
+    if git.get("dirty").and_then(|v| v.as_bool()) == Some(true) {
+        line.push_str(" [modified]");
+    }
+    println!("{}", line);
+}
+
+/// Exact definition lookup in the `symbols` extracted from code entries.
+/// `Type::method` or `Type.method` restricts matches to that parent.
+async fn find_symbol(client: &Client, symbol: &str) -> Result<()> {
+    let (parent, name) = match symbol.rsplit_once("::").or_else(|| symbol.rsplit_once('.')) {
+        Some((parent, name)) => (Some(parent.rsplit("::").next().unwrap_or(parent)), name),
+        None => (None, symbol),
+    };
+
+    let mut pattern = serde_json::json!({ "name": name });
+    if let Some(parent) = parent {
+        pattern["parent"] = parent.into();
+    }
+    let containment = serde_json::json!({ "symbols": [pattern] });
+
+    let rows = client
+        .query(
+            r#"
+            SELECT e.id, e.file_path, e.metadata->>'language' AS language, s
+            FROM entries e, jsonb_array_elements(e.metadata->'symbols') s
+            WHERE e.metadata @> $1 AND s @> $2 AND e.deleted_at IS NULL
+            ORDER BY e.id, (s->>'line')::int
+            "#,
+            &[&containment, &pattern],
+        )
+        .await
+        .context("Symbol lookup failed")?;
 
     if rows.is_empty() {
-        println!("No results found for: {}", query);
+        println!("No definition found for: {}", symbol);
         return Ok(());
     }
 
-    println!("Found {} results:\n", rows.len());
+    println!("Found {} definitions:\n", rows.len());
 
     for row in rows {
         let id: i32 = row.get("id");
-        let entry_type: &str = row.get("entry_type");
         let file_path: Option<&str> = row.get("file_path");
-        let metadata: serde_json::Value = row.get("metadata");
-        let snippet: &str = row.get("snippet");
-        let rank: f32 = row.get("rank");
+        let language: Option<&str> = row.get("language");
+        let found: serde_json::Value = row.get("s");
 
-        println!("--- Entry {} (score: {:.2}) ---", id, rank);
-        println!("Type: {}", entry_type);
-        if let Some(path) = file_path {
-            println!("File: {}", path);
-        }
-        if let Some(title) = metadata.get("title").and_then(|t| t.as_str()) {
-            println!("Title: {}", title);
-        }
-        println!("Snippet: {}...\n", snippet.trim());
+        let kind = found.get("kind").and_then(|k| k.as_str()).unwrap_or("symbol");
+        let line = found.get("line").and_then(|l| l.as_u64()).unwrap_or(0);
+        let qualified = match found.get("parent").and_then(|p| p.as_str()) {
+            Some(parent) => format!("{}::{}", parent, name),
+            None => name.to_string(),
+        };
+
+        println!(
+            "Entry {}: {} {} ({})",
+            id,
+            kind,
+            qualified,
+            language.unwrap_or("unknown")
+        );
+        println!("  {}:{}", file_path.unwrap_or("<no file>"), line);
     }
 
     Ok(())
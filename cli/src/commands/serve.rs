@@ -0,0 +1,28 @@
+use anyhow::{Context, Result};
+use std::io::Read;
+use std::net::SocketAddr;
+use crate::config::Config;
+use crate::server;
+
+pub async fn run(bind: Option<String>) -> Result<()> {
+    let mut config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+    if config.server.token.is_none() {
+        config.server.token = Some(generate_token()?);
+        config.save()?;
+        println!("Generated server.token in {}", Config::config_path()?.display());
+    }
+    let bind = bind.unwrap_or_else(|| config.server.bind.clone());
+    let addr: SocketAddr = bind
+        .parse()
+        .with_context(|| format!("Invalid bind address '{}'. Use IP:PORT, e.g. 127.0.0.1:8765", bind))?;
+    server::serve(config, addr).await
+}
+
+/// 32 random bytes from the OS, hex-encoded
+fn generate_token() -> Result<String> {
+    let mut bytes = [0u8; 32];
+    std::fs::File::open("/dev/urandom")
+        .and_then(|mut f| f.read_exact(&mut bytes))
+        .context("Could not read random bytes for server.token")?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
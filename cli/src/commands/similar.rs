@@ -0,0 +1,155 @@
+//! `elastic-claude similar`: entries like a given one.
+//!
+//! The entry's most distinctive lexemes are read from its `content_tsv` with
+//! `ts_stat` and weighted by TF-IDF over the live entries. Other entries are scored
+//! by the summed weight of the lexemes they share with it.
+
+use anyhow::{bail, Context, Result};
+use tokio_postgres::Client;
+use crate::config::Config;
+use crate::{db, language};
+use super::search::Filters;
+
+/// Lexemes of the entry considered, most frequent first
+const MAX_CANDIDATES: i64 = 400;
+
+/// Lexemes the lookup is built from
+const MAX_TERMS: usize = 15;
+
+pub struct SimilarEntry {
+    pub id: i32,
+    pub entry_type: String,
+    pub file_path: Option<String>,
+    pub label: String,
+    pub score: f64,
+    /// The distinctive lexemes this entry shares
+    pub shared: Vec<String>,
+}
+
+pub async fn run(id: i32, filters: Filters, limit: i64) -> Result<()> {
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+    let client = db::connect(&config).await?;
+    if let Some(lang) = &filters.language {
+        language::check(&client, lang).await?;
+    }
+
+    let terms = distinctive_terms(&client, id).await?;
+    if terms.is_empty() {
+        println!("Entry {} has no terms shared with other entries", id);
+        return Ok(());
+    }
+    let lexemes: Vec<&str> = terms.iter().map(|(l, _)| l.as_str()).collect();
+    println!("Entries like #{} (by: {})\n", id, lexemes.join(", "));
+
+    let similar = similar(&client, id, &terms, &filters, limit).await?;
+    if similar.is_empty() {
+        println!("No similar entries found");
+        return Ok(());
+    }
+
+    for entry in similar {
+        println!("--- Entry {} (score: {:.2}) ---", entry.id, entry.score);
+        println!("Type: {}", entry.entry_type);
+        match &entry.file_path {
+            Some(path) if *path != entry.label => println!("File: {}\nTitle: {}", path, entry.label),
+            Some(path) => println!("File: {}", path),
+            None => println!("Title: {}", entry.label),
+        }
+        println!("Shared: {}\n", entry.shared.join(", "));
+    }
+    Ok(())
+}
+
+/// The entry's lexemes that best tell it apart, with their TF-IDF weights. Lexemes
+/// no other entry contains cannot find anything and are left out.
+pub async fn distinctive_terms(client: &Client, id: i32) -> Result<Vec<(String, f64)>> {
+    let exists = client
+        .query_opt("SELECT 1 FROM entries WHERE id = $1 AND deleted_at IS NULL", &[&id])
+        .await
+        .context("Failed to look up entry")?;
+    if exists.is_none() {
+        bail!("Entry {} not found (or in trash)", id);
+    }
+
+    // ts_stat takes the query as text; the id is an integer, so it is safe to inline
+    let stat_query = format!("SELECT content_tsv FROM entries WHERE id = {}", id);
+    let rows = client
+        .query(
+            r#"
+            WITH lexemes AS (
+                SELECT word, nentry FROM ts_stat($1)
+                WHERE length(word) > 2 AND word ~ '^[[:alpha:]_][[:alnum:]_]*$'
+                ORDER BY nentry DESC, word
+                LIMIT $2
+            ),
+            corpus AS (SELECT count(*) AS total FROM entries WHERE deleted_at IS NULL)
+            SELECT l.word,
+                   (1 + ln(l.nentry)) * ln((c.total + 1)::float8 / (f.ndoc + 1)) AS weight
+            FROM lexemes l, corpus c,
+                 LATERAL (SELECT count(*) AS ndoc FROM entries
+                          WHERE content_tsv @@ quote_literal(l.word)::tsquery AND deleted_at IS NULL) f
+            WHERE f.ndoc > 1
+            ORDER BY weight DESC, l.word
+            LIMIT $3
+            "#,
+            &[&stat_query, &MAX_CANDIDATES, &(MAX_TERMS as i64)],
+        )
+        .await
+        .context("Failed to read entry lexemes")?;
+
+    Ok(rows.iter().map(|row| (row.get("word"), row.get("weight"))).collect())
+}
+
+/// Live entries sharing `terms` with entry `id`, best first. The entry itself and
+/// entries derived from it (its summaries) are excluded. Lexemes are only comparable
+/// within a language, so candidates are in `filters.language`, or else in the
+/// entry's own language.
+pub async fn similar(
+    client: &Client,
+    id: i32,
+    terms: &[(String, f64)],
+    filters: &Filters,
+    limit: i64,
+) -> Result<Vec<SimilarEntry>> {
+    let lexemes: Vec<&str> = terms.iter().map(|(l, _)| l.as_str()).collect();
+    let weights: Vec<f64> = terms.iter().map(|(_, w)| *w).collect();
+
+    let rows = client
+        .query(
+            r#"
+            WITH terms AS (
+                SELECT l, w, quote_literal(l)::tsquery AS q FROM unnest($2::text[], $3::float8[]) AS t(l, w)
+            )
+            SELECT e.id, e.entry_type, e.file_path,
+                   COALESCE(e.metadata->>'title', e.file_path, left(regexp_replace(e.content, '\s+', ' ', 'g'), 60)) AS label,
+                   m.score, m.shared
+            FROM entries e,
+                 LATERAL (SELECT sum(w) AS score, array_agg(l ORDER BY w DESC) AS shared
+                          FROM terms WHERE e.content_tsv @@ q) m
+            WHERE e.content_tsv @@ (SELECT string_agg(quote_literal(l), ' | ') FROM terms)::tsquery
+              AND e.id <> $1
+              AND e.source_id IS DISTINCT FROM $1
+              AND e.deleted_at IS NULL
+              AND ($4::text IS NULL OR e.entry_type = $4)
+              AND ($5::text IS NULL OR e.metadata->>'project' = $5)
+              AND e.language = COALESCE($7::text::regconfig, (SELECT language FROM entries WHERE id = $1))
+            ORDER BY m.score DESC, e.id DESC
+            LIMIT $6
+            "#,
+            &[&id, &lexemes, &weights, &filters.entry_type, &filters.project, &limit, &filters.language],
+        )
+        .await
+        .context("Similar-entry query failed")?;
+
+    Ok(rows
+        .iter()
+        .map(|row| SimilarEntry {
+            id: row.get("id"),
+            entry_type: row.get("entry_type"),
+            file_path: row.get("file_path"),
+            label: row.get("label"),
+            score: row.get("score"),
+            shared: row.get("shared"),
+        })
+        .collect())
+}
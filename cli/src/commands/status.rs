@@ -1,4 +1,6 @@
 use anyhow::Result;
+use serde::Serialize;
+use tokio_postgres::Client;
 use crate::config::Config;
 use crate::docker;
 
@@ -30,8 +32,9 @@ pub async fn run() -> Result<()> {
 
         // Get entry counts if running
         if status == docker::ContainerStatus::Running {
-            if let Ok(counts) = get_entry_counts(&config).await {
-                println!("Entries:   {}", counts);
+            match get_entry_counts(&config).await {
+                Ok(counts) => println!("Entries:   {}", counts),
+                Err(_) => println!("Entries:   unavailable (run 'elastic-claude doctor')"),
             }
 
             if let Ok(size) = get_database_size(&config).await {
@@ -53,27 +56,47 @@ async fn get_entry_counts(config: &Config) -> Result<String> {
         let _ = connection.await;
     });
 
-    let rows = client
-        .query(
-            "SELECT entry_type, COUNT(*) as count FROM entries GROUP BY entry_type ORDER BY count DESC",
-            &[],
-        )
-        .await?;
-
-    if rows.is_empty() {
+    let counts = entry_counts(&client).await?;
+    if counts.by_type.is_empty() {
         return Ok("0 entries".to_string());
     }
 
-    let counts: Vec<String> = rows
+    let mut parts: Vec<String> = counts
+        .by_type
         .iter()
-        .map(|row| {
-            let entry_type: &str = row.get(0);
-            let count: i64 = row.get(1);
-            format!("{} {}s", count, entry_type)
-        })
+        .map(|(entry_type, count)| format!("{} {}s", count, entry_type))
         .collect();
+    if counts.trash > 0 {
+        parts.push(format!("{} in trash", counts.trash));
+    }
+
+    Ok(parts.join(", "))
+}
+
+#[derive(Serialize)]
+pub struct EntryCounts {
+    /// Live entries per type, most common first
+    pub by_type: Vec<(String, i64)>,
+    pub trash: i64,
+}
 
-    Ok(counts.join(", "))
+/// Used by `status` and `serve`.
+pub async fn entry_counts(client: &Client) -> Result<EntryCounts> {
+    let rows = client
+        .query(
+            "SELECT entry_type, COUNT(*) as count FROM entries WHERE deleted_at IS NULL GROUP BY entry_type ORDER BY count DESC",
+            &[],
+        )
+        .await?;
+    let trash: i64 = client
+        .query_one("SELECT COUNT(*) FROM entries WHERE deleted_at IS NOT NULL", &[])
+        .await?
+        .get(0);
+
+    Ok(EntryCounts {
+        by_type: rows.iter().map(|row| (row.get(0), row.get(1))).collect(),
+        trash,
+    })
 }
 
 async fn get_database_size(config: &Config) -> Result<String> {
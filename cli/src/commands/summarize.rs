@@ -0,0 +1,308 @@
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use tokio_postgres::{Client, Row};
+use crate::config::Config;
+use crate::summary::{self, Summarizer};
+use crate::{db, entries, links, versions};
+
+/// Types summarized by --since unless -t is given
+const DEFAULT_TYPES: &[&str] = &["chat", "document"];
+
+pub struct SummarizeOptions {
+    pub id: Option<i32>,
+    pub since: Option<String>,
+    pub rollup: Option<String>,
+    pub entry_type: Option<String>,
+    pub project: Option<String>,
+    pub sentences: usize,
+    pub backend: Option<String>,
+}
+
+pub async fn run(options: SummarizeOptions) -> Result<()> {
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+    let client = db::connect(&config).await?;
+
+    let summarizer = summary::from_config(&config.summarizer, options.backend.as_deref(), options.sentences)?;
+
+    if let Some(id) = options.id {
+        let Some(summary_id) = summarize_id(&client, summarizer.as_ref(), id).await? else {
+            bail!("Entry {} has no prose to summarize", id);
+        };
+        versions::prune(&client, Some(summary_id), config.versions.keep).await?;
+        println!("✓ Summary of #{} saved as entry {}", id, summary_id);
+        return Ok(());
+    }
+
+    let since = options.since.as_deref().context("Specify an entry id or --since")?;
+    let rows = entries_since(&client, since, &options).await?;
+    if rows.is_empty() {
+        println!("No entries created since {}", since);
+        return Ok(());
+    }
+
+    match options.rollup.as_deref() {
+        Some(period) => rollup(&client, summarizer.as_ref(), &rows, period).await?,
+        None => summarize_each(&client, summarizer.as_ref(), &rows).await?,
+    }
+    // Refreshed summaries and roll-ups archive their previous text
+    versions::prune(&client, None, config.versions.keep).await?;
+    Ok(())
+}
+
+async fn entries_since(client: &Client, since: &str, options: &SummarizeOptions) -> Result<Vec<Row>> {
+    let (date, interval) = parse_since(since)?;
+    let types: Vec<String> = match &options.entry_type {
+        Some(t) => vec![t.clone()],
+        None => DEFAULT_TYPES.iter().map(|t| t.to_string()).collect(),
+    };
+
+    client
+        .query(
+            r#"
+            SELECT id, entry_type, content, file_path, metadata, created_at
+            FROM entries
+            WHERE created_at >= COALESCE($1::timestamp, NOW() - $2::text::interval)
+              AND entry_type = ANY($3)
+              AND deleted_at IS NULL
+              AND ($4::text IS NULL OR metadata->>'project' = $4)
+            ORDER BY created_at, id
+            "#,
+            &[&date, &interval, &types, &options.project],
+        )
+        .await
+        .context("Failed to query entries")
+}
+
+/// Summarize entries that do not have a summary yet.
+async fn summarize_each(client: &Client, summarizer: &dyn Summarizer, rows: &[Row]) -> Result<()> {
+    let (mut created, mut skipped) = (0, 0);
+    for row in rows {
+        let id: i32 = row.get("id");
+        if existing_summary(client, id).await?.is_some() {
+            skipped += 1;
+            continue;
+        }
+        match summarize_entry(client, summarizer, row).await? {
+            Some(summary_id) => {
+                println!("  #{} {} -> summary #{}", id, title(row), summary_id);
+                created += 1;
+            }
+            None => skipped += 1,
+        }
+    }
+    println!("\n✓ {} summaries created, {} skipped (already summarized or no prose)", created, skipped);
+    Ok(())
+}
+
+/// Summarize one entry by id; None when it has no prose. Used by `chat --summarize`.
+pub async fn summarize_id(client: &Client, summarizer: &dyn Summarizer, id: i32) -> Result<Option<i32>> {
+    let row = client
+        .query_opt(
+            "SELECT id, entry_type, content, file_path, metadata, created_at FROM entries WHERE id = $1",
+            &[&id],
+        )
+        .await
+        .context("Failed to query entry")?;
+    let Some(row) = row else {
+        bail!("Entry {} not found", id);
+    };
+    summarize_entry(client, summarizer, &row).await
+}
+
+/// Write (or refresh) the summary of one entry. A title suggested by the
+/// summarizer is also stored on the source entry if it has none.
+async fn summarize_entry(client: &Client, summarizer: &dyn Summarizer, row: &Row) -> Result<Option<i32>> {
+    let id: i32 = row.get("id");
+    let content: &str = row.get("content");
+    let Some(summary) = summarizer.summarize(content).await? else {
+        return Ok(None);
+    };
+
+    let source_metadata: serde_json::Value = row.get("metadata");
+    if let Some(suggested) = &summary.title {
+        if source_metadata.get("title").is_none() {
+            client
+                .execute(
+                    "UPDATE entries SET metadata = metadata || jsonb_build_object('title', $2::text) WHERE id = $1",
+                    &[&id, suggested],
+                )
+                .await
+                .context("Failed to set entry title")?;
+        }
+    }
+
+    let title = match (source_metadata.get("title").and_then(|t| t.as_str()), &summary.title) {
+        (Some(existing), _) => existing.to_string(),
+        (None, Some(suggested)) => suggested.clone(),
+        (None, None) => title(row),
+    };
+    let mut metadata = serde_json::json!({
+        "summary_type": "entry",
+        "method": summarizer.method(),
+        "model": summarizer.model(),
+        "title": format!("Summary: {}", title),
+        "source_ids": [id],
+        "generated_at": chrono::Utc::now().to_rfc3339(),
+    });
+    if let Some(project) = source_metadata.get("project") {
+        metadata["project"] = project.clone();
+    }
+
+    let summary_id = match existing_summary(client, id).await? {
+        Some(summary_id) => {
+            entries::update(client, summary_id, "summary", &summary.text, &metadata).await?;
+            summary_id
+        }
+        None => entries::insert_summary(client, &summary.text, Some(id), &metadata).await?,
+    };
+    Ok(Some(summary_id))
+}
+
+async fn existing_summary(client: &Client, source_id: i32) -> Result<Option<i32>> {
+    let row = client
+        .query_opt(
+            r#"
+            SELECT id FROM entries
+            WHERE entry_type = 'summary' AND source_id = $1 AND deleted_at IS NULL
+              AND metadata @> '{"summary_type": "entry"}'
+            ORDER BY id
+            LIMIT 1
+            "#,
+            &[&source_id],
+        )
+        .await
+        .context("Failed to look up existing summary")?;
+    Ok(row.map(|r| r.get(0)))
+}
+
+/// One summary per project and day or week, replacing an earlier roll-up of the
+/// same period.
+async fn rollup(client: &Client, summarizer: &dyn Summarizer, rows: &[Row], period: &str) -> Result<()> {
+    let mut groups: BTreeMap<(String, Option<String>), Vec<&Row>> = BTreeMap::new();
+    for row in rows {
+        let created_at: chrono::NaiveDateTime = row.get("created_at");
+        let label = match period {
+            "weekly" => created_at.format("%G-W%V").to_string(),
+            _ => created_at.format("%Y-%m-%d").to_string(),
+        };
+        let metadata: serde_json::Value = row.get("metadata");
+        let project = metadata.get("project").and_then(|p| p.as_str()).map(str::to_string);
+        groups.entry((label, project)).or_default().push(row);
+    }
+
+    let mut written = 0;
+    for ((label, project), group) in groups {
+        let combined: Vec<&str> = group.iter().map(|r| r.get::<_, &str>("content")).collect();
+        let Some(summary) = summarizer.summarize(&combined.join("\n\n")).await? else {
+            continue;
+        };
+
+        let heading = format!(
+            "{} summary: {}, {} ({} entries)",
+            if period == "weekly" { "Weekly" } else { "Daily" },
+            project.as_deref().unwrap_or("no project"),
+            label,
+            group.len()
+        );
+        let mut text = format!("{}\n\n{}\n\nSources:\n", heading, summary.text);
+        for row in &group {
+            text.push_str(&format!("- #{} {}: {}\n", row.get::<_, i32>("id"), row.get::<_, &str>("entry_type"), title(row)));
+        }
+
+        let source_ids: Vec<i32> = group.iter().map(|r| r.get("id")).collect();
+        let metadata = serde_json::json!({
+            "summary_type": period,
+            "method": summarizer.method(),
+            "model": summarizer.model(),
+            "title": heading,
+            "project": project,
+            "period": label,
+            "source_ids": source_ids,
+            "generated_at": chrono::Utc::now().to_rfc3339(),
+        });
+
+        let existing = client
+            .query_opt(
+                "SELECT id FROM entries WHERE entry_type = 'summary' AND metadata @> $1 AND deleted_at IS NULL ORDER BY id LIMIT 1",
+                &[&serde_json::json!({ "summary_type": period, "project": project, "period": label })],
+            )
+            .await
+            .context("Failed to look up existing roll-up")?;
+
+        let id = match existing {
+            Some(row) => {
+                let id: i32 = row.get(0);
+                entries::update(client, id, "summary", &text, &metadata).await?;
+                id
+            }
+            None => entries::insert_summary(client, &text, None, &metadata).await?,
+        };
+        // Sources that left the group (trashed, moved to another project) are unlinked
+        client
+            .execute(
+                "DELETE FROM entry_links WHERE from_id = $1 AND link_type = 'summarizes' AND auto AND NOT to_id = ANY($2)",
+                &[&id, &source_ids],
+            )
+            .await
+            .context("Failed to unlink roll-up sources")?;
+        for &source_id in &source_ids {
+            links::add(client, id, source_id, "summarizes", true).await?;
+        }
+        println!("  {} -> summary #{}", heading, id);
+        written += 1;
+    }
+
+    println!("\n✓ {} {} roll-ups written", written, period);
+    Ok(())
+}
+
+/// Human-readable name of an entry: its title, file name, or id.
+fn title(row: &Row) -> String {
+    let metadata: serde_json::Value = row.get("metadata");
+    if let Some(title) = metadata.get("title").and_then(|t| t.as_str()) {
+        return title.to_string();
+    }
+    let file_path: Option<&str> = row.get("file_path");
+    match file_path.and_then(|p| std::path::Path::new(p).file_name()) {
+        Some(name) => name.to_string_lossy().to_string(),
+        None => format!("entry #{}", row.get::<_, i32>("id")),
+    }
+}
+
+/// `--since` accepts a date (2025-01-15) or a duration back from now (12h, 7d, 2w).
+fn parse_since(since: &str) -> Result<(Option<chrono::NaiveDateTime>, Option<String>)> {
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(since, "%Y-%m-%d") {
+        return Ok((date.and_hms_opt(0, 0, 0), None));
+    }
+
+    let invalid = || format!("Invalid --since '{}'. Use a date (2025-01-15) or a duration (12h, 7d, 2w)", since);
+    let (amount, unit) = [('h', "hours"), ('d', "days"), ('w', "weeks")]
+        .into_iter()
+        .find_map(|(suffix, unit)| since.strip_suffix(suffix).map(|amount| (amount, unit)))
+        .with_context(invalid)?;
+    let amount: u32 = amount.parse().with_context(invalid)?;
+    Ok((None, Some(format!("{} {}", amount, unit))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn since_accepts_dates_and_durations() {
+        let (date, interval) = parse_since("2025-01-15").unwrap();
+        assert_eq!(date.unwrap().to_string(), "2025-01-15 00:00:00");
+        assert!(interval.is_none());
+        assert_eq!(parse_since("12h").unwrap().1.as_deref(), Some("12 hours"));
+        assert_eq!(parse_since("7d").unwrap().1.as_deref(), Some("7 days"));
+        assert_eq!(parse_since("2w").unwrap().1.as_deref(), Some("2 weeks"));
+    }
+
+    #[test]
+    fn since_rejects_other_input_without_panicking() {
+        for since in ["7д", "д", "7", "d", "", "-1d", "7m", "1.5d", "😀"] {
+            let err = parse_since(since).unwrap_err().to_string();
+            assert!(err.starts_with("Invalid --since"), "{}: {}", since, err);
+        }
+    }
+}
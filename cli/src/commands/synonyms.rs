@@ -0,0 +1,108 @@
+//! `elastic-claude synonyms`: manage the synonym table search expands terms with.
+//!
+//! A term and its synonyms form a group whose members all match each other, both
+//! in queries and in the indexed entries. Entries store the members of the groups
+//! they mention (`synonym_words`), so they are re-indexed whenever the table changes.
+
+use anyhow::{bail, Context, Result};
+use tokio_postgres::Client;
+use crate::config::Config;
+use crate::db;
+
+pub enum Action {
+    Add { term: String, synonyms: Vec<String> },
+    List,
+    Remove { term: String, synonyms: Vec<String> },
+}
+
+pub async fn run(action: Action) -> Result<()> {
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+    let client = db::connect(&config).await?;
+
+    match action {
+        Action::Add { term, synonyms } => {
+            let term = normalize(&term)?;
+            let mut added = 0;
+            for synonym in &synonyms {
+                let synonym = normalize(synonym)?;
+                if synonym == term {
+                    bail!("'{}' cannot be a synonym of itself", term);
+                }
+                added += client
+                    .execute(
+                        "INSERT INTO synonyms (term, synonym) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+                        &[&term, &synonym],
+                    )
+                    .await
+                    .context("Failed to add synonym")?;
+            }
+            let reindexed = reindex(&client).await?;
+            println!("✓ Added {} synonyms for '{}' ({} entries re-indexed)", added, term, reindexed);
+            Ok(())
+        }
+        Action::List => list(&client).await,
+        Action::Remove { term, synonyms } => {
+            let term = normalize(&term)?;
+            let synonyms = synonyms.iter().map(|s| normalize(s)).collect::<Result<Vec<_>>>()?;
+            let removed = client
+                .execute(
+                    "DELETE FROM synonyms WHERE term = $1 AND (cardinality($2::text[]) = 0 OR synonym = ANY($2))",
+                    &[&term, &synonyms],
+                )
+                .await
+                .context("Failed to remove synonyms")?;
+            if removed == 0 {
+                println!("No matching synonyms of '{}'", term);
+                return Ok(());
+            }
+            let reindexed = reindex(&client).await?;
+            println!("✓ Removed {} synonyms of '{}' ({} entries re-indexed)", removed, term, reindexed);
+            Ok(())
+        }
+    }
+}
+
+/// Lowercased, with whitespace collapsed, the way terms are stored and matched
+fn normalize(term: &str) -> Result<String> {
+    let normalized = term.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    if normalized.is_empty() {
+        bail!("Synonyms cannot be empty");
+    }
+    Ok(normalized)
+}
+
+async fn list(client: &Client) -> Result<()> {
+    let rows = client
+        .query(
+            "SELECT term, array_agg(synonym ORDER BY synonym) AS synonyms FROM synonyms GROUP BY term ORDER BY term",
+            &[],
+        )
+        .await
+        .context("Failed to query synonyms")?;
+    if rows.is_empty() {
+        println!("No synonyms defined (add some with: elastic-claude synonyms add <term> <synonym>...)");
+        return Ok(());
+    }
+
+    for row in &rows {
+        let synonyms: Vec<String> = row.get("synonyms");
+        println!("{}: {}", row.get::<_, &str>("term"), synonyms.join(", "));
+    }
+    Ok(())
+}
+
+/// Recompute `synonym_words` (and with it `content_tsv`) for every entry whose
+/// synonyms changed, trashed ones included. Returns how many were updated.
+async fn reindex(client: &Client) -> Result<u64> {
+    client
+        .execute(
+            r#"
+            UPDATE entries e SET synonym_words = s.words
+            FROM (SELECT id, entry_synonyms(language, content) AS words FROM entries) s
+            WHERE e.id = s.id AND e.synonym_words IS DISTINCT FROM s.words
+            "#,
+            &[],
+        )
+        .await
+        .context("Failed to re-index entries")
+}
@@ -0,0 +1,99 @@
+use anyhow::{Context, Result};
+use std::io::{self, Write};
+use tokio_postgres::Client;
+use crate::config::Config;
+use crate::{db, entries};
+
+pub enum Action {
+    List,
+    Restore(Vec<i32>),
+    Empty { older_than_days: Option<u32>, yes: bool },
+}
+
+pub async fn run(action: Action) -> Result<()> {
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+    let client = db::connect(&config).await?;
+
+    match action {
+        Action::List => list(&client).await,
+        Action::Restore(ids) => {
+            let restored = entries::restore(&client, &ids).await?;
+            for id in ids.iter().filter(|id| !restored.contains(id)) {
+                println!("Entry {} is not in the trash", id);
+            }
+            if !restored.is_empty() {
+                println!("✓ Restored {} entries", restored.len());
+            }
+            Ok(())
+        }
+        Action::Empty { older_than_days, yes } => empty(&client, older_than_days, yes).await,
+    }
+}
+
+async fn list(client: &Client) -> Result<()> {
+    let rows = trashed(client, None).await?;
+    if rows.is_empty() {
+        println!("Trash is empty");
+        return Ok(());
+    }
+
+    println!("{} entries in trash:\n", rows.len());
+    for row in &rows {
+        let deleted_at: chrono::NaiveDateTime = row.get("deleted_at");
+        println!(
+            "#{:<6} {:<10} deleted {}  {}",
+            row.get::<_, i32>("id"),
+            row.get::<_, &str>("entry_type"),
+            deleted_at.format("%Y-%m-%d %H:%M"),
+            row.get::<_, &str>("label")
+        );
+    }
+    println!("\nRestore with: elastic-claude trash restore <id>...");
+    Ok(())
+}
+
+async fn empty(client: &Client, older_than_days: Option<u32>, yes: bool) -> Result<()> {
+    let ids: Vec<i32> = trashed(client, older_than_days).await?.iter().map(|r| r.get("id")).collect();
+    if ids.is_empty() {
+        println!("Nothing to remove");
+        return Ok(());
+    }
+
+    if !yes {
+        println!("This will permanently remove {} entries, with their links and versions.", ids.len());
+        print!("Are you sure? [y/N] ");
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+
+        if !input.trim().eq_ignore_ascii_case("y") {
+            println!("Cancelled");
+            return Ok(());
+        }
+    }
+
+    let purged = entries::purge(client, &ids).await?;
+    println!("✓ Permanently removed {} entries", purged);
+    Ok(())
+}
+
+/// Trashed entries, oldest deletion first; with `older_than_days`, only those
+/// deleted at least that long ago.
+pub async fn trashed(client: &Client, older_than_days: Option<u32>) -> Result<Vec<tokio_postgres::Row>> {
+    let days = older_than_days.map(|d| d as i32);
+    client
+        .query(
+            r#"
+            SELECT id, entry_type, deleted_at,
+                   COALESCE(metadata->>'title', file_path, left(regexp_replace(content, '\s+', ' ', 'g'), 60)) AS label
+            FROM entries
+            WHERE deleted_at IS NOT NULL
+              AND ($1::int IS NULL OR deleted_at < NOW() - make_interval(days => $1))
+            ORDER BY deleted_at, id
+            "#,
+            &[&days],
+        )
+        .await
+        .context("Failed to query trash")
+}
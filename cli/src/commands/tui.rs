@@ -0,0 +1,14 @@
+use anyhow::{Context, Result};
+use crate::config::Config;
+use crate::db;
+use crate::tui::{self, Outcome};
+
+pub async fn run() -> Result<()> {
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+    let client = db::connect(&config).await?;
+
+    if let Outcome::Print(content) = tui::run(client, config.search.ranking).await? {
+        println!("{}", content);
+    }
+    Ok(())
+}
@@ -0,0 +1,16 @@
+use anyhow::{Context, Result};
+use crate::config::Config;
+use crate::{db, links};
+
+pub async fn run(from: i32, to: i32, link_type: Option<String>) -> Result<()> {
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+    let client = db::connect(&config).await?;
+
+    let removed = links::remove(&client, from, to, link_type.as_deref()).await?;
+    if removed == 0 {
+        println!("No matching link from #{} to #{}", from, to);
+    } else {
+        println!("✓ Removed {} link(s) from #{} to #{}", removed, from, to);
+    }
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+use crate::config::Config;
+use crate::{db, entries, extract, language, versions};
+
+/// Replace an entry's content (from -c or a file), merge metadata into it and/or
+/// change its text search language. A file's extracted metadata (front matter,
+/// title, outline) is merged too, under any given with -m. The previous content is kept as a version.
+pub async fn run(
+    id: i32,
+    content: Option<String>,
+    path: Option<String>,
+    metadata: Option<String>,
+    lang: Option<String>,
+) -> Result<()> {
+    let mut patch: Option<serde_json::Value> = match &metadata {
+        Some(m) => Some(serde_json::from_str(m).context("Invalid JSON in metadata")?),
+        None => None,
+    };
+    let content = match (content, path) {
+        (Some(c), None) => Some(c),
+        (None, Some(p)) => {
+            let document = extract::read_file(Path::new(&p))?;
+            // Explicit -m wins over metadata extracted from the file
+            let mut extracted = document.metadata;
+            let explicit = patch.as_ref().and_then(|m| m.as_object());
+            if let (Some(target), Some(explicit)) = (extracted.as_object_mut(), explicit) {
+                target.extend(explicit.clone());
+            }
+            patch = Some(extracted);
+            Some(document.content)
+        }
+        (None, None) => None,
+        (Some(_), Some(_)) => bail!("Cannot specify both --content and --path"),
+    };
+    if content.is_none() && patch.is_none() && lang.is_none() {
+        bail!("Nothing to update. Give new content (-c or -p), metadata (-m) and/or --lang");
+    }
+
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+    let client = db::connect(&config).await?;
+    if let Some(lang) = &lang {
+        language::check(&client, lang).await?;
+    }
+
+    if !entries::edit(&client, id, content.as_deref(), patch.as_ref(), lang.as_deref()).await? {
+        bail!("Entry {} not found (or in trash)", id);
+    }
+    versions::prune(&client, Some(id), config.versions.keep).await?;
+    println!("✓ Updated entry {}", id);
+    Ok(())
+}
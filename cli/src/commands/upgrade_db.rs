@@ -0,0 +1,335 @@
+use anyhow::{bail, Context, Result};
+use bollard::Docker;
+use std::collections::BTreeMap;
+use crate::config::Config;
+use crate::docker;
+use super::backup;
+
+const OLD_CONTAINER: &str = "elastic-claude-upgrade-old";
+const NEW_CONTAINER: &str = "elastic-claude-upgrade-new";
+
+pub async fn run() -> Result<()> {
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+    let docker_client = docker::connect().await?;
+
+    let status = docker::get_container_status(&docker_client).await?;
+    if status == docker::ContainerStatus::NotFound {
+        bail!(
+            "elastic-claude is not initialized.\n\
+             Run 'elastic-claude init' first."
+        );
+    }
+
+    let old_volume = docker::mounted_volume(&docker_client)
+        .await?
+        .unwrap_or_else(|| docker::VOLUME_NAME.to_string());
+
+    // The data directory can only be read by a server of the same major version
+    let old_major = docker::read_pg_version(&docker_client, &old_volume).await?;
+    let new_major = docker::POSTGRES_MAJOR;
+
+    if old_major == new_major {
+        println!("Volume {} is already on PostgreSQL {}. Nothing to do.", old_volume, new_major);
+        return Ok(());
+    }
+    if old_major > new_major {
+        bail!(
+            "Volume {} holds PostgreSQL {} data, newer than this binary's {}.\n\
+             Upgrade elastic-claude instead.",
+            old_volume,
+            old_major,
+            docker::IMAGE_NAME
+        );
+    }
+
+    let new_volume = docker::volume_name(new_major);
+    if docker_client.inspect_volume(&new_volume).await.is_ok() {
+        bail!(
+            "Volume {} already exists.\n\
+             Remove it with 'docker volume rm {}' if it is left over from a failed upgrade.",
+            new_volume,
+            new_volume
+        );
+    }
+
+    let old_image = format!("postgres:{}-alpine", old_major);
+    println!(
+        "Upgrading PostgreSQL {} ({}) to {} ({})\n",
+        old_major, old_volume, new_major, new_volume
+    );
+
+    if status == docker::ContainerStatus::Running {
+        println!("Stopping elastic-claude...");
+        docker::stop_container(&docker_client).await?;
+    }
+
+    // From here on the database is down, so every failure puts the original back
+    let copied = copy_to_new_volume(&docker_client, &config, &old_image, &old_volume, old_major, &new_volume).await;
+    let new_counts = match copied {
+        Ok(counts) => counts,
+        Err(e) => {
+            rollback(&docker_client, &new_volume, status).await;
+            return Err(e);
+        }
+    };
+    println!("Verified entry counts: {}", format_counts(&new_counts));
+
+    if let Err(e) = swap_volume(&docker_client, &config, &new_volume).await {
+        recreate_original(&docker_client, &config, &old_image, &old_volume).await;
+        rollback(&docker_client, &new_volume, status).await;
+        return Err(e);
+    }
+
+    println!("\n✓ Upgraded to PostgreSQL {}", new_major);
+    println!("\nThe old volume {} was kept for rollback. To roll back:", old_volume);
+    println!("  docker rm -f {}", docker::CONTAINER_NAME);
+    println!(
+        "  docker run -d --name {} -p 127.0.0.1:{}:5432 -v {}:{} -e POSTGRES_PASSWORD=<password> -e POSTGRES_DB={} {}",
+        docker::CONTAINER_NAME,
+        config.database.port,
+        old_volume,
+        docker::DATA_DIR,
+        config.database.name,
+        old_image
+    );
+    println!("Once you are satisfied, free the space with 'docker volume rm {}'.", old_volume);
+
+    Ok(())
+}
+
+/// Dump `old_volume` from a temporary container on the old image, then restore the
+/// dump into `new_volume` on the new image and check the row counts match. Returns
+/// the counts.
+async fn copy_to_new_volume(
+    docker_client: &Docker,
+    config: &Config,
+    old_image: &str,
+    old_volume: &str,
+    old_major: u32,
+    new_volume: &str,
+) -> Result<BTreeMap<String, i64>> {
+    docker::pull_image(docker_client, old_image).await?;
+    let (dump, old_counts) = dump_old(docker_client, config, old_image, old_volume).await?;
+
+    let dump_path = backup::write_backup(config, &dump, Some(&format!("pg{}", old_major)))?;
+    println!("Dump saved to {}", dump_path.display());
+
+    docker::pull_image(docker_client, docker::IMAGE_NAME).await?;
+    docker::create_volume(docker_client, new_volume).await?;
+    let new_counts = restore_new(docker_client, config, new_volume, &dump).await?;
+
+    if new_counts != old_counts {
+        bail!(
+            "Row counts differ after restore; the upgrade was abandoned and {} is untouched.\n\
+             Before: {}\nAfter:  {}",
+            old_volume,
+            format_counts(&old_counts),
+            format_counts(&new_counts)
+        );
+    }
+    Ok(new_counts)
+}
+
+async fn dump_old(
+    docker_client: &Docker,
+    config: &Config,
+    image: &str,
+    volume: &str,
+) -> Result<(Vec<u8>, BTreeMap<String, i64>)> {
+    start_temporary(docker_client, config, OLD_CONTAINER, image, volume).await?;
+
+    let counts = entry_counts(docker_client, config, OLD_CONTAINER).await?;
+    println!("Dumping {}...", volume);
+    let dump = docker::exec_in(
+        docker_client,
+        OLD_CONTAINER,
+        vec![
+            "pg_dump",
+            "-U",
+            &config.database.user,
+            "-d",
+            &config.database.name,
+            "--format=custom",
+        ],
+        None,
+    )
+    .await
+    .context("pg_dump on the old image failed")?;
+
+    docker::force_remove_container(docker_client, OLD_CONTAINER).await?;
+    Ok((dump, counts))
+}
+
+async fn restore_new(
+    docker_client: &Docker,
+    config: &Config,
+    volume: &str,
+    dump: &[u8],
+) -> Result<BTreeMap<String, i64>> {
+    start_temporary(docker_client, config, NEW_CONTAINER, docker::IMAGE_NAME, volume).await?;
+
+    println!("Restoring into {}...", volume);
+    docker::exec_in(
+        docker_client,
+        NEW_CONTAINER,
+        vec![
+            "pg_restore",
+            "-U",
+            &config.database.user,
+            "-d",
+            &config.database.name,
+            "--no-owner",
+            "--single-transaction",
+        ],
+        Some(dump),
+    )
+    .await
+    .context("pg_restore on the new image failed")?;
+
+    let counts = entry_counts(docker_client, config, NEW_CONTAINER).await?;
+    docker::force_remove_container(docker_client, NEW_CONTAINER).await?;
+    Ok(counts)
+}
+
+/// Start an unpublished PostgreSQL container and wait until it accepts TCP connections.
+/// During first-time initialisation the server listens on the socket only, so a TCP
+/// check avoids racing the entrypoint's restart.
+async fn start_temporary(
+    docker_client: &Docker,
+    config: &Config,
+    name: &str,
+    image: &str,
+    volume: &str,
+) -> Result<()> {
+    docker::force_remove_container(docker_client, name).await?;
+    docker::create_postgres_container(
+        docker_client,
+        name,
+        image,
+        volume,
+        None,
+        &config.database.password,
+        &config.database.name,
+    )
+    .await?;
+    docker_client
+        .start_container(name, None::<bollard::container::StartContainerOptions<String>>)
+        .await
+        .with_context(|| format!("Failed to start {}", name))?;
+
+    for _ in 0..60 {
+        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+        let ready = docker::exec_in(
+            docker_client,
+            name,
+            vec!["pg_isready", "-h", "127.0.0.1", "-U", &config.database.user],
+            None,
+        )
+        .await;
+        if ready.is_ok() {
+            return Ok(());
+        }
+    }
+
+    bail!("{} did not become ready in time", name)
+}
+
+async fn entry_counts(docker_client: &Docker, config: &Config, container: &str) -> Result<BTreeMap<String, i64>> {
+    let output = docker::exec_in(
+        docker_client,
+        container,
+        vec![
+            "psql",
+            "-U",
+            &config.database.user,
+            "-d",
+            &config.database.name,
+            "-At",
+            "-F",
+            "\t",
+            "-c",
+            "SELECT entry_type, COUNT(*) FROM entries GROUP BY entry_type",
+        ],
+        None,
+    )
+    .await
+    .context("Failed to count entries")?;
+
+    let mut counts = BTreeMap::new();
+    for line in String::from_utf8_lossy(&output).lines() {
+        if let Some((entry_type, count)) = line.split_once('\t') {
+            counts.insert(entry_type.to_string(), count.trim().parse()?);
+        }
+    }
+    Ok(counts)
+}
+
+fn format_counts(counts: &BTreeMap<String, i64>) -> String {
+    if counts.is_empty() {
+        return "0 entries".to_string();
+    }
+    counts
+        .iter()
+        .map(|(entry_type, count)| format!("{} {}", count, entry_type))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Recreate the main container on `new_volume` with the new image and wait for it.
+async fn swap_volume(docker_client: &Docker, config: &Config, new_volume: &str) -> Result<()> {
+    println!("Recreating {} on {}...", docker::CONTAINER_NAME, docker::IMAGE_NAME);
+    docker::remove_container(docker_client).await?;
+    docker::create_container(docker_client, new_volume, &config.database.password, &config.database.name).await?;
+    docker::start_container(docker_client).await?;
+    docker::wait_for_postgres(config).await
+}
+
+/// Put the main container back on `old_volume` with the old image after a failed
+/// swap, stopped; `rollback` starts it again if it was running.
+async fn recreate_original(docker_client: &Docker, config: &Config, old_image: &str, old_volume: &str) {
+    println!("Recreating {} on {}...", docker::CONTAINER_NAME, old_volume);
+    let recreated = async {
+        docker::force_remove_container(docker_client, docker::CONTAINER_NAME).await?;
+        docker::create_postgres_container(
+            docker_client,
+            docker::CONTAINER_NAME,
+            old_image,
+            old_volume,
+            Some(docker::HOST_PORT),
+            &config.database.password,
+            &config.database.name,
+        )
+        .await
+    };
+    if let Err(e) = recreated.await {
+        eprintln!("Warning: {:#}", e);
+    }
+}
+
+/// Undo a failed upgrade: remove the temporary containers and the new volume, and
+/// restart the original container. Each step is attempted even if an earlier one
+/// fails; failures are printed so the error that caused the rollback is the one
+/// returned.
+async fn rollback(docker_client: &Docker, new_volume: &str, status: docker::ContainerStatus) {
+    for name in [OLD_CONTAINER, NEW_CONTAINER] {
+        if let Err(e) = docker::force_remove_container(docker_client, name).await {
+            eprintln!("Warning: {:#}", e);
+        }
+    }
+    if docker_client.inspect_volume(new_volume).await.is_ok() {
+        if let Err(e) = docker::remove_volume(docker_client, new_volume).await {
+            eprintln!("Warning: could not remove {}: {:#}", new_volume, e);
+        }
+    }
+    if let Err(e) = restart_original(docker_client, status).await {
+        eprintln!("Warning: {:#}. Start it with 'elastic-claude start'.", e);
+    }
+}
+
+async fn restart_original(docker_client: &Docker, status: docker::ContainerStatus) -> Result<()> {
+    if status == docker::ContainerStatus::Running {
+        println!("Restarting elastic-claude on the original volume...");
+        docker::start_container(docker_client).await?;
+    }
+    Ok(())
+}
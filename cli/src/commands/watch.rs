@@ -0,0 +1,372 @@
+use anyhow::{bail, Context, Result};
+use notify::event::{ModifyKind, RenameMode};
+use notify::{EventKind, RecursiveMode};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, DebouncedEvent};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use tokio_postgres::Client;
+use crate::config::Config;
+use crate::entries::{self, Upsert};
+use crate::{db, extract, systemd, versions};
+
+const SERVICE_UNIT: &str = "elastic-claude-watch.service";
+
+pub struct WatchOptions {
+    pub patterns: Vec<String>,
+    pub entry_type: String,
+    pub metadata: Option<String>,
+    pub on_delete: String,
+    pub debounce_ms: u64,
+    pub install_service: bool,
+    pub uninstall_service: bool,
+}
+
+/// A watched directory and, for glob arguments, the pattern files must match
+struct Target {
+    root: PathBuf,
+    pattern: Option<glob::Pattern>,
+}
+
+impl Target {
+    fn matches(&self, path: &Path) -> bool {
+        path.starts_with(&self.root)
+            && !path.components().any(|c| c == Component::Normal(".git".as_ref()))
+            && self.pattern.as_ref().is_none_or(|p| p.matches_path(path))
+    }
+}
+
+struct Watcher {
+    config: Config,
+    client: Client,
+    targets: Vec<Target>,
+    entry_type: String,
+    metadata: serde_json::Value,
+    on_delete: String,
+}
+
+pub async fn run(options: WatchOptions) -> Result<()> {
+    if options.uninstall_service {
+        systemd::uninstall(&[SERVICE_UNIT], SERVICE_UNIT)?;
+        println!("✓ Watch service removed");
+        return Ok(());
+    }
+
+    if options.patterns.is_empty() {
+        bail!("Specify at least one path or glob pattern to watch");
+    }
+
+    let targets = options
+        .patterns
+        .iter()
+        .map(|p| parse_target(p))
+        .collect::<Result<Vec<_>>>()?;
+
+    if options.install_service {
+        return install_service(&options, &targets);
+    }
+
+    let metadata: serde_json::Value = match &options.metadata {
+        Some(m) => serde_json::from_str(m).context("Invalid JSON in metadata")?,
+        None => serde_json::json!({}),
+    };
+
+    let config = Config::load().context("elastic-claude not initialized. Run 'elastic-claude init' first.")?;
+    let client = db::connect(&config).await?;
+
+    let mut watcher = Watcher {
+        config,
+        client,
+        targets,
+        entry_type: options.entry_type,
+        metadata,
+        on_delete: options.on_delete,
+    };
+
+    // Bring the index up to date with edits made while nothing was watching
+    watcher.initial_sync().await?;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut debouncer = new_debouncer(
+        Duration::from_millis(options.debounce_ms),
+        None,
+        move |result: DebounceEventResult| {
+            let _ = tx.send(result);
+        },
+    )
+    .context("Failed to create filesystem watcher")?;
+
+    for target in &watcher.targets {
+        debouncer
+            .watch(&target.root, RecursiveMode::Recursive)
+            .with_context(|| format!("Failed to watch {}", target.root.display()))?;
+        println!("Watching {}", target.root.display());
+    }
+    println!("Press Ctrl+C to stop.\n");
+
+    loop {
+        tokio::select! {
+            Some(result) = rx.recv() => match result {
+                Ok(events) => {
+                    for event in events {
+                        if let Err(e) = watcher.handle(&event).await {
+                            log(&format!("error: {:#}", e));
+                        }
+                    }
+                }
+                Err(errors) => {
+                    for e in errors {
+                        log(&format!("watch error: {}", e));
+                    }
+                }
+            },
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    println!("\nStopped watching");
+    Ok(())
+}
+
+/// Split an argument into the directory to watch and an optional glob pattern.
+/// Paths are resolved the way `entries::stored_path` resolves them, so events
+/// name files the same way `add -p` and `ingest` do.
+fn parse_target(arg: &str) -> Result<Target> {
+    let cwd = std::env::current_dir().context("Could not get current directory")?;
+    let path: PathBuf = cwd.join(arg).components().collect();
+
+    if path.is_dir() {
+        return Ok(Target { root: std::fs::canonicalize(&path)?, pattern: None });
+    }
+
+    if path.is_file() {
+        let path = std::fs::canonicalize(&path)?;
+        let pattern = glob::Pattern::new(&glob::Pattern::escape(&path.to_string_lossy()))?;
+        let root = path.parent().map(Path::to_path_buf).unwrap_or(cwd);
+        return Ok(Target { root, pattern: Some(pattern) });
+    }
+
+    // Watch the deepest directory that has no glob characters
+    let components: Vec<_> = path.components().collect();
+    let split = components
+        .iter()
+        .position(|c| c.as_os_str().to_string_lossy().contains(['*', '?', '[']))
+        .unwrap_or(components.len());
+    let root: PathBuf = components[..split].iter().collect();
+
+    if !root.is_dir() {
+        bail!("Nothing to watch at {}", arg);
+    }
+
+    let root = std::fs::canonicalize(&root)?;
+    let rest: PathBuf = components[split..].iter().collect();
+    let pattern = format!(
+        "{}{}{}",
+        glob::Pattern::escape(&root.to_string_lossy()),
+        std::path::MAIN_SEPARATOR,
+        rest.to_string_lossy()
+    );
+    let pattern = glob::Pattern::new(&pattern).context("Invalid glob pattern")?;
+    Ok(Target { root, pattern: Some(pattern) })
+}
+
+impl Watcher {
+    fn matches(&self, path: &Path) -> bool {
+        self.targets.iter().any(|t| t.matches(path))
+    }
+
+    async fn initial_sync(&mut self) -> Result<()> {
+        let mut files = Vec::new();
+        for target in &self.targets {
+            collect_files(&target.root, &mut files);
+        }
+        files.retain(|f| self.matches(f));
+        files.sort();
+        files.dedup();
+
+        println!("Syncing {} files...", files.len());
+        for file in files {
+            if let Err(e) = self.sync_file(&file).await {
+                log(&format!("error: {:#}", e));
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle(&mut self, event: &DebouncedEvent) -> Result<()> {
+        if let EventKind::Modify(ModifyKind::Name(RenameMode::Both)) = event.kind {
+            if let [from, to] = event.paths.as_slice() {
+                return self.rename(from, to).await;
+            }
+        }
+
+        if !matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+        ) {
+            return Ok(());
+        }
+
+        for path in &event.paths {
+            if !self.matches(path) {
+                continue;
+            }
+            if path.is_file() {
+                self.sync_file(path).await?;
+            } else if !path.exists() {
+                self.file_removed(path).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn sync_file(&mut self, path: &Path) -> Result<()> {
+        let document = match extract::read_file(path) {
+            Ok(document) => document,
+            Err(e) => {
+                log(&format!("skipped {}: {:#}", path.display(), e));
+                return Ok(());
+            }
+        };
+
+        // Explicit --metadata wins over metadata extracted from the file
+        let mut metadata = document.metadata.clone();
+        if let (Some(target), Some(explicit)) = (metadata.as_object_mut(), self.metadata.as_object()) {
+            target.extend(explicit.clone());
+        }
+
+        let file_path = path.to_string_lossy();
+        self.ensure_connected().await?;
+        match entries::upsert_file(&self.client, &self.entry_type, &file_path, &document.content, &metadata, document.blob(), None)
+            .await?
+        {
+            Upsert::Inserted(id) => log(&format!("added #{} {}", id, file_path)),
+            Upsert::Updated(id) => {
+                versions::prune(&self.client, Some(id), self.config.versions.keep).await?;
+                log(&format!("updated #{} {}", id, file_path))
+            }
+            Upsert::Unchanged => {}
+            Upsert::Trashed(id) => log(&format!("skipped #{} {} (in the trash)", id, file_path)),
+        }
+        Ok(())
+    }
+
+    async fn rename(&mut self, from: &Path, to: &Path) -> Result<()> {
+        let from_str = from.to_string_lossy();
+        let to_str = to.to_string_lossy();
+        self.ensure_connected().await?;
+
+        if to.is_dir() {
+            let moved = entries::rename_dir(&self.client, &from_str, &to_str).await?;
+            if moved > 0 {
+                log(&format!("moved {} entries {} -> {}", moved, from_str, to_str));
+            }
+            return Ok(());
+        }
+
+        match (self.matches(from), self.matches(to)) {
+            (true, true) => {
+                let moved = entries::rename_file(&self.client, &from_str, &to_str).await?;
+                if moved > 0 {
+                    log(&format!("renamed {} -> {}", from_str, to_str));
+                }
+                self.sync_file(to).await
+            }
+            (false, true) => self.sync_file(to).await,
+            (true, false) => self.file_removed(from).await,
+            (false, false) => Ok(()),
+        }
+    }
+
+    async fn file_removed(&mut self, path: &Path) -> Result<()> {
+        let file_path = path.to_string_lossy();
+        self.ensure_connected().await?;
+
+        let affected = match self.on_delete.as_str() {
+            "mark" => entries::mark_file_deleted(&self.client, &file_path).await?,
+            "remove" => entries::trash_file(&self.client, &file_path).await?,
+            _ => 0,
+        };
+        if affected > 0 {
+            let action = if self.on_delete == "remove" { "moved to trash" } else { "marked deleted" };
+            log(&format!("{} {}", action, file_path));
+        }
+        Ok(())
+    }
+
+    /// Reconnect if the database went away, e.g. after a container restart.
+    async fn ensure_connected(&mut self) -> Result<()> {
+        if self.client.is_closed() {
+            log("database connection lost, reconnecting");
+            self.client = db::connect(&self.config).await?;
+        }
+        Ok(())
+    }
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            if entry.file_name() != ".git" {
+                collect_files(&path, files);
+            }
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+}
+
+fn log(message: &str) {
+    println!("[{}] {}", chrono::Local::now().format("%H:%M:%S"), message);
+}
+
+fn install_service(options: &WatchOptions, targets: &[Target]) -> Result<()> {
+    let mut args = vec![systemd::exec_path()?, "watch".to_string()];
+
+    // Absolute patterns so the service does not depend on its working directory
+    for target in targets {
+        match &target.pattern {
+            Some(pattern) => args.push(pattern.as_str().to_string()),
+            None => args.push(target.root.to_string_lossy().to_string()),
+        }
+    }
+    args.extend([
+        "--type".to_string(),
+        options.entry_type.clone(),
+        "--on-delete".to_string(),
+        options.on_delete.clone(),
+        "--debounce-ms".to_string(),
+        options.debounce_ms.to_string(),
+    ]);
+    if let Some(metadata) = &options.metadata {
+        args.extend(["--metadata".to_string(), metadata.clone()]);
+    }
+
+    let exec_start = args
+        .iter()
+        .map(|a| systemd::quote(a))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let service = format!(
+        "[Unit]\n\
+         Description=elastic-claude file watcher\n\
+         \n\
+         [Service]\n\
+         ExecStart={}\n\
+         Restart=on-failure\n\
+         RestartSec=10\n\
+         \n\
+         [Install]\n\
+         WantedBy=default.target\n",
+        exec_start
+    );
+
+    systemd::install(&[(SERVICE_UNIT, service)], SERVICE_UNIT)?;
+    println!("Follow the log with: journalctl --user -u {} -f", SERVICE_UNIT);
+    Ok(())
+}
@@ -1,10 +1,25 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
     pub database: DatabaseConfig,
+    #[serde(default)]
+    pub backup: BackupConfig,
+    #[serde(default)]
+    pub attachments: AttachmentConfig,
+    #[serde(default)]
+    pub summarizer: SummarizerConfig,
+    #[serde(default)]
+    pub versions: VersionConfig,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    #[serde(default)]
+    pub server: ServerConfig,
+    #[serde(default)]
+    pub search: SearchConfig,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -16,6 +31,187 @@ pub struct DatabaseConfig {
     pub password: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupConfig {
+    /// Directory for dumps (defaults to ~/.elastic-claude/backups)
+    pub dir: Option<PathBuf>,
+    /// Number of most recent dumps to keep (0 keeps all)
+    pub keep_last: usize,
+    /// Delete dumps older than this many days
+    pub max_age_days: Option<u64>,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            keep_last: 10,
+            max_age_days: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AttachmentConfig {
+    /// Largest file accepted by `add --attach`, in bytes
+    pub max_size: u64,
+    /// Attachments larger than this many bytes go to the blob store instead of the
+    /// database (unset keeps everything in the database)
+    pub store_threshold: Option<u64>,
+    /// Blob store directory (defaults to ~/.elastic-claude/blobs)
+    pub store_dir: Option<PathBuf>,
+}
+
+impl Default for AttachmentConfig {
+    fn default() -> Self {
+        Self {
+            max_size: 25 * 1024 * 1024,
+            store_threshold: None,
+            store_dir: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SummarizerConfig {
+    /// `textrank` (offline) or `openai` for an OpenAI-compatible endpoint
+    pub backend: String,
+    /// Base URL of the endpoint, e.g. http://localhost:8080/v1 (llama.cpp) or
+    /// http://localhost:11434/v1 (Ollama)
+    pub endpoint: String,
+    /// Model name sent with each request
+    pub model: Option<String>,
+    /// Sent as a bearer token when set
+    pub api_key: Option<String>,
+    /// Longer inputs are condensed with TextRank before they are sent
+    pub max_input_chars: usize,
+    pub timeout_secs: u64,
+}
+
+impl Default for SummarizerConfig {
+    fn default() -> Self {
+        Self {
+            backend: "textrank".to_string(),
+            endpoint: "http://localhost:8080/v1".to_string(),
+            model: None,
+            api_key: None,
+            max_input_chars: 24_000,
+            timeout_secs: 120,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VersionConfig {
+    /// Earlier versions kept per entry (0 keeps all)
+    pub keep: usize,
+}
+
+impl Default for VersionConfig {
+    fn default() -> Self {
+        Self { keep: 20 }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionConfig {
+    /// `gc` purges entries that have been in the trash this many days (unset keeps
+    /// them until `trash empty`)
+    pub trash_days: Option<u32>,
+    /// Rules `gc` uses to move entries to the trash
+    pub rules: Vec<RetentionRule>,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            trash_days: Some(30),
+            rules: Vec::new(),
+        }
+    }
+}
+
+/// Entries matching `type` and `project` are removed when older than
+/// `older_than_days` and/or beyond the newest `keep_last` per project (both must
+/// hold when both are set). Entries with `metadata.pinned: true` are never removed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RetentionRule {
+    #[serde(rename = "type")]
+    pub entry_type: Option<String>,
+    pub project: Option<String>,
+    pub older_than_days: Option<u32>,
+    pub keep_last: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    /// Address `serve` listens on unless --bind is given
+    pub bind: String,
+    /// Bearer token required by the API (generated by the first `serve` when unset)
+    pub token: Option<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind: "127.0.0.1:8765".to_string(),
+            token: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    /// Text search configuration for entries whose language is not detected or given
+    pub language: String,
+    /// How full-text matches are ordered (overridable per query)
+    pub ranking: RankingConfig,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            language: "english".to_string(),
+            ranking: RankingConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RankingConfig {
+    /// `ts_rank`, or `ts_rank_cd` (cover density) to favour query words close together
+    pub function: String,
+    /// ts_rank normalization bit mask, e.g. 1 divides by 1 + log(length), 32 scales to 0-1
+    pub normalization: i32,
+    /// What a match is worth in each section: D, C (body), B (headings), A (title)
+    pub weights: [f32; 4],
+    /// Scores halve for every this many days since the entry was created (unset
+    /// ignores age)
+    pub half_life_days: Option<f64>,
+    /// Scores are multiplied by the weight of the entry's type (1 when not listed)
+    pub type_weights: BTreeMap<String, f64>,
+}
+
+impl Default for RankingConfig {
+    fn default() -> Self {
+        Self {
+            function: "ts_rank".to_string(),
+            normalization: 0,
+            weights: [0.1, 0.2, 0.4, 1.0],
+            half_life_days: None,
+            type_weights: BTreeMap::new(),
+        }
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
@@ -26,6 +222,13 @@ impl Default for Config {
                 user: "postgres".to_string(),
                 password: "elastic".to_string(),
             },
+            backup: BackupConfig::default(),
+            attachments: AttachmentConfig::default(),
+            summarizer: SummarizerConfig::default(),
+            versions: VersionConfig::default(),
+            retention: RetentionConfig::default(),
+            server: ServerConfig::default(),
+            search: SearchConfig::default(),
         }
     }
 }
@@ -40,6 +243,20 @@ impl Config {
         Ok(Self::config_dir()?.join("config.yaml"))
     }
 
+    pub fn backup_dir(&self) -> Result<PathBuf> {
+        match &self.backup.dir {
+            Some(dir) => Ok(dir.clone()),
+            None => Ok(Self::config_dir()?.join("backups")),
+        }
+    }
+
+    pub fn blob_store_dir(&self) -> Result<PathBuf> {
+        match &self.attachments.store_dir {
+            Some(dir) => Ok(dir.clone()),
+            None => Ok(Self::config_dir()?.join("blobs")),
+        }
+    }
+
     pub fn skill_dir() -> Result<PathBuf> {
         let home = dirs::home_dir().context("Could not find home directory")?;
         Ok(home.join(".claude").join("skills").join("elastic-claude"))
@@ -59,6 +276,14 @@ impl Config {
         }
         let contents = serde_yaml::to_string(self)?;
         std::fs::write(&path, contents)?;
+
+        // The config holds the database password
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        }
+
         Ok(())
     }
 
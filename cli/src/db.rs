@@ -0,0 +1,180 @@
+use anyhow::{Context, Result};
+use tokio_postgres::Client;
+use crate::config::Config;
+
+pub struct Migration {
+    pub version: i32,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+/// Schema migrations, applied in order. Version 1 is the original `init.sql`.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial schema",
+        sql: include_str!("../../docker/init.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "updated_at",
+        sql: include_str!("../../docker/migrations/002_updated_at.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "code identifiers",
+        sql: include_str!("../../docker/migrations/003_code_identifiers.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "blob store",
+        sql: include_str!("../../docker/migrations/004_blob_store.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "entry links",
+        sql: include_str!("../../docker/migrations/005_entry_links.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "entry versions",
+        sql: include_str!("../../docker/migrations/006_entry_versions.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "soft delete",
+        sql: include_str!("../../docker/migrations/007_soft_delete.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "fingerprints",
+        sql: include_str!("../../docker/migrations/008_fingerprints.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "trigram search",
+        sql: include_str!("../../docker/migrations/009_trigram_search.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "entry language",
+        sql: include_str!("../../docker/migrations/010_entry_language.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "synonyms",
+        sql: include_str!("../../docker/migrations/011_synonyms.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "weighted search",
+        sql: include_str!("../../docker/migrations/012_weighted_search.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "version metadata",
+        sql: include_str!("../../docker/migrations/013_version_metadata.sql"),
+    },
+    Migration {
+        version: 14,
+        name: "vocabulary refresh",
+        sql: include_str!("../../docker/migrations/014_vocabulary_refresh.sql"),
+    },
+];
+
+pub fn latest_version() -> i32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+pub async fn connect(config: &Config) -> Result<Client> {
+    let (client, connection) =
+        tokio_postgres::connect(&config.connection_string(), tokio_postgres::NoTls)
+            .await
+            .context("Failed to connect to database")?;
+
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("Database connection error: {}", e);
+        }
+    });
+
+    // Entries with no detected or explicit language get the session default
+    client
+        .query_one(
+            "SELECT set_config('default_text_search_config', $1, false)",
+            &[&config.search.language],
+        )
+        .await
+        .with_context(|| format!("Invalid search.language '{}' in config.yaml", config.search.language))?;
+
+    Ok(client)
+}
+
+/// Current schema version, or `None` if the database has never been migrated.
+pub async fn schema_version(client: &Client) -> Result<Option<i32>> {
+    let row = client
+        .query_one(
+            r#"
+            SELECT to_regclass('schema_migrations') IS NOT NULL,
+                   to_regclass('entries') IS NOT NULL
+            "#,
+            &[],
+        )
+        .await?;
+
+    let has_migrations: bool = row.get(0);
+    let has_entries: bool = row.get(1);
+
+    if !has_migrations {
+        // Databases created before schema versioning have the initial schema only
+        return Ok(has_entries.then_some(1));
+    }
+
+    let row = client
+        .query_one("SELECT MAX(version) FROM schema_migrations", &[])
+        .await?;
+    Ok(row.get(0))
+}
+
+/// Apply all pending migrations, each in its own transaction.
+pub async fn migrate(client: &mut Client) -> Result<()> {
+    let current = schema_version(client).await?.unwrap_or(0);
+
+    client
+        .batch_execute(
+            r#"
+            CREATE TABLE IF NOT EXISTS schema_migrations (
+                version INT PRIMARY KEY,
+                name TEXT NOT NULL,
+                applied_at TIMESTAMP DEFAULT NOW()
+            )
+            "#,
+        )
+        .await
+        .context("Failed to create schema_migrations table")?;
+
+    if current > 0 {
+        client
+            .execute(
+                "INSERT INTO schema_migrations (version, name) VALUES (1, $1) ON CONFLICT DO NOTHING",
+                &[&MIGRATIONS[0].name],
+            )
+            .await?;
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let tx = client.transaction().await?;
+        tx.batch_execute(migration.sql)
+            .await
+            .with_context(|| format!("Migration {} ({}) failed", migration.version, migration.name))?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name) VALUES ($1, $2)",
+            &[&migration.version, &migration.name],
+        )
+        .await?;
+        tx.commit().await?;
+        println!("Applied migration {}: {}", migration.version, migration.name);
+    }
+
+    Ok(())
+}
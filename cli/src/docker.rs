@@ -1,14 +1,34 @@
-use anyhow::{Context, Result};
-use bollard::container::{Config, CreateContainerOptions, ListContainersOptions, StartContainerOptions, StopContainerOptions};
+use anyhow::{bail, Context, Result};
+use bollard::container::{
+    Config, CreateContainerOptions, ListContainersOptions, LogOutput, LogsOptions, RemoveContainerOptions,
+    StartContainerOptions, StopContainerOptions, WaitContainerOptions,
+};
+use bollard::exec::{CreateExecOptions, StartExecResults};
 use bollard::image::CreateImageOptions;
 use bollard::volume::CreateVolumeOptions;
 use bollard::Docker;
 use futures_util::StreamExt;
 use std::collections::HashMap;
+use tokio::io::AsyncWriteExt;
 
 pub const CONTAINER_NAME: &str = "elastic-claude-db";
 pub const VOLUME_NAME: &str = "elastic-claude-data";
 pub const IMAGE_NAME: &str = "postgres:16-alpine";
+/// Major version of `IMAGE_NAME`; bump both together
+pub const POSTGRES_MAJOR: u32 = 16;
+pub const DATA_DIR: &str = "/var/lib/postgresql/data";
+/// Port the main container publishes on localhost
+pub const HOST_PORT: &str = "5433";
+
+/// Data volume for a PostgreSQL major version. Installations on 16 keep the
+/// original unsuffixed volume; later majors get their own so upgrades can roll back.
+pub fn volume_name(major: u32) -> String {
+    if major <= 16 {
+        VOLUME_NAME.to_string()
+    } else {
+        format!("{}-pg{}", VOLUME_NAME, major)
+    }
+}
 
 pub async fn connect() -> Result<Docker> {
     Docker::connect_with_local_defaults().context(
@@ -17,11 +37,11 @@ pub async fn connect() -> Result<Docker> {
     )
 }
 
-pub async fn pull_image(docker: &Docker) -> Result<()> {
-    println!("Pulling {}...", IMAGE_NAME);
+pub async fn pull_image(docker: &Docker, image: &str) -> Result<()> {
+    println!("Pulling {}...", image);
 
     let options = CreateImageOptions {
-        from_image: IMAGE_NAME,
+        from_image: image,
         ..Default::default()
     };
 
@@ -34,11 +54,11 @@ pub async fn pull_image(docker: &Docker) -> Result<()> {
     Ok(())
 }
 
-pub async fn create_volume(docker: &Docker) -> Result<()> {
-    println!("Creating volume {}...", VOLUME_NAME);
+pub async fn create_volume(docker: &Docker, name: &str) -> Result<()> {
+    println!("Creating volume {}...", name);
 
     let options = CreateVolumeOptions {
-        name: VOLUME_NAME,
+        name,
         ..Default::default()
     };
 
@@ -47,27 +67,40 @@ pub async fn create_volume(docker: &Docker) -> Result<()> {
     Ok(())
 }
 
-pub async fn create_container(docker: &Docker, password: &str, db_name: &str) -> Result<()> {
-    println!("Creating container {}...", CONTAINER_NAME);
+pub async fn create_container(docker: &Docker, volume: &str, password: &str, db_name: &str) -> Result<()> {
+    create_postgres_container(docker, CONTAINER_NAME, IMAGE_NAME, volume, Some(HOST_PORT), password, db_name).await
+}
+
+/// Create a PostgreSQL container on `volume`. `host_port` publishes 5432 on localhost.
+pub async fn create_postgres_container(
+    docker: &Docker,
+    name: &str,
+    image: &str,
+    volume: &str,
+    host_port: Option<&str>,
+    password: &str,
+    db_name: &str,
+) -> Result<()> {
+    println!("Creating container {}...", name);
 
     let options = CreateContainerOptions {
-        name: CONTAINER_NAME,
+        name,
         ..Default::default()
     };
 
     let host_config = bollard::service::HostConfig {
-        port_bindings: Some({
+        port_bindings: host_port.map(|port| {
             let mut map = HashMap::new();
             map.insert(
                 "5432/tcp".to_string(),
                 Some(vec![bollard::service::PortBinding {
                     host_ip: Some("127.0.0.1".to_string()),
-                    host_port: Some("5433".to_string()),
+                    host_port: Some(port.to_string()),
                 }]),
             );
             map
         }),
-        binds: Some(vec![format!("{}:/var/lib/postgresql/data", VOLUME_NAME)]),
+        binds: Some(vec![format!("{}:{}", volume, DATA_DIR)]),
         ..Default::default()
     };
 
@@ -75,7 +108,7 @@ pub async fn create_container(docker: &Docker, password: &str, db_name: &str) ->
     let env_db_name = format!("POSTGRES_DB={}", db_name);
 
     let config = Config {
-        image: Some(IMAGE_NAME),
+        image: Some(image),
         env: Some(vec![&env_password, &env_db_name]),
         host_config: Some(host_config),
         ..Default::default()
@@ -114,9 +147,22 @@ pub async fn remove_container(docker: &Docker) -> Result<()> {
     Ok(())
 }
 
-pub async fn remove_volume(docker: &Docker) -> Result<()> {
+/// Force-remove a container by name, ignoring containers that do not exist.
+pub async fn force_remove_container(docker: &Docker, name: &str) -> Result<()> {
+    let options = RemoveContainerOptions {
+        force: true,
+        ..Default::default()
+    };
+
+    match docker.remove_container(name, Some(options)).await {
+        Ok(()) | Err(bollard::errors::Error::DockerResponseServerError { status_code: 404, .. }) => Ok(()),
+        Err(e) => Err(e).with_context(|| format!("Failed to remove container {}", name)),
+    }
+}
+
+pub async fn remove_volume(docker: &Docker, name: &str) -> Result<()> {
     docker
-        .remove_volume(VOLUME_NAME, None)
+        .remove_volume(name, None)
         .await
         .context("Failed to remove volume")?;
     Ok(())
@@ -151,6 +197,77 @@ pub async fn get_container_status(docker: &Docker) -> Result<ContainerStatus> {
     Ok(ContainerStatus::NotFound)
 }
 
+/// Name of the volume mounted as the data directory of the main container.
+pub async fn mounted_volume(docker: &Docker) -> Result<Option<String>> {
+    let info = match docker.inspect_container(CONTAINER_NAME, None).await {
+        Ok(info) => info,
+        Err(bollard::errors::Error::DockerResponseServerError { status_code: 404, .. }) => return Ok(None),
+        Err(e) => return Err(e).context("Failed to inspect container"),
+    };
+
+    Ok(info
+        .mounts
+        .unwrap_or_default()
+        .into_iter()
+        .find(|m| m.destination.as_deref() == Some(DATA_DIR))
+        .and_then(|m| m.name))
+}
+
+/// Read `PG_VERSION` from a data volume using a throwaway container.
+pub async fn read_pg_version(docker: &Docker, volume: &str) -> Result<u32> {
+    let name = format!("{}-probe", CONTAINER_NAME);
+    force_remove_container(docker, &name).await?;
+
+    let version_file = format!("{}/PG_VERSION", DATA_DIR);
+    let bind = format!("{}:{}:ro", volume, DATA_DIR);
+    let config = Config {
+        image: Some(IMAGE_NAME),
+        entrypoint: Some(vec!["cat"]),
+        cmd: Some(vec![version_file.as_str()]),
+        host_config: Some(bollard::service::HostConfig {
+            binds: Some(vec![bind]),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    docker
+        .create_container(Some(CreateContainerOptions { name: name.as_str(), ..Default::default() }), config)
+        .await
+        .context("Failed to create probe container")?;
+    docker
+        .start_container(&name, None::<StartContainerOptions<String>>)
+        .await
+        .context("Failed to start probe container")?;
+
+    let mut wait = docker.wait_container(&name, None::<WaitContainerOptions<String>>);
+    while let Some(result) = wait.next().await {
+        if let Err(e) = result {
+            force_remove_container(docker, &name).await?;
+            return Err(e).with_context(|| format!("Could not read PG_VERSION from volume {}", volume));
+        }
+    }
+
+    let mut output = String::new();
+    let mut logs = docker.logs(
+        &name,
+        Some(LogsOptions::<String> {
+            stdout: true,
+            ..Default::default()
+        }),
+    );
+    while let Some(chunk) = logs.next().await {
+        output.push_str(&chunk?.to_string());
+    }
+
+    force_remove_container(docker, &name).await?;
+
+    output
+        .trim()
+        .parse()
+        .with_context(|| format!("Unexpected PG_VERSION in volume {}: {:?}", volume, output.trim()))
+}
+
 pub async fn wait_for_postgres(config: &crate::config::Config) -> Result<()> {
     println!("Waiting for PostgreSQL to be ready...");
 
@@ -169,3 +286,58 @@ pub async fn wait_for_postgres(config: &crate::config::Config) -> Result<()> {
 
     Ok(())
 }
+
+/// Run a command inside the container and return its stdout.
+/// `stdin`, when given, is written to the process and then closed.
+pub async fn exec(docker: &Docker, cmd: Vec<&str>, stdin: Option<&[u8]>) -> Result<Vec<u8>> {
+    exec_in(docker, CONTAINER_NAME, cmd, stdin).await
+}
+
+pub async fn exec_in(docker: &Docker, container: &str, cmd: Vec<&str>, stdin: Option<&[u8]>) -> Result<Vec<u8>> {
+    let exec = docker
+        .create_exec(
+            container,
+            CreateExecOptions {
+                cmd: Some(cmd.clone()),
+                attach_stdin: Some(stdin.is_some()),
+                attach_stdout: Some(true),
+                attach_stderr: Some(true),
+                tty: Some(false),
+                ..Default::default()
+            },
+        )
+        .await
+        .context("Failed to create exec in container")?;
+
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+
+    if let StartExecResults::Attached { mut output, mut input } =
+        docker.start_exec(&exec.id, None).await.context("Failed to start exec")?
+    {
+        if let Some(data) = stdin {
+            input.write_all(data).await.context("Failed to write to exec stdin")?;
+            input.shutdown().await?;
+        }
+
+        while let Some(chunk) = output.next().await {
+            match chunk.context("Failed to read exec output")? {
+                LogOutput::StdOut { message } => stdout.extend_from_slice(&message),
+                LogOutput::StdErr { message } => stderr.extend_from_slice(&message),
+                _ => {}
+            }
+        }
+    }
+
+    let inspect = docker.inspect_exec(&exec.id).await?;
+    if let Some(code) = inspect.exit_code.filter(|c| *c != 0) {
+        bail!(
+            "'{}' exited with code {}: {}",
+            cmd.join(" "),
+            code,
+            String::from_utf8_lossy(&stderr).trim()
+        );
+    }
+
+    Ok(stdout)
+}
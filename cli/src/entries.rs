@@ -0,0 +1,613 @@
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+use tokio_postgres::{Client, GenericClient};
+use crate::{code, fingerprint, language, links};
+use crate::keywords::{self, Corpus};
+
+/// Columns derived from an entry's content when it is written
+struct Derived {
+    metadata: serde_json::Value,
+    identifiers: Option<Vec<String>>,
+    identifier_words: Option<String>,
+    minhash: Option<Vec<i32>>,
+    shingle_count: Option<i32>,
+    /// Text search configuration: explicit, detected, or None for the default
+    language: Option<String>,
+}
+
+/// Every entry gets a near-duplicate fingerprint, and prose its detected text
+/// search language unless one is given. Code entries also get their language,
+/// symbols and identifier terms extracted; explicit metadata wins over extracted keys.
+fn derive(
+    entry_type: &str,
+    file_path: Option<&str>,
+    content: &str,
+    metadata: &serde_json::Value,
+    language: Option<&str>,
+) -> Derived {
+    let (minhash, shingle_count) = fingerprint::compute(content).map(|f| (f.minhash, f.shingles)).unzip();
+    if entry_type != "code" {
+        return Derived {
+            metadata: metadata.clone(),
+            identifiers: None,
+            identifier_words: None,
+            minhash,
+            shingle_count,
+            language: language.or_else(|| language::detect(content)).map(str::to_string),
+        };
+    }
+
+    let mut extracted = serde_json::Map::new();
+    if let Some(language) = code::detect_language(file_path, content) {
+        extracted.insert("language".to_string(), language.into());
+        let symbols = code::extract_symbols(language, content);
+        if !symbols.is_empty() {
+            extracted.insert("symbols".to_string(), serde_json::json!(symbols));
+        }
+    }
+    if let Some(explicit) = metadata.as_object() {
+        extracted.extend(explicit.clone());
+    }
+
+    let terms = code::identifier_terms(content);
+    Derived {
+        metadata: serde_json::Value::Object(extracted),
+        identifiers: Some(terms.raw),
+        identifier_words: Some(terms.words),
+        minhash,
+        shingle_count,
+        language: language.map(str::to_string),
+    }
+}
+
+/// Original bytes of a file whose text was extracted into `content`
+pub struct Blob<'a> {
+    pub data: &'a [u8],
+    pub mime_type: &'a str,
+}
+
+/// Fill `metadata.keywords` for prose entries unless it was given explicitly. Code
+/// entries are described by their symbols instead.
+async fn add_keywords(
+    client: &Client,
+    entry_type: &str,
+    content: &str,
+    explicit: &serde_json::Value,
+    derived: &mut Derived,
+) -> Result<()> {
+    if entry_type == "code" || explicit.get("keywords").is_some() {
+        return Ok(());
+    }
+    let keywords = keywords::extract(client, &mut Corpus::default(), content, derived.language.as_deref()).await?;
+    if let Some(metadata) = derived.metadata.as_object_mut() {
+        if !keywords.is_empty() {
+            metadata.insert("keywords".to_string(), serde_json::json!(keywords));
+        }
+    }
+    Ok(())
+}
+
+pub async fn insert(
+    client: &Client,
+    entry_type: &str,
+    content: &str,
+    file_path: Option<&str>,
+    metadata: &serde_json::Value,
+    blob: Option<Blob<'_>>,
+    language: Option<&str>,
+) -> Result<i32> {
+    let (data, mime_type) = blob.map(|b| (b.data, b.mime_type)).unzip();
+    insert_row(client, entry_type, content, file_path, None, metadata, data, mime_type, None, language).await
+}
+
+/// Insert a `summary` entry. `source_id` links it to the entry it summarizes;
+/// roll-ups over several entries list them in `metadata.source_ids` instead.
+pub async fn insert_summary(
+    client: &Client,
+    content: &str,
+    source_id: Option<i32>,
+    metadata: &serde_json::Value,
+) -> Result<i32> {
+    insert_row(client, "summary", content, None, source_id, metadata, None, None, None, None).await
+}
+
+/// Replace the content of an entry and merge `metadata` into it. Prose gets the
+/// language detected for the new content.
+pub async fn update(client: &Client, id: i32, entry_type: &str, content: &str, metadata: &serde_json::Value) -> Result<()> {
+    let mut derived = derive(entry_type, None, content, metadata, None);
+    add_keywords(client, entry_type, content, metadata, &mut derived).await?;
+    client
+        .execute(
+            r#"
+            UPDATE entries
+            SET content = $2,
+                metadata = COALESCE(metadata, '{}') || $3,
+                identifiers = $4,
+                identifier_words = $5,
+                minhash = $6,
+                shingle_count = $7,
+                language = COALESCE($8::text::regconfig, language),
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+            &[
+                &id,
+                &content,
+                &derived.metadata,
+                &derived.identifiers,
+                &derived.identifier_words,
+                &derived.minhash,
+                &derived.shingle_count,
+                &derived.language,
+            ],
+        )
+        .await
+        .context("Failed to update entry")?;
+    Ok(())
+}
+
+/// Change the content, metadata and/or text search language of a live entry.
+/// `patch` is merged into the metadata; top-level `null` values remove keys. New
+/// content without `language` gets its detected language. Returns false if there
+/// is no live entry `id`.
+pub async fn edit(
+    client: &Client,
+    id: i32,
+    content: Option<&str>,
+    patch: Option<&serde_json::Value>,
+    language: Option<&str>,
+) -> Result<bool> {
+    let row = client
+        .query_opt(
+            "SELECT entry_type, content, metadata FROM entries WHERE id = $1 AND deleted_at IS NULL",
+            &[&id],
+        )
+        .await
+        .context("Failed to query entry")?;
+    let Some(row) = row else {
+        return Ok(false);
+    };
+    let entry_type: String = row.get(0);
+    let old_content: String = row.get(1);
+
+    let (mut removed, mut set) = (Vec::new(), serde_json::Map::new());
+    if let Some(patch) = patch {
+        let Some(patch) = patch.as_object() else {
+            bail!("Metadata must be a JSON object");
+        };
+        for (key, value) in patch {
+            if value.is_null() {
+                removed.push(key.clone());
+            } else {
+                set.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    let set = serde_json::Value::Object(set);
+
+    // Each case is a single UPDATE, so a failure leaves the entry as it was
+    let updated = match content.filter(|c| *c != old_content) {
+        Some(content) => {
+            let mut derived = derive(&entry_type, None, content, &set, language);
+            add_keywords(client, &entry_type, content, &set, &mut derived).await?;
+            client
+                .execute(
+                    r#"
+                    UPDATE entries
+                    SET content = $5,
+                        metadata = (COALESCE(metadata, '{}') - $2::text[]) || $3,
+                        language = COALESCE($4::text::regconfig, language),
+                        identifiers = $6,
+                        identifier_words = $7,
+                        minhash = $8,
+                        shingle_count = $9,
+                        updated_at = NOW()
+                    WHERE id = $1 AND deleted_at IS NULL
+                    "#,
+                    &[
+                        &id,
+                        &removed,
+                        &derived.metadata,
+                        &derived.language,
+                        &content,
+                        &derived.identifiers,
+                        &derived.identifier_words,
+                        &derived.minhash,
+                        &derived.shingle_count,
+                    ],
+                )
+                .await
+        }
+        None if patch.is_some() || language.is_some() => {
+            client
+                .execute(
+                    r#"
+                    UPDATE entries
+                    SET metadata = (COALESCE(metadata, '{}') - $2::text[]) || $3,
+                        language = COALESCE($4::text::regconfig, language),
+                        updated_at = NOW()
+                    WHERE id = $1 AND deleted_at IS NULL
+                    "#,
+                    &[&id, &removed, &set, &language],
+                )
+                .await
+        }
+        None => return Ok(true),
+    };
+    Ok(updated.context("Failed to update entry")? == 1)
+}
+
+/// Where an attachment's bytes are kept
+pub enum Attachment<'a> {
+    Inline(Blob<'a>),
+    /// In the blob store, by SHA-256
+    Stored { sha256: &'a str, mime_type: &'a str },
+}
+
+pub async fn insert_attachment(
+    client: &Client,
+    entry_type: &str,
+    description: &str,
+    file_path: Option<&str>,
+    metadata: &serde_json::Value,
+    attachment: Attachment<'_>,
+    language: Option<&str>,
+) -> Result<i32> {
+    match attachment {
+        Attachment::Inline(blob) => {
+            insert(client, entry_type, description, file_path, metadata, Some(blob), language).await
+        }
+        Attachment::Stored { sha256, mime_type } => {
+            insert_row(client, entry_type, description, file_path, None, metadata, None, Some(mime_type), Some(sha256), language)
+                .await
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn insert_row(
+    client: &Client,
+    entry_type: &str,
+    content: &str,
+    file_path: Option<&str>,
+    source_id: Option<i32>,
+    metadata: &serde_json::Value,
+    blob: Option<&[u8]>,
+    mime_type: Option<&str>,
+    blob_sha256: Option<&str>,
+    language: Option<&str>,
+) -> Result<i32> {
+    let mut derived = derive(entry_type, file_path, content, metadata, language);
+    add_keywords(client, entry_type, content, metadata, &mut derived).await?;
+    let row = client
+        .query_one(
+            r#"
+            INSERT INTO entries (
+                entry_type, content, file_path, source_id, metadata, identifiers, identifier_words,
+                blob, mime_type, blob_sha256, minhash, shingle_count, language
+            )
+            VALUES (
+                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12,
+                COALESCE($13::text::regconfig, get_current_ts_config())
+            )
+            RETURNING id
+            "#,
+            &[
+                &entry_type,
+                &content,
+                &file_path,
+                &source_id,
+                &derived.metadata,
+                &derived.identifiers,
+                &derived.identifier_words,
+                &blob,
+                &mime_type,
+                &blob_sha256,
+                &derived.minhash,
+                &derived.shingle_count,
+                &derived.language,
+            ],
+        )
+        .await
+        .context("Failed to insert entry")?;
+    let id = row.get(0);
+
+    if let Some(source_id) = source_id {
+        let link_type = if entry_type == "summary" { "summarizes" } else { "derived_from" };
+        links::add(client, id, source_id, link_type, true).await?;
+    }
+    if entry_type == "chat" {
+        links::detect_mentions(client, id, content).await?;
+    }
+    Ok(id)
+}
+
+/// Result of writing a file-backed entry
+pub enum Upsert {
+    Inserted(i32),
+    Updated(i32),
+    Unchanged,
+    /// The entry is in the trash and was left there
+    Trashed(i32),
+}
+
+/// The form file paths are stored in, so `add -p`, `ingest` and `watch` agree on
+/// which entry belongs to a file: absolute, with `..` and symlinks resolved.
+pub fn stored_path(path: &Path) -> Result<String> {
+    let path = std::fs::canonicalize(path).with_context(|| format!("Failed to read file: {}", path.display()))?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Insert or refresh the entry for `file_path`. An existing entry keeps its type,
+/// metadata and language; `metadata` is merged on top, `language` replaces its
+/// language if given (otherwise changed content gets its detected language), and
+/// any deletion mark is cleared. Nothing is written when the
+/// content, original bytes, given metadata and language are already current, or when
+/// the entry is in the trash: only `trash restore` brings it back.
+pub async fn upsert_file(
+    client: &Client,
+    entry_type: &str,
+    file_path: &str,
+    content: &str,
+    metadata: &serde_json::Value,
+    blob: Option<Blob<'_>>,
+    language: Option<&str>,
+) -> Result<Upsert> {
+    let existing = client
+        .query_opt(
+            r#"
+            SELECT id, entry_type, content, COALESCE(metadata ? 'file_deleted', false), deleted_at IS NOT NULL
+            FROM entries
+            WHERE file_path = $1
+            ORDER BY id
+            LIMIT 1
+            "#,
+            &[&file_path],
+        )
+        .await
+        .context("Failed to look up entry by file path")?;
+
+    let Some(row) = existing else {
+        let id = insert(client, entry_type, content, Some(file_path), metadata, blob, language).await?;
+        return Ok(Upsert::Inserted(id));
+    };
+
+    let id: i32 = row.get(0);
+    let existing_type: &str = row.get(1);
+    let old_content: &str = row.get(2);
+    let marked_deleted: bool = row.get(3);
+    if row.get::<_, bool>(4) {
+        return Ok(Upsert::Trashed(id));
+    }
+
+    let mut derived = derive(existing_type, Some(file_path), content, metadata, language);
+    let (data, mime_type) = blob.map(|b| (b.data, b.mime_type)).unzip();
+    let current: bool = client
+        .query_one(
+            r#"
+            SELECT COALESCE(metadata @> $2, false) AND blob IS NOT DISTINCT FROM $3
+                   AND ($4::text IS NULL OR language = $4::text::regconfig)
+            FROM entries WHERE id = $1
+            "#,
+            &[&id, &derived.metadata, &data, &language],
+        )
+        .await?
+        .get(0);
+
+    if old_content == content && current && !marked_deleted {
+        return Ok(Upsert::Unchanged);
+    }
+    // Keywords shift as the corpus grows, so they are left out of the comparison above
+    add_keywords(client, existing_type, content, metadata, &mut derived).await?;
+    let language = if old_content == content { language } else { derived.language.as_deref() };
+
+    client
+        .execute(
+            r#"
+            UPDATE entries
+            SET content = $2,
+                metadata = (COALESCE(metadata, '{}') - 'file_deleted') || $3,
+                identifiers = $4,
+                identifier_words = $5,
+                blob = $6,
+                mime_type = $7,
+                minhash = $8,
+                shingle_count = $9,
+                language = COALESCE($10::text::regconfig, language),
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+            &[
+                &id,
+                &content,
+                &derived.metadata,
+                &derived.identifiers,
+                &derived.identifier_words,
+                &data,
+                &mime_type,
+                &derived.minhash,
+                &derived.shingle_count,
+                &language,
+            ],
+        )
+        .await
+        .context("Failed to update entry")?;
+    Ok(Upsert::Updated(id))
+}
+
+/// Point entries at a file's new location. Returns the number of entries moved.
+pub async fn rename_file(client: &Client, from: &str, to: &str) -> Result<u64> {
+    client
+        .execute(
+            "UPDATE entries SET file_path = $2, updated_at = NOW() WHERE file_path = $1",
+            &[&from, &to],
+        )
+        .await
+        .context("Failed to update file path")
+}
+
+/// Re-point entries under a renamed directory. Returns the number of entries moved.
+pub async fn rename_dir(client: &Client, from: &str, to: &str) -> Result<u64> {
+    client
+        .execute(
+            r#"
+            UPDATE entries
+            SET file_path = $2 || substr(file_path, length($1) + 1), updated_at = NOW()
+            WHERE starts_with(file_path, $1 || '/')
+            "#,
+            &[&from, &to],
+        )
+        .await
+        .context("Failed to update file paths")
+}
+
+/// Flag entries whose file no longer exists, keeping their content searchable.
+pub async fn mark_file_deleted(client: &Client, file_path: &str) -> Result<u64> {
+    client
+        .execute(
+            r#"
+            UPDATE entries
+            SET metadata = COALESCE(metadata, '{}') || jsonb_build_object('file_deleted', true),
+                updated_at = NOW()
+            WHERE file_path = $1
+            "#,
+            &[&file_path],
+        )
+        .await
+        .context("Failed to mark entry as deleted")
+}
+
+/// Move the entries for a deleted file to the trash.
+pub async fn trash_file(client: &Client, file_path: &str) -> Result<u64> {
+    client
+        .execute(
+            "UPDATE entries SET deleted_at = NOW() WHERE file_path = $1 AND deleted_at IS NULL",
+            &[&file_path],
+        )
+        .await
+        .context("Failed to move entry to trash")
+}
+
+/// Move entries to the trash. Returns the ids that were moved.
+pub async fn trash(client: &impl GenericClient, ids: &[i32]) -> Result<Vec<i32>> {
+    let rows = client
+        .query(
+            "UPDATE entries SET deleted_at = NOW() WHERE id = ANY($1) AND deleted_at IS NULL RETURNING id",
+            &[&ids],
+        )
+        .await
+        .context("Failed to move entries to trash")?;
+    Ok(rows.iter().map(|r| r.get(0)).collect())
+}
+
+/// Take entries out of the trash. Returns the ids that were restored.
+pub async fn restore(client: &Client, ids: &[i32]) -> Result<Vec<i32>> {
+    let rows = client
+        .query(
+            "UPDATE entries SET deleted_at = NULL WHERE id = ANY($1) AND deleted_at IS NOT NULL RETURNING id",
+            &[&ids],
+        )
+        .await
+        .context("Failed to restore entries")?;
+    Ok(rows.iter().map(|r| r.get(0)).collect())
+}
+
+/// Permanently remove trashed entries; their links and versions go with them.
+/// Entries not in the trash are left alone.
+pub async fn purge(client: &impl GenericClient, ids: &[i32]) -> Result<u64> {
+    // Detach derived entries so the foreign key does not block the delete
+    client
+        .execute(
+            "UPDATE entries SET source_id = NULL WHERE source_id IN (SELECT id FROM entries WHERE id = ANY($1) AND deleted_at IS NOT NULL)",
+            &[&ids],
+        )
+        .await
+        .context("Failed to detach derived entries")?;
+
+    client
+        .execute("DELETE FROM entries WHERE id = ANY($1) AND deleted_at IS NOT NULL", &[&ids])
+        .await
+        .context("Failed to purge entries")
+}
+
+/// Fold the `drop` entries into `keep`. Their metadata fills keys `keep` lacks and
+/// extends its arrays (keywords, tags); `metadata.merged_from` records their ids.
+/// Entries derived from them and their links move to `keep`, and each gets a
+/// `duplicates` link to it. The dropped entries themselves are left for the caller
+/// to trash or purge.
+pub async fn merge(client: &impl GenericClient, keep: i32, drop: &[i32]) -> Result<()> {
+    let rows = client
+        .query(
+            "SELECT id, metadata FROM entries WHERE id = $1 OR id = ANY($2) ORDER BY id = $1 DESC, id DESC",
+            &[&keep, &drop],
+        )
+        .await
+        .context("Failed to read metadata")?;
+
+    // The kept entry first, then the dropped ones newest first
+    let mut metadata = serde_json::Map::new();
+    for row in &rows {
+        let Some(serde_json::Value::Object(map)) = row.get::<_, Option<serde_json::Value>>(1) else {
+            continue;
+        };
+        for (key, value) in map {
+            match (metadata.get_mut(&key), value) {
+                (None, value) => {
+                    metadata.insert(key, value);
+                }
+                (Some(serde_json::Value::Array(existing)), serde_json::Value::Array(more)) => {
+                    for item in more {
+                        if !existing.contains(&item) {
+                            existing.push(item);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    let merged_from = metadata.entry("merged_from").or_insert_with(|| serde_json::json!([]));
+    if let Some(ids) = merged_from.as_array_mut() {
+        ids.extend(drop.iter().map(|id| serde_json::json!(id)));
+    }
+    client
+        .execute(
+            "UPDATE entries SET metadata = $2, updated_at = NOW() WHERE id = $1",
+            &[&keep, &serde_json::Value::Object(metadata)],
+        )
+        .await
+        .context("Failed to update metadata")?;
+
+    client
+        .execute("UPDATE entries SET source_id = $1 WHERE source_id = ANY($2)", &[&keep, &drop])
+        .await
+        .context("Failed to re-point derived entries")?;
+
+    client
+        .execute(
+            r#"
+            INSERT INTO entry_links (from_id, to_id, link_type, auto, created_at)
+            SELECT from_id, to_id, link_type, auto, created_at FROM (
+                SELECT CASE WHEN from_id = ANY($2) THEN $1 ELSE from_id END AS from_id,
+                       CASE WHEN to_id = ANY($2) THEN $1 ELSE to_id END AS to_id,
+                       link_type, auto, created_at
+                FROM entry_links
+                WHERE from_id = ANY($2) OR to_id = ANY($2)
+            ) moved
+            WHERE from_id <> to_id
+            ON CONFLICT DO NOTHING
+            "#,
+            &[&keep, &drop],
+        )
+        .await
+        .context("Failed to move links")?;
+    client
+        .execute("DELETE FROM entry_links WHERE from_id = ANY($1) OR to_id = ANY($1)", &[&drop])
+        .await
+        .context("Failed to remove moved links")?;
+
+    for &id in drop {
+        links::add(client, id, keep, "duplicates", true).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use anyhow::{Context, Result};
+use quick_xml::events::Event;
+use std::collections::HashMap;
+use std::io::Cursor;
+use super::{html, read_zip_entry, xml, Extracted, Extractor};
+
+pub struct Epub;
+
+impl Extractor for Epub {
+    fn mime_types(&self) -> &'static [&'static str] {
+        &["application/epub+zip"]
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["epub"]
+    }
+
+    /// Chapters are read in spine order: container.xml points at the package
+    /// document, whose manifest and spine list the XHTML files.
+    fn extract(&self, bytes: &[u8]) -> Result<Extracted> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).context("Not a valid EPUB archive")?;
+
+        let container = read_zip_entry(&mut archive, "META-INF/container.xml")?;
+        let package_path = first_attribute(&container, "rootfile", "full-path")
+            .context("EPUB container does not name a package document")?;
+        let package = read_zip_entry(&mut archive, &package_path)?;
+        let base = package_path.rsplit_once('/').map(|(dir, _)| format!("{}/", dir)).unwrap_or_default();
+
+        let mut chapters = Vec::new();
+        for href in spine(&package)? {
+            let Ok(chapter) = read_zip_entry(&mut archive, &format!("{}{}", base, href)) else {
+                continue;
+            };
+            let page = html::parse(&chapter);
+            if !page.text.is_empty() {
+                chapters.push(page.text);
+            }
+        }
+
+        let mut metadata = serde_json::Map::new();
+        if let Some(title) = xml::first_element_text(&package, "title") {
+            metadata.insert("title".to_string(), title.into());
+        }
+        if let Some(creator) = xml::first_element_text(&package, "creator") {
+            metadata.insert("author".to_string(), creator.into());
+        }
+        metadata.insert("chapters".to_string(), chapters.len().into());
+
+        Ok(Extracted {
+            content: chapters.join("\n\n"),
+            metadata,
+        })
+    }
+}
+
+/// Content file paths, relative to the package document, in reading order.
+fn spine(package: &str) -> Result<Vec<String>> {
+    let mut reader = quick_xml::Reader::from_str(package);
+    let mut manifest = HashMap::new();
+    let mut order = Vec::new();
+
+    loop {
+        match reader.read_event().context("Malformed EPUB package document")? {
+            Event::Start(e) | Event::Empty(e) => match e.local_name().into_inner() {
+                "item" => {
+                    if let (Some(id), Some(href)) = (xml::attribute(&e, "id"), xml::attribute(&e, "href")) {
+                        manifest.insert(id, href);
+                    }
+                }
+                "itemref" => order.extend(xml::attribute(&e, "idref")),
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(order.into_iter().filter_map(|id| manifest.remove(&id)).collect())
+}
+
+fn first_attribute(xml_text: &str, element: &str, name: &str) -> Option<String> {
+    let mut reader = quick_xml::Reader::from_str(xml_text);
+    loop {
+        match reader.read_event().ok()? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().into_inner() == element => {
+                return xml::attribute(&e, name);
+            }
+            Event::Eof => return None,
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use anyhow::Result;
+use scraper::{ElementRef, Node};
+use super::{tidy, Extracted, Extractor};
+
+/// Elements whose text is never shown to a reader
+const SKIPPED: &[&str] = &["head", "script", "style", "noscript", "template", "svg", "iframe"];
+
+/// Elements that start a new line of text
+const BLOCKS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "br", "dd", "div", "dl", "dt", "fieldset",
+    "figcaption", "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6", "header",
+    "hr", "li", "main", "nav", "ol", "p", "pre", "section", "table", "tr", "ul",
+];
+
+pub struct Html;
+
+impl Extractor for Html {
+    fn mime_types(&self) -> &'static [&'static str] {
+        &["text/html", "application/xhtml+xml"]
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["html", "htm", "xhtml"]
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<Extracted> {
+        let page = parse(&String::from_utf8_lossy(bytes));
+        let mut metadata = serde_json::Map::new();
+        if let Some(title) = page.title {
+            metadata.insert("title".to_string(), title.into());
+        }
+        Ok(Extracted {
+            content: page.text,
+            metadata,
+        })
+    }
+}
+
+pub struct Page {
+    pub title: Option<String>,
+    pub text: String,
+}
+
+/// Visible text of an HTML page, titled by `<title>` or else the first `<h1>`.
+pub fn parse(html: &str) -> Page {
+    let document = scraper::Html::parse_document(html);
+
+    let title = ["title", "h1"].iter().find_map(|tag| {
+        let selector = scraper::Selector::parse(tag).ok()?;
+        let element = document.select(&selector).next()?;
+        let title = collapse_whitespace(&element.text().collect::<String>());
+        (!title.is_empty()).then_some(title)
+    });
+
+    let mut text = String::new();
+    write_text(document.root_element(), &mut text);
+
+    Page {
+        title,
+        text: tidy(&text),
+    }
+}
+
+fn write_text(element: ElementRef, out: &mut String) {
+    let name = element.value().name();
+    if SKIPPED.contains(&name) {
+        return;
+    }
+    let block = BLOCKS.contains(&name);
+    if block {
+        new_line(out);
+    }
+    if name == "li" {
+        out.push_str("- ");
+    }
+
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) if name == "pre" => out.push_str(text),
+            Node::Text(text) => {
+                // Keep a single space at the edges so inline elements don't run together
+                let collapsed = collapse_whitespace(text);
+                if text.starts_with(char::is_whitespace) && !out.ends_with([' ', '\n']) {
+                    out.push(' ');
+                }
+                out.push_str(&collapsed);
+                if text.ends_with(char::is_whitespace) && !collapsed.is_empty() {
+                    out.push(' ');
+                }
+            }
+            Node::Element(_) => {
+                if let Some(child) = ElementRef::wrap(child) {
+                    write_text(child, out);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if block {
+        new_line(out);
+    }
+}
+
+fn new_line(out: &mut String) {
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
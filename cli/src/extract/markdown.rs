@@ -0,0 +1,222 @@
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// File extensions treated as Markdown
+pub const EXTENSIONS: &[&str] = &["md", "markdown", "mdx"];
+
+/// Most headings recorded in the outline
+const MAX_HEADINGS: usize = 200;
+
+pub fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Metadata for a Markdown file, and its content without the front matter.
+///
+/// Keys come from, lowest precedence first: `category` from the parent directory,
+/// `title` from the first H1, `word_count` and `headings`, then the front matter.
+pub fn analyze(path: &Path, text: &str) -> (String, Map<String, Value>) {
+    let (front_matter, body) = split_front_matter(text);
+
+    let mut metadata = Map::new();
+    if let Some(category) = category(path) {
+        metadata.insert("category".to_string(), category.into());
+    }
+
+    let headings = headings(body);
+    if let Some((_, title)) = headings.iter().find(|(level, _)| *level == 1) {
+        metadata.insert("title".to_string(), title.clone().into());
+    }
+    metadata.insert("word_count".to_string(), word_count(body).into());
+    if !headings.is_empty() {
+        let outline = headings
+            .iter()
+            .take(MAX_HEADINGS)
+            .map(|(level, text)| serde_json::json!({ "level": level, "text": text }))
+            .collect();
+        metadata.insert("headings".to_string(), Value::Array(outline));
+    }
+
+    if let Some(front_matter) = front_matter {
+        metadata.extend(front_matter);
+    }
+
+    (body.trim_start_matches(['\r', '\n']).to_string(), metadata)
+}
+
+/// Words in `body`. Markup standing alone (`#`, `-`, `>`, `---`) is not counted.
+fn word_count(body: &str) -> usize {
+    body.split_whitespace().filter(|w| w.chars().any(char::is_alphanumeric)).count()
+}
+
+/// Name of the directory holding the file, e.g. `adr` for `docs/adr/0001-use-postgres.md`.
+fn category(path: &Path) -> Option<String> {
+    let name = path.parent()?.file_name()?.to_string_lossy().to_string();
+    (!name.is_empty() && name != "." && name != "..").then_some(name)
+}
+
+/// Split off a leading `---` YAML or `+++` TOML block. Front matter that does not
+/// parse to a table is left in the content.
+fn split_front_matter(text: &str) -> (Option<Map<String, Value>>, &str) {
+    let text_start = text.trim_start_matches('\u{feff}');
+    let Some(first_line_end) = text_start.find('\n') else {
+        return (None, text);
+    };
+    let delimiter = text_start[..first_line_end].trim_end();
+    if delimiter != "---" && delimiter != "+++" {
+        return (None, text);
+    }
+
+    let rest = &text_start[first_line_end + 1..];
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == delimiter {
+            let raw = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            let parsed = if delimiter == "---" { parse_yaml(raw) } else { parse_toml(raw) };
+            return match parsed {
+                Some(map) => (Some(map), body),
+                None => (None, text),
+            };
+        }
+        offset += line.len();
+    }
+    (None, text)
+}
+
+fn parse_yaml(raw: &str) -> Option<Map<String, Value>> {
+    match serde_yaml::from_str::<Value>(raw).ok()? {
+        Value::Object(map) => Some(map),
+        _ => None,
+    }
+}
+
+fn parse_toml(raw: &str) -> Option<Map<String, Value>> {
+    let table: toml::Table = toml::from_str(raw).ok()?;
+    match toml_to_json(toml::Value::Table(table)) {
+        Value::Object(map) => Some(map),
+        _ => None,
+    }
+}
+
+/// Like serde_json::to_value, but with dates as plain strings rather than TOML's
+/// internal datetime wrapper.
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => s.into(),
+        toml::Value::Integer(i) => i.into(),
+        toml::Value::Float(f) => f.into(),
+        toml::Value::Boolean(b) => b.into(),
+        toml::Value::Datetime(d) => d.to_string().into(),
+        toml::Value::Array(items) => items.into_iter().map(toml_to_json).collect(),
+        toml::Value::Table(table) => table.into_iter().map(|(k, v)| (k, toml_to_json(v))).collect(),
+    }
+}
+
+/// ATX headings (`## Setup`) outside fenced code blocks, as (level, text).
+fn headings(body: &str) -> Vec<(usize, String)> {
+    let mut headings = Vec::new();
+    let mut fence: Option<&str> = None;
+
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+        if let Some(open) = fence {
+            if trimmed.starts_with(open) {
+                fence = None;
+            }
+            continue;
+        }
+        if trimmed.starts_with("```") {
+            fence = Some("```");
+            continue;
+        }
+        if trimmed.starts_with("~~~") {
+            fence = Some("~~~");
+            continue;
+        }
+
+        // More than three spaces of indentation makes it a code block
+        if line.len() - trimmed.len() > 3 {
+            continue;
+        }
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        if !(1..=6).contains(&level) {
+            continue;
+        }
+        let rest = &trimmed[level..];
+        if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+            continue;
+        }
+        let text = rest.trim().trim_end_matches('#').trim_end();
+        if !text.is_empty() {
+            headings.push((level, text.to_string()));
+        }
+    }
+    headings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yaml_front_matter_wins_over_derived_keys() {
+        let text = "---\ntitle: From front matter\ntags: [db, ops]\n---\n# Heading title\n\nBody text.\n";
+        let (body, metadata) = analyze(Path::new("docs/adr/0001.md"), text);
+        assert_eq!(body, "# Heading title\n\nBody text.\n");
+        assert_eq!(metadata["title"], "From front matter");
+        assert_eq!(metadata["tags"], serde_json::json!(["db", "ops"]));
+        assert_eq!(metadata["category"], "adr");
+        assert_eq!(metadata["word_count"], 4);
+    }
+
+    #[test]
+    fn markup_is_not_counted_as_words() {
+        assert_eq!(word_count("## Setup
+
+- install it
+> quoted 42
+
+---
+
+| a | b |
+"), 7);
+    }
+
+    #[test]
+    fn toml_front_matter_dates_become_strings() {
+        let (front_matter, body) = split_front_matter("+++\ndate = 2024-05-01\ndraft = true\n+++\nBody\n");
+        let front_matter = front_matter.unwrap();
+        assert_eq!(front_matter["date"], "2024-05-01");
+        assert_eq!(front_matter["draft"], true);
+        assert_eq!(body, "Body\n");
+    }
+
+    #[test]
+    fn unclosed_front_matter_stays_in_the_content() {
+        let text = "---\ntitle: Never closed\n\nBody text.\n";
+        assert_eq!(split_front_matter(text), (None, text));
+    }
+
+    #[test]
+    fn invalid_yaml_stays_in_the_content() {
+        for text in ["---\ntitle: [unterminated\n---\nBody\n", "---\n- just\n- a list\n---\nBody\n"] {
+            assert_eq!(split_front_matter(text), (None, text));
+        }
+    }
+
+    #[test]
+    fn byte_order_mark_before_front_matter_is_skipped() {
+        let (front_matter, body) = split_front_matter("\u{feff}---\r\ntitle: Windows file\r\n---\r\nBody\r\n");
+        assert_eq!(front_matter.unwrap()["title"], "Windows file");
+        assert_eq!(body, "Body\r\n");
+    }
+
+    #[test]
+    fn thematic_break_is_not_front_matter() {
+        let text = "Intro\n---\nMore\n";
+        assert_eq!(split_front_matter(text), (None, text));
+    }
+}
@@ -0,0 +1,204 @@
+//! Turning non-plain-text files into searchable text.
+//!
+//! Each format is handled by an [`Extractor`] registered by mime type and file
+//! extension. The extracted text becomes the entry's `content`; the original bytes
+//! are kept in `blob` alongside their `mime_type`.
+
+mod epub;
+mod html;
+mod markdown;
+mod notebook;
+mod office;
+mod pdf;
+mod xml;
+
+use anyhow::{bail, Context, Result};
+use std::io::{Cursor, Read};
+use std::path::Path;
+use crate::entries::Blob;
+
+/// Text pulled out of a document, plus metadata such as its title
+#[derive(Default)]
+pub struct Extracted {
+    pub content: String,
+    pub metadata: serde_json::Map<String, serde_json::Value>,
+}
+
+pub trait Extractor {
+    /// Mime types handled, the first being the one stored with the entry
+    fn mime_types(&self) -> &'static [&'static str];
+    /// Lowercase file extensions handled, without the dot
+    fn extensions(&self) -> &'static [&'static str];
+    fn extract(&self, bytes: &[u8]) -> Result<Extracted>;
+}
+
+pub struct Registry {
+    extractors: Vec<Box<dyn Extractor>>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Self { extractors: Vec::new() };
+        registry.register(Box::new(pdf::Pdf));
+        registry.register(Box::new(html::Html));
+        registry.register(Box::new(office::Docx));
+        registry.register(Box::new(office::Odt));
+        registry.register(Box::new(notebook::Notebook));
+        registry.register(Box::new(epub::Epub));
+        registry
+    }
+}
+
+impl Registry {
+    pub fn register(&mut self, extractor: Box<dyn Extractor>) {
+        self.extractors.push(extractor);
+    }
+
+    pub fn for_mime_type(&self, mime_type: &str) -> Option<&dyn Extractor> {
+        self.extractors
+            .iter()
+            .find(|e| e.mime_types().contains(&mime_type))
+            .map(|e| e.as_ref())
+    }
+
+    pub fn for_extension(&self, extension: &str) -> Option<&dyn Extractor> {
+        let extension = extension.to_lowercase();
+        self.extractors
+            .iter()
+            .find(|e| e.extensions().contains(&extension.as_str()))
+            .map(|e| e.as_ref())
+    }
+
+    /// Pick an extractor by extension, falling back to sniffing the content.
+    pub fn detect(&self, path: &Path, bytes: &[u8]) -> Option<&dyn Extractor> {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .and_then(|e| self.for_extension(e))
+            .or_else(|| sniff_mime_type(bytes).and_then(|m| self.for_mime_type(&m)))
+    }
+}
+
+/// A file ready to be stored as an entry
+pub struct Document {
+    pub content: String,
+    pub metadata: serde_json::Value,
+    /// Original bytes and their mime type, for extracted formats
+    pub blob: Option<(Vec<u8>, String)>,
+}
+
+impl Document {
+    pub fn text(content: String) -> Self {
+        Self {
+            content,
+            metadata: serde_json::json!({}),
+            blob: None,
+        }
+    }
+
+    pub fn blob(&self) -> Option<Blob<'_>> {
+        self.blob.as_ref().map(|(data, mime_type)| Blob { data, mime_type })
+    }
+}
+
+/// Read a file as an entry: plain UTF-8 text is stored as-is (Markdown with its
+/// front matter and headings turned into metadata), known formats are extracted,
+/// anything else is an error.
+pub fn read_file(path: &Path) -> Result<Document> {
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read file: {}", path.display()))?;
+    document_from_bytes(&Registry::default(), path, bytes).with_context(|| format!("Cannot index {}", path.display()))
+}
+
+/// `path` picks the format and, for Markdown, the `category`; pass a path relative
+/// to the project root when there is one.
+pub fn document_from_bytes(registry: &Registry, path: &Path, bytes: Vec<u8>) -> Result<Document> {
+    let Some(extractor) = registry.detect(path, &bytes) else {
+        let content = String::from_utf8(bytes)
+            .map_err(|_| anyhow::anyhow!("binary or not valid UTF-8, and no extractor handles it"))?;
+        return Ok(text_document(path, content));
+    };
+
+    let mime_type = extractor.mime_types()[0].to_string();
+    let extracted = extractor
+        .extract(&bytes)
+        .with_context(|| format!("Failed to extract text ({})", mime_type))?;
+    if extracted.content.trim().is_empty() {
+        bail!("No text could be extracted ({})", mime_type);
+    }
+
+    Ok(Document {
+        content: extracted.content,
+        metadata: serde_json::Value::Object(extracted.metadata),
+        blob: Some((bytes, mime_type)),
+    })
+}
+
+fn text_document(path: &Path, content: String) -> Document {
+    if !markdown::is_markdown(path) {
+        return Document::text(content);
+    }
+    let (content, metadata) = markdown::analyze(path, &content);
+    Document {
+        content,
+        metadata: serde_json::Value::Object(metadata),
+        blob: None,
+    }
+}
+
+/// Guess a mime type from magic bytes, for files with missing or unknown extensions.
+fn sniff_mime_type(bytes: &[u8]) -> Option<String> {
+    if bytes.starts_with(b"%PDF-") {
+        return Some("application/pdf".to_string());
+    }
+
+    if bytes.starts_with(b"PK\x03\x04") {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).ok()?;
+        // ODF and EPUB store their mime type uncompressed in a "mimetype" entry
+        if let Ok(mut file) = archive.by_name("mimetype") {
+            let mut mime_type = String::new();
+            file.read_to_string(&mut mime_type).ok()?;
+            return Some(mime_type.trim().to_string());
+        }
+        if archive.by_name("word/document.xml").is_ok() {
+            return Some(office::DOCX_MIME_TYPE.to_string());
+        }
+        return None;
+    }
+
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(512)]).to_lowercase();
+    let head = head.trim_start_matches('\u{feff}').trim_start();
+    if head.starts_with("<!doctype html") || head.starts_with("<html") {
+        return Some("text/html".to_string());
+    }
+
+    None
+}
+
+/// Read a UTF-8 file out of a zip archive.
+fn read_zip_entry(archive: &mut zip::ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<String> {
+    let mut file = archive
+        .by_name(name)
+        .with_context(|| format!("Archive has no {}", name))?;
+    let mut text = String::new();
+    file.read_to_string(&mut text)
+        .with_context(|| format!("Failed to read {} from archive", name))?;
+    Ok(text)
+}
+
+/// Collapse runs of blank lines and trailing spaces left behind by markup.
+fn tidy(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut blank = 0;
+    for line in text.lines() {
+        let line = line.trim_end();
+        if line.trim().is_empty() {
+            blank += 1;
+            continue;
+        }
+        if !out.is_empty() {
+            out.push_str(if blank > 0 { "\n\n" } else { "\n" });
+        }
+        out.push_str(line);
+        blank = 0;
+    }
+    out
+}
@@ -0,0 +1,98 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use super::{tidy, Extracted, Extractor};
+
+/// Longest text output kept per code cell
+const MAX_OUTPUT_CHARS: usize = 2000;
+
+pub struct Notebook;
+
+impl Extractor for Notebook {
+    fn mime_types(&self) -> &'static [&'static str] {
+        &["application/x-ipynb+json"]
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["ipynb"]
+    }
+
+    /// Markdown cells are kept as-is, code cells become fenced blocks followed by
+    /// their text output.
+    fn extract(&self, bytes: &[u8]) -> Result<Extracted> {
+        let notebook: Value = serde_json::from_slice(bytes).context("Not a valid notebook (invalid JSON)")?;
+        let cells = notebook["cells"].as_array().context("Notebook has no cells")?;
+
+        let language = notebook["metadata"]["kernelspec"]["language"]
+            .as_str()
+            .or_else(|| notebook["metadata"]["language_info"]["name"].as_str())
+            .unwrap_or_default();
+
+        let mut out = Vec::new();
+        for cell in cells {
+            let source = join_lines(&cell["source"]);
+            match cell["cell_type"].as_str() {
+                Some("markdown") | Some("raw") => out.push(source),
+                Some("code") => {
+                    if !source.trim().is_empty() {
+                        out.push(format!("```{}\n{}\n```", language, source.trim_end()));
+                    }
+                    let output = cell_output(cell);
+                    if !output.is_empty() {
+                        out.push(output);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut metadata = serde_json::Map::new();
+        metadata.insert("cells".to_string(), cells.len().into());
+        if !language.is_empty() {
+            metadata.insert("language".to_string(), language.into());
+        }
+        if let Some(title) = notebook["metadata"]["title"].as_str() {
+            metadata.insert("title".to_string(), title.into());
+        }
+
+        Ok(Extracted {
+            content: tidy(&out.join("\n\n")),
+            metadata,
+        })
+    }
+}
+
+/// Streams and plain-text results of a code cell, truncated to MAX_OUTPUT_CHARS.
+fn cell_output(cell: &Value) -> String {
+    let mut text = String::new();
+    for output in cell["outputs"].as_array().into_iter().flatten() {
+        let part = match output["output_type"].as_str() {
+            Some("stream") => join_lines(&output["text"]),
+            Some("execute_result") | Some("display_data") => join_lines(&output["data"]["text/plain"]),
+            Some("error") => format!(
+                "{}: {}",
+                output["ename"].as_str().unwrap_or_default(),
+                output["evalue"].as_str().unwrap_or_default()
+            ),
+            _ => continue,
+        };
+        text.push_str(&part);
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+    }
+
+    if text.chars().count() > MAX_OUTPUT_CHARS {
+        text = text.chars().take(MAX_OUTPUT_CHARS).collect();
+        text.push_str("\n…");
+    }
+    text
+}
+
+/// Notebook text fields are either a string or a list of lines.
+fn join_lines(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(lines) => lines.iter().filter_map(|l| l.as_str()).collect(),
+        _ => String::new(),
+    }
+}
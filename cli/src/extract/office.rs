@@ -0,0 +1,76 @@
+use anyhow::{Context, Result};
+use std::io::Cursor;
+use super::xml::{self, Tag};
+use super::{read_zip_entry, tidy, Extracted, Extractor};
+
+pub const DOCX_MIME_TYPE: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.document";
+
+pub struct Docx;
+
+impl Extractor for Docx {
+    fn mime_types(&self) -> &'static [&'static str] {
+        &[DOCX_MIME_TYPE]
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["docx"]
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<Extracted> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).context("Not a valid DOCX archive")?;
+        let document = read_zip_entry(&mut archive, "word/document.xml")?;
+
+        let text = xml::text(&document, &["t"], |name, tag| match (name, tag) {
+            ("p", Tag::Close) => Some("\n"),
+            ("tab", Tag::Empty) => Some("\t"),
+            ("br" | "cr", Tag::Empty) => Some("\n"),
+            _ => None,
+        })?;
+
+        let title = read_zip_entry(&mut archive, "docProps/core.xml")
+            .ok()
+            .and_then(|core| xml::first_element_text(&core, "title"));
+        Ok(extracted(text, title))
+    }
+}
+
+pub struct Odt;
+
+impl Extractor for Odt {
+    fn mime_types(&self) -> &'static [&'static str] {
+        &["application/vnd.oasis.opendocument.text"]
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["odt"]
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<Extracted> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).context("Not a valid ODT archive")?;
+        let content = read_zip_entry(&mut archive, "content.xml")?;
+
+        let text = xml::text(&content, &["text"], |name, tag| match (name, tag) {
+            ("p" | "h", Tag::Close) => Some("\n"),
+            ("s", Tag::Empty) => Some(" "),
+            ("tab", Tag::Empty) => Some("\t"),
+            ("line-break", Tag::Empty) => Some("\n"),
+            _ => None,
+        })?;
+
+        let title = read_zip_entry(&mut archive, "meta.xml")
+            .ok()
+            .and_then(|meta| xml::first_element_text(&meta, "title"));
+        Ok(extracted(text, title))
+    }
+}
+
+fn extracted(text: String, title: Option<String>) -> Extracted {
+    let mut metadata = serde_json::Map::new();
+    if let Some(title) = title {
+        metadata.insert("title".to_string(), title.into());
+    }
+    Extracted {
+        content: tidy(&text),
+        metadata,
+    }
+}
@@ -0,0 +1,29 @@
+use anyhow::{anyhow, Result};
+use super::{tidy, Extracted, Extractor};
+
+pub struct Pdf;
+
+impl Extractor for Pdf {
+    fn mime_types(&self) -> &'static [&'static str] {
+        &["application/pdf"]
+    }
+
+    fn extensions(&self) -> &'static [&'static str] {
+        &["pdf"]
+    }
+
+    fn extract(&self, bytes: &[u8]) -> Result<Extracted> {
+        // pdf-extract panics on some malformed files instead of returning an error
+        let pages = std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem_by_pages(bytes))
+            .map_err(|_| anyhow!("PDF parser crashed on this file"))?
+            .map_err(|e| anyhow!("{}", e))?;
+
+        let mut metadata = serde_json::Map::new();
+        metadata.insert("pages".to_string(), pages.len().into());
+
+        Ok(Extracted {
+            content: tidy(&pages.join("\n\n")),
+            metadata,
+        })
+    }
+}
@@ -0,0 +1,83 @@
+use anyhow::{Context, Result};
+use quick_xml::events::{BytesRef, Event};
+use quick_xml::Reader;
+
+pub enum Tag {
+    Open,
+    Close,
+    Empty,
+}
+
+/// Collect the text of an XML document. Only text inside one of `text_elements` is
+/// kept; `markup` returns the separator to emit for a tag (e.g. a newline after a
+/// paragraph). Names are matched without their namespace prefix.
+pub fn text(xml: &str, text_elements: &[&str], markup: impl Fn(&str, Tag) -> Option<&'static str>) -> Result<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut out = String::new();
+    let mut depth = 0usize;
+
+    loop {
+        match reader.read_event().context("Malformed XML")? {
+            Event::Start(e) => {
+                let name = e.local_name().into_inner();
+                if text_elements.contains(&name) {
+                    depth += 1;
+                }
+                out.push_str(markup(name, Tag::Open).unwrap_or_default());
+            }
+            Event::End(e) => {
+                let name = e.local_name().into_inner();
+                if text_elements.contains(&name) {
+                    depth = depth.saturating_sub(1);
+                }
+                out.push_str(markup(name, Tag::Close).unwrap_or_default());
+            }
+            Event::Empty(e) => {
+                out.push_str(markup(e.local_name().into_inner(), Tag::Empty).unwrap_or_default());
+            }
+            Event::Text(t) if depth > 0 => out.push_str(&t.xml10_content()),
+            Event::CData(t) if depth > 0 => out.push_str(&t.into_inner()),
+            Event::GeneralRef(r) if depth > 0 => out.push_str(&resolve(&r)),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+/// Text of the first element called `name`, e.g. `title` for `<dc:title>`.
+pub fn first_element_text(xml: &str, name: &str) -> Option<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut inside = false;
+    let mut out = String::new();
+
+    loop {
+        match reader.read_event().ok()? {
+            Event::Start(e) if e.local_name().into_inner() == name => inside = true,
+            Event::End(e) if inside && e.local_name().into_inner() == name => break,
+            Event::Text(t) if inside => out.push_str(&t.xml10_content()),
+            Event::GeneralRef(r) if inside => out.push_str(&resolve(&r)),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let out = out.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!out.is_empty()).then_some(out)
+}
+
+/// Attribute value on a start tag, by unprefixed name.
+pub fn attribute(element: &quick_xml::events::BytesStart, name: &str) -> Option<String> {
+    element
+        .attributes()
+        .filter_map(|a| a.ok())
+        .find(|a| a.key.local_name().into_inner() == name)
+        .and_then(|a| a.normalized_value(quick_xml::XmlVersion::Implicit1_0).ok().map(|v| v.to_string()))
+}
+
+fn resolve(reference: &BytesRef) -> String {
+    if let Ok(Some(c)) = reference.resolve_char_ref() {
+        return c.to_string();
+    }
+    quick_xml::escape::resolve_predefined_entity(reference).unwrap_or_default().to_string()
+}
@@ -0,0 +1,140 @@
+//! Near-duplicate fingerprints.
+//!
+//! Content is normalized to lowercase words and cut into overlapping 5-word
+//! shingles. The MinHash signature keeps, for each of 64 hash functions, the
+//! smallest hash over all shingles; the share of positions where two signatures
+//! agree estimates the Jaccard similarity of their shingle sets. Hashes are
+//! computed here rather than with std's hasher so stored signatures stay comparable
+//! across builds.
+
+use std::collections::HashSet;
+
+/// Signature length; the estimate's standard error is about 1/sqrt(64)
+pub const NUM_HASHES: usize = 64;
+
+/// Words per shingle
+const SHINGLE_WORDS: usize = 5;
+
+pub struct Fingerprint {
+    pub minhash: Vec<i32>,
+    /// Number of distinct shingles, used to estimate containment
+    pub shingles: i32,
+}
+
+/// None when the text has no words.
+pub fn compute(text: &str) -> Option<Fingerprint> {
+    let lowered = text.to_lowercase();
+    let words: Vec<&str> = lowered
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+
+    let shingles: HashSet<u64> = words
+        .windows(SHINGLE_WORDS.min(words.len()))
+        .map(|window| {
+            let mut hash = FNV_OFFSET;
+            for word in window {
+                hash = fnv1a(hash, word.as_bytes());
+                hash = fnv1a(hash, b" ");
+            }
+            hash
+        })
+        .collect();
+
+    let mut minhash = vec![u32::MAX; NUM_HASHES];
+    for &shingle in &shingles {
+        for (i, min) in minhash.iter_mut().enumerate() {
+            let hash = splitmix64(shingle ^ seed(i)) as u32;
+            if hash < *min {
+                *min = hash;
+            }
+        }
+    }
+
+    Some(Fingerprint {
+        minhash: minhash.into_iter().map(|h| h as i32).collect(),
+        shingles: shingles.len().min(i32::MAX as usize) as i32,
+    })
+}
+
+/// Estimated Jaccard similarity of the two shingle sets
+pub fn jaccard(a: &[i32], b: &[i32]) -> f64 {
+    let len = a.len().min(b.len());
+    if len == 0 {
+        return 0.0;
+    }
+    let agree = a.iter().zip(b).filter(|(x, y)| x == y).count();
+    agree as f64 / len as f64
+}
+
+/// Estimated share of the smaller entry's shingles that also occur in the larger
+/// one. An earlier save of a chat is contained in a later one even though their
+/// Jaccard similarity is low.
+pub fn containment(a: &Fingerprint, b: &Fingerprint) -> f64 {
+    let j = jaccard(&a.minhash, &b.minhash);
+    let (size_a, size_b) = (a.shingles as f64, b.shingles as f64);
+    let smaller = size_a.min(size_b);
+    if smaller == 0.0 {
+        return 0.0;
+    }
+    let shared = j * (size_a + size_b) / (1.0 + j);
+    (shared / smaller).min(1.0)
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn seed(i: usize) -> u64 {
+    splitmix64(i as u64 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(words: std::ops::Range<usize>) -> String {
+        words.map(|i| format!("word{} ", i)).collect()
+    }
+
+    #[test]
+    fn identical_text_matches_regardless_of_case_and_punctuation() {
+        let a = compute("Retry the request, with exponential backoff and jitter.").unwrap();
+        let b = compute("retry the request with EXPONENTIAL backoff and jitter").unwrap();
+        assert_eq!(jaccard(&a.minhash, &b.minhash), 1.0);
+        assert!(compute(" -- ").is_none());
+    }
+
+    #[test]
+    fn edited_copy_is_similar_and_unrelated_text_is_not() {
+        let original = compute(&text(0..400)).unwrap();
+        let edited = compute(&(text(0..200) + "inserted sentence here " + &text(200..400))).unwrap();
+        let unrelated = compute(&text(1000..1400)).unwrap();
+        assert!(jaccard(&original.minhash, &edited.minhash) > 0.8);
+        assert!(jaccard(&original.minhash, &unrelated.minhash) < 0.1);
+    }
+
+    #[test]
+    fn earlier_save_is_contained_in_later_one() {
+        let early = compute(&text(0..300)).unwrap();
+        let late = compute(&text(0..1200)).unwrap();
+        assert!(jaccard(&early.minhash, &late.minhash) < 0.5);
+        assert!(containment(&early, &late) > 0.8);
+    }
+}
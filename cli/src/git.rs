@@ -0,0 +1,80 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub struct RepoInfo {
+    pub root: PathBuf,
+    pub name: String,
+    pub branch: Option<String>,
+    /// Paths (relative to `root`) with uncommitted changes
+    dirty: HashSet<String>,
+}
+
+pub struct FileCommit {
+    pub hash: String,
+    pub author: String,
+    pub date: String,
+}
+
+impl RepoInfo {
+    pub fn discover(dir: &Path) -> Result<Self> {
+        let root = git(dir, &["rev-parse", "--show-toplevel"]).with_context(|| {
+            format!("{} is not inside a git repository (is git installed?)", dir.display())
+        })?;
+        let root = PathBuf::from(root);
+
+        let name = root
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| root.to_string_lossy().to_string());
+
+        // Detached HEAD reports "HEAD"
+        let branch = git(&root, &["rev-parse", "--abbrev-ref", "HEAD"]).filter(|b| b != "HEAD");
+
+        let dirty = git(&root, &["status", "--porcelain", "--no-renames"])
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.get(3..))
+            .map(|path| path.trim_matches('"').to_string())
+            .collect();
+
+        Ok(Self { root, name, branch, dirty })
+    }
+
+    /// Path relative to the repository root, with `/` separators.
+    pub fn relative_path(&self, path: &Path) -> Result<String> {
+        let rel = path
+            .strip_prefix(&self.root)
+            .with_context(|| format!("{} is outside {}", path.display(), self.root.display()))?;
+        Ok(rel.to_string_lossy().replace('\\', "/"))
+    }
+
+    pub fn is_dirty(&self, relative_path: &str) -> bool {
+        self.dirty.contains(relative_path)
+    }
+
+    /// Last commit touching `relative_path`, or `None` for untracked files.
+    pub fn last_commit(&self, relative_path: &str) -> Option<FileCommit> {
+        let out = git(
+            &self.root,
+            &["log", "-1", "--format=%H%x00%an%x00%aI", "--", relative_path],
+        )?;
+        let mut parts = out.split('\0');
+        Some(FileCommit {
+            hash: parts.next()?.to_string(),
+            author: parts.next()?.to_string(),
+            date: parts.next()?.to_string(),
+        })
+    }
+}
+
+/// Run git in `dir` and return trimmed stdout, or `None` if it fails or prints nothing.
+fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout).trim_end().to_string();
+    (!stdout.is_empty()).then_some(stdout)
+}
@@ -0,0 +1,211 @@
+//! Offline keyword extraction.
+//!
+//! Candidate phrases are runs of words between stopwords and punctuation (as in
+//! RAKE). Each word is weighted by TF-IDF, with document frequencies taken from the
+//! `entries` table, and phrases are ranked by the mean weight of their words boosted
+//! by how often the phrase recurs.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use tokio_postgres::Client;
+
+/// Keywords stored per entry
+pub const MAX_KEYWORDS: usize = 10;
+
+/// Longest candidate phrase, in words
+const MAX_PHRASE_WORDS: usize = 3;
+
+/// Only the start of very large entries is analysed
+const MAX_TEXT_CHARS: usize = 200_000;
+
+pub const STOPWORDS: &[&str] = &[
+    "a", "about", "above", "after", "again", "against", "all", "also", "am", "an", "and", "any",
+    "are", "aren't", "as", "at", "be", "because", "been", "before", "being", "below", "between",
+    "both", "but", "by", "can", "cannot", "could", "couldn't", "did", "didn't", "do", "does",
+    "doesn't", "doing", "don't", "down", "during", "each", "e.g", "eg", "etc", "even", "ever",
+    "every", "few", "for", "from", "further", "get", "gets", "got", "had", "hadn't", "has",
+    "hasn't", "have", "haven't", "having", "he", "her", "here", "hers", "herself", "him",
+    "himself", "his", "how", "however", "i", "i.e", "ie", "if", "in", "into", "is", "isn't", "it",
+    "it's", "its", "itself", "just", "let", "let's", "like", "may", "me", "might", "more", "most",
+    "much", "must", "my", "myself", "need", "needs", "no", "nor", "not", "now", "of", "off", "on",
+    "once", "one", "only", "or", "other", "others", "our", "ours", "ourselves", "out", "over",
+    "own", "per", "rather", "same", "see", "shall", "she", "should", "shouldn't", "since", "so",
+    "some", "still", "such", "than", "that", "that's", "the", "their", "theirs", "them",
+    "themselves", "then", "there", "there's", "these", "they", "this", "those", "though",
+    "through", "thus", "to", "too", "under", "until", "up", "upon", "us", "use", "used", "uses",
+    "using", "very", "via", "was", "wasn't", "we", "well", "were", "weren't", "what", "when",
+    "where", "whether", "which", "while", "who", "whom", "whose", "why", "will", "with", "within",
+    "without", "won't", "would", "wouldn't", "yes", "yet", "you", "your", "yours", "yourself",
+    "yourselves",
+];
+
+/// Document frequencies of words in the `entries` table, fetched on demand and
+/// cached so a batch (e.g. `retag`) queries each word once per language.
+#[derive(Default)]
+pub struct Corpus {
+    entries: Option<i64>,
+    /// By text search language (None is the default) and word
+    document_frequency: HashMap<(Option<String>, String), i64>,
+}
+
+impl Corpus {
+    async fn load(&mut self, client: &Client, words: &[&str], language: Option<&str>) -> Result<()> {
+        if self.entries.is_none() {
+            let row = client
+                .query_one("SELECT count(*) FROM entries WHERE deleted_at IS NULL", &[])
+                .await
+                .context("Failed to count entries")?;
+            self.entries = Some(row.get(0));
+        }
+
+        let missing: Vec<&str> = words
+            .iter()
+            .copied()
+            .filter(|w| !self.document_frequency.contains_key(&(language.map(str::to_string), w.to_string())))
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        let rows = client
+            .query(
+                r#"
+                SELECT w, (SELECT count(*) FROM entries
+                           WHERE content_tsv @@ plainto_tsquery(COALESCE($2::text::regconfig, get_current_ts_config()), w)
+                             AND deleted_at IS NULL)
+                FROM unnest($1::text[]) AS w
+                "#,
+                &[&missing, &language],
+            )
+            .await
+            .context("Failed to read corpus statistics")?;
+        for row in rows {
+            self.document_frequency.insert((language.map(str::to_string), row.get(0)), row.get(1));
+        }
+        Ok(())
+    }
+
+    fn idf(&self, word: &str, language: Option<&str>) -> f64 {
+        let entries = self.entries.unwrap_or(0) as f64;
+        let key = (language.map(str::to_string), word.to_string());
+        let frequency = self.document_frequency.get(&key).copied().unwrap_or(0) as f64;
+        ((entries + 1.0) / (frequency + 1.0)).ln() + 1.0
+    }
+}
+
+/// Up to MAX_KEYWORDS keywords for `text`, most relevant first. Document
+/// frequencies are counted with the text search configuration `language`.
+pub async fn extract(client: &Client, corpus: &mut Corpus, text: &str, language: Option<&str>) -> Result<Vec<String>> {
+    let phrases = candidate_phrases(text);
+    if phrases.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut term_frequency: HashMap<&str, usize> = HashMap::new();
+    for phrase in &phrases {
+        for word in phrase {
+            *term_frequency.entry(word.as_str()).or_default() += 1;
+        }
+    }
+    let words: Vec<&str> = term_frequency.keys().copied().collect();
+    corpus.load(client, &words, language).await?;
+
+    let weight = |word: &str| (1.0 + (term_frequency[word] as f64).ln()) * corpus.idf(word, language);
+
+    let mut phrase_count: HashMap<&[String], usize> = HashMap::new();
+    for phrase in &phrases {
+        *phrase_count.entry(phrase.as_slice()).or_default() += 1;
+    }
+
+    let mut ranked: Vec<(f64, &[String])> = phrase_count
+        .into_iter()
+        .map(|(phrase, count)| {
+            let mean = phrase.iter().map(|w| weight(w)).sum::<f64>() / phrase.len() as f64;
+            (mean * (1.0 + (count as f64).ln()), phrase)
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+
+    // Skip phrases already covered by a higher-ranked one ("backoff" after "exponential backoff")
+    let mut keywords: Vec<String> = Vec::new();
+    let mut covered: Vec<&[String]> = Vec::new();
+    for (_, phrase) in ranked {
+        if covered.iter().any(|c| contains(c, phrase) || contains(phrase, c)) {
+            continue;
+        }
+        covered.push(phrase);
+        keywords.push(phrase.join(" "));
+        if keywords.len() == MAX_KEYWORDS {
+            break;
+        }
+    }
+    Ok(keywords)
+}
+
+/// Whether `phrase` occurs as a contiguous run of words in `within`.
+fn contains(within: &[String], phrase: &[String]) -> bool {
+    phrase.len() <= within.len() && within.windows(phrase.len()).any(|w| w == phrase)
+}
+
+/// Lowercased runs of content words, split at stopwords, punctuation and line ends.
+/// Fenced code blocks are ignored.
+fn candidate_phrases(text: &str) -> Vec<Vec<String>> {
+    let mut phrases = Vec::new();
+    let mut current: Vec<String> = Vec::new();
+    let mut in_fence = false;
+    let mut chars = 0;
+
+    for line in text.lines() {
+        chars += line.len();
+        if chars > MAX_TEXT_CHARS {
+            break;
+        }
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        for chunk in line.split_whitespace() {
+            // Punctuation inside or around a chunk ("retries," or "(see") separates phrases
+            let parts = chunk.split(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '\'' | '.')));
+            for (i, part) in parts.enumerate() {
+                if i > 0 || part.is_empty() {
+                    flush(&mut current, &mut phrases);
+                }
+                let word = part.trim_matches(|c: char| matches!(c, '-' | '_' | '\'' | '.')).to_lowercase();
+                if is_content_word(&word) {
+                    current.push(word);
+                    if current.len() == MAX_PHRASE_WORDS {
+                        flush(&mut current, &mut phrases);
+                    }
+                } else if !part.is_empty() {
+                    flush(&mut current, &mut phrases);
+                }
+                // A trailing '.' ends the sentence
+                if part.ends_with('.') {
+                    flush(&mut current, &mut phrases);
+                }
+            }
+        }
+        flush(&mut current, &mut phrases);
+    }
+    phrases
+}
+
+fn flush(current: &mut Vec<String>, phrases: &mut Vec<Vec<String>>) {
+    if !current.is_empty() {
+        phrases.push(std::mem::take(current));
+    }
+}
+
+fn is_content_word(word: &str) -> bool {
+    let length = word.chars().count();
+    (3..=40).contains(&length)
+        && word.chars().any(|c| c.is_alphabetic())
+        && !word.contains('.')
+        && !STOPWORDS.contains(&word)
+}
@@ -0,0 +1,123 @@
+//! Detection of the text search configuration (`entries.language`) for prose.
+//!
+//! Non-Latin scripts map directly to a configuration (Cyrillic to `russian`, Greek
+//! to `greek`, ...). Latin-script text is scored against short lists of each
+//! language's most frequent function words. Text without a clear winner is left
+//! undetected and gets the configured default.
+
+use anyhow::{bail, Context, Result};
+use tokio_postgres::Client;
+
+/// Only the start of very large entries is examined
+const SAMPLE_CHARS: usize = 20_000;
+
+/// Function-word hits the best Latin-script language needs...
+const MIN_HITS: usize = 5;
+
+/// ...and how far ahead of the runner-up it must be
+const MIN_LEAD: f64 = 1.5;
+
+const PROFILES: &[(&str, &[&str])] = &[
+    ("english", &["the", "and", "of", "to", "is", "that", "with", "for", "this", "are", "was", "it", "be", "not", "have", "you", "which"]),
+    ("german", &["der", "die", "und", "das", "ist", "nicht", "mit", "ein", "eine", "sich", "auf", "für", "dem", "den", "wird", "auch", "ich"]),
+    ("french", &["le", "la", "les", "et", "est", "une", "des", "du", "pour", "que", "dans", "qui", "pas", "sur", "avec", "ce", "sont"]),
+    ("spanish", &["el", "los", "las", "y", "es", "una", "del", "por", "que", "para", "con", "se", "no", "como", "está", "pero", "más"]),
+    ("italian", &["il", "di", "che", "è", "per", "una", "non", "sono", "della", "con", "del", "gli", "anche", "nel", "come", "questo"]),
+    ("portuguese", &["o", "os", "de", "que", "não", "uma", "com", "para", "do", "da", "em", "é", "um", "são", "mais", "mas", "como"]),
+    ("dutch", &["de", "het", "een", "en", "van", "is", "dat", "niet", "op", "zijn", "voor", "met", "ook", "wordt", "maar", "bij"]),
+    ("swedish", &["och", "det", "att", "är", "som", "en", "på", "för", "med", "inte", "av", "den", "till", "har", "jag", "om"]),
+    ("danish", &["og", "det", "at", "er", "en", "til", "på", "med", "for", "ikke", "af", "den", "har", "som", "jeg", "de"]),
+    ("norwegian", &["og", "det", "er", "ikke", "som", "på", "til", "med", "for", "av", "jeg", "har", "en", "den", "å", "vi"]),
+    ("finnish", &["ja", "on", "ei", "se", "että", "oli", "ovat", "mutta", "kun", "niin", "myös", "tai", "joka", "ole", "hän"]),
+];
+
+/// The configuration for a non-Latin letter, if its script has one
+fn script_language(c: char) -> Option<&'static str> {
+    match c as u32 {
+        0x0400..=0x052F => Some("russian"),
+        0x0370..=0x03FF | 0x1F00..=0x1FFF => Some("greek"),
+        0x0600..=0x06FF | 0x0750..=0x077F => Some("arabic"),
+        0x0530..=0x058F => Some("armenian"),
+        0x0900..=0x097F => Some("hindi"),
+        0x0B80..=0x0BFF => Some("tamil"),
+        _ => None,
+    }
+}
+
+/// The text search configuration `text` is most likely written in, or None when
+/// there is too little evidence.
+pub fn detect(text: &str) -> Option<&'static str> {
+    let sample: String = text.chars().take(SAMPLE_CHARS).collect();
+
+    let mut latin = 0usize;
+    let mut scripts: Vec<(&str, usize)> = Vec::new();
+    for c in sample.chars().filter(|c| c.is_alphabetic()) {
+        match script_language(c) {
+            Some(language) => match scripts.iter_mut().find(|(l, _)| *l == language) {
+                Some((_, count)) => *count += 1,
+                None => scripts.push((language, 1)),
+            },
+            None if c.is_ascii_alphabetic() || ('\u{00C0}'..='\u{024F}').contains(&c) => latin += 1,
+            None => {}
+        }
+    }
+    if let Some(&(language, count)) = scripts.iter().max_by_key(|(_, count)| *count) {
+        if count > latin {
+            return Some(language);
+        }
+    }
+    if latin == 0 {
+        return None;
+    }
+
+    let lowered = sample.to_lowercase();
+    let words: Vec<&str> = lowered.split(|c: char| !c.is_alphabetic()).filter(|w| !w.is_empty()).collect();
+    let mut scores: Vec<(&str, usize)> = PROFILES
+        .iter()
+        .map(|(language, common)| (*language, words.iter().filter(|w| common.contains(w)).count()))
+        .collect();
+    scores.sort_by_key(|&(_, hits)| std::cmp::Reverse(hits));
+
+    let (best, hits) = scores[0];
+    let runner_up = scores.get(1).map(|s| s.1).unwrap_or(0);
+    (hits >= MIN_HITS && hits as f64 >= runner_up as f64 * MIN_LEAD).then_some(best)
+}
+
+/// Fail unless `name` is a text search configuration in the database
+pub async fn check(client: &Client, name: &str) -> Result<()> {
+    let exists: bool = client
+        .query_one("SELECT EXISTS (SELECT 1 FROM pg_ts_config WHERE cfgname = $1)", &[&name])
+        .await
+        .context("Failed to look up text search configurations")?
+        .get(0);
+    if !exists {
+        bail!("Unknown language '{}' (not a PostgreSQL text search configuration)", name);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_by_script() {
+        assert_eq!(detect("Повторять запрос с экспоненциальной задержкой, see RetryPolicy"), Some("russian"));
+        assert_eq!(detect("Η αναφορά είναι έτοιμη"), Some("greek"));
+    }
+
+    #[test]
+    fn detects_latin_languages_by_function_words() {
+        let english = "The worker retries the request with exponential backoff, and it is not allowed to exceed the limit that was set for this queue.";
+        let german = "Der Worker wiederholt die Anfrage mit exponentiellem Backoff, und das Limit ist nicht zu überschreiten, weil die Warteschlange sich auf den Wert verlässt.";
+        assert_eq!(detect(english), Some("english"));
+        assert_eq!(detect(german), Some("german"));
+    }
+
+    #[test]
+    fn leaves_short_or_ambiguous_text_undetected() {
+        assert_eq!(detect("Retry spec"), None);
+        assert_eq!(detect("fn main() { println!(\"{}\", x); }"), None);
+        assert_eq!(detect("12345 -- !!"), None);
+    }
+}
@@ -0,0 +1,152 @@
+//! Typed links between entries (the `entry_links` table).
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::collections::HashSet;
+use tokio_postgres::{Client, GenericClient};
+
+/// Allowed values of `entry_links.link_type`
+pub const LINK_TYPES: &[&str] = &["derived_from", "references", "summarizes", "duplicates", "follows_up"];
+
+/// Returns false if the link already existed.
+pub async fn add(client: &impl GenericClient, from_id: i32, to_id: i32, link_type: &str, auto: bool) -> Result<bool> {
+    if !LINK_TYPES.contains(&link_type) {
+        bail!("Unknown link type '{}'. Use one of: {}", link_type, LINK_TYPES.join(", "));
+    }
+    if from_id == to_id {
+        bail!("An entry cannot link to itself");
+    }
+    let added = client
+        .execute(
+            r#"
+            INSERT INTO entry_links (from_id, to_id, link_type, auto)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT DO NOTHING
+            "#,
+            &[&from_id, &to_id, &link_type, &auto],
+        )
+        .await
+        .context("Failed to add link")?;
+    Ok(added > 0)
+}
+
+/// Remove links from `from_id` to `to_id`, of one type or all of them.
+pub async fn remove(client: &Client, from_id: i32, to_id: i32, link_type: Option<&str>) -> Result<u64> {
+    client
+        .execute(
+            "DELETE FROM entry_links WHERE from_id = $1 AND to_id = $2 AND ($3::text IS NULL OR link_type = $3)",
+            &[&from_id, &to_id, &link_type],
+        )
+        .await
+        .context("Failed to remove link")
+}
+
+/// An entry reached from the starting entry
+#[derive(Serialize)]
+pub struct Related {
+    pub id: i32,
+    pub depth: i32,
+    /// The entry it was reached from
+    pub via: i32,
+    pub link_type: String,
+    /// Whether the link points away from `via` (`via -> id`)
+    pub outgoing: bool,
+    pub entry_type: String,
+    pub label: String,
+}
+
+/// Deepest walk `related` makes; paths multiply with every level
+pub const MAX_DEPTH: i32 = 5;
+
+/// Entries within `depth` links of `id` (1 to `MAX_DEPTH`), following links in
+/// both directions. Each entry is reported once, at its shortest distance.
+pub async fn related(client: &Client, id: i32, depth: i32) -> Result<Vec<Related>> {
+    let depth = depth.clamp(1, MAX_DEPTH);
+    let rows = client
+        .query(
+            r#"
+            WITH RECURSIVE edges AS (
+                SELECT from_id AS a, to_id AS b, link_type, TRUE AS outgoing FROM entry_links
+                UNION ALL
+                SELECT to_id, from_id, link_type, FALSE FROM entry_links
+            ),
+            walk (id, depth, via, link_type, outgoing, path) AS (
+                SELECT b, 1, a, link_type, outgoing, ARRAY[a, b] FROM edges WHERE a = $1
+                UNION ALL
+                SELECT e.b, w.depth + 1, e.a, e.link_type, e.outgoing, w.path || e.b
+                FROM walk w
+                JOIN edges e ON e.a = w.id
+                WHERE w.depth < $2 AND NOT e.b = ANY(w.path)
+            ),
+            nearest AS (
+                SELECT DISTINCT ON (id) id, depth, via, link_type, outgoing
+                FROM walk
+                ORDER BY id, depth, via
+            )
+            SELECT n.id, n.depth, n.via, n.link_type, n.outgoing, e.entry_type,
+                   COALESCE(e.metadata->>'title', e.file_path, left(regexp_replace(e.content, '\s+', ' ', 'g'), 60)) AS label
+            FROM nearest n
+            JOIN entries e ON e.id = n.id
+            WHERE n.id <> $1 AND e.deleted_at IS NULL
+            ORDER BY n.depth, n.id
+            "#,
+            &[&id, &depth],
+        )
+        .await
+        .context("Failed to query related entries")?;
+
+    Ok(rows
+        .iter()
+        .map(|row| Related {
+            id: row.get("id"),
+            depth: row.get("depth"),
+            via: row.get("via"),
+            link_type: row.get("link_type"),
+            outgoing: row.get("outgoing"),
+            entry_type: row.get("entry_type"),
+            label: row.get("label"),
+        })
+        .collect())
+}
+
+/// Add `references` links from a chat to the entries it mentions: indexed files by
+/// absolute path (or repository-relative path for `ingest --repo` entries), and
+/// entries whose `metadata.ticket_id` appears as a word. Returns the linked ids.
+pub async fn detect_mentions(client: &Client, id: i32, content: &str) -> Result<Vec<i32>> {
+    // Candidate ticket ids: words with a digit, e.g. PROJ-123 or #42
+    let tokens: Vec<String> = content
+        .split(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | '#')))
+        .map(|t| t.trim_matches(['-', '_']))
+        .filter(|t| t.len() >= 2 && t.chars().any(|c| c.is_ascii_digit()))
+        .map(str::to_string)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+
+    let rows = client
+        .query(
+            r#"
+            SELECT id FROM entries
+            WHERE id <> $1
+              AND deleted_at IS NULL
+              AND entry_type NOT IN ('chat', 'summary')
+              AND (
+                  (file_path IS NOT NULL AND strpos($2, file_path) > 0)
+                  OR (metadata ? 'git' AND metadata->>'title' LIKE '%/%' AND strpos($2, metadata->>'title') > 0)
+                  OR metadata->>'ticket_id' = ANY($3)
+              )
+            ORDER BY id
+            "#,
+            &[&id, &content, &tokens],
+        )
+        .await
+        .context("Failed to look up mentioned entries")?;
+
+    let mut linked = Vec::new();
+    for row in rows {
+        let to_id: i32 = row.get(0);
+        add(client, id, to_id, "references", true).await?;
+        linked.push(to_id);
+    }
+    Ok(linked)
+}
@@ -2,9 +2,24 @@ use anyhow::Result;
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
+mod blobs;
+mod code;
 mod commands;
 mod config;
+mod db;
 mod docker;
+mod entries;
+mod extract;
+mod fingerprint;
+mod git;
+mod keywords;
+mod language;
+mod links;
+mod server;
+mod summary;
+mod systemd;
+mod tui;
+mod versions;
 
 #[derive(Parser)]
 #[command(name = "elastic-claude")]
@@ -29,44 +44,314 @@ enum Commands {
     /// Show status of elastic-claude
     Status,
 
+    /// Diagnose the installation and suggest fixes
+    Doctor,
+
     /// Remove elastic-claude installation
     Destroy {
         /// Also remove the data volume
         #[arg(long)]
         include_data: bool,
+
+        /// Skip the automatic backup taken before removing data
+        #[arg(long, requires = "include_data")]
+        no_backup: bool,
+    },
+
+    /// Dump the database to a timestamped backup file
+    Backup {
+        /// List existing backups instead of creating one
+        #[arg(long, conflicts_with = "schedule")]
+        list: bool,
+
+        /// Schedule backups with a systemd user timer ("off" removes it)
+        #[arg(long, value_parser = ["hourly", "daily", "weekly", "off"])]
+        schedule: Option<String>,
+    },
+
+    /// Restore the database from a backup file
+    Restore {
+        /// Backup file created by 'elastic-claude backup'
+        file: PathBuf,
+
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
     },
 
+    /// Migrate the data volume to the PostgreSQL version this binary uses
+    UpgradeDb,
+
     /// Add an entry to the knowledge base (used by skill)
     Add {
         /// Entry type (e.g., "document", "chat", "code")
         #[arg(short = 't', long)]
         entry_type: String,
 
-        /// Content to store (inline)
+        /// Content to store (inline), or the description of an attachment
         #[arg(short, long, conflicts_with = "path")]
         content: Option<String>,
 
         /// Read content from file path
-        #[arg(short, long, conflicts_with = "content")]
+        #[arg(short, long, conflicts_with_all = ["content", "attach"])]
         path: Option<String>,
 
+        /// Attach a binary file (image, diagram, PDF); -c or stdin describes it
+        #[arg(long)]
+        attach: Option<String>,
+
         /// JSON metadata (optional)
         #[arg(short, long)]
         metadata: Option<String>,
+
+        /// Text search language (e.g. "english", "russian") instead of detecting it
+        #[arg(long)]
+        lang: Option<String>,
     },
 
     /// Ingest files into the knowledge base
     Ingest {
         /// Glob patterns for files to ingest
-        #[arg(required = true)]
+        #[arg(required_unless_present = "repo", conflicts_with = "repo")]
+        patterns: Vec<String>,
+
+        /// Ingest a git working tree, honouring .gitignore, .ignore and .elastic-claude-ignore
+        #[arg(long, value_name = "DIR")]
+        repo: Option<PathBuf>,
+
+        /// Entry type (default: "document" for docs, "code" for everything else)
+        #[arg(short = 't', long = "type", requires = "repo")]
+        entry_type: Option<String>,
+
+        /// JSON metadata merged into every entry (optional)
+        #[arg(short, long, requires = "repo")]
+        metadata: Option<String>,
+
+        /// Skip files larger than this many bytes
+        #[arg(long, default_value_t = 1_048_576, requires = "repo")]
+        max_size: u64,
+
+        /// Text search language for every file (e.g. "english", "russian") instead of detecting it
+        #[arg(long, requires = "repo")]
+        lang: Option<String>,
+    },
+
+    /// Watch files and keep their entries in sync
+    Watch {
+        /// Directories, files, or glob patterns to watch
         patterns: Vec<String>,
+
+        /// Entry type for newly indexed files
+        #[arg(short = 't', long = "type", default_value = "document")]
+        entry_type: String,
+
+        /// JSON metadata merged into every indexed file (optional)
+        #[arg(short, long)]
+        metadata: Option<String>,
+
+        /// What to do with entries whose file is deleted
+        #[arg(long, default_value = "mark", value_parser = ["keep", "mark", "remove"])]
+        on_delete: String,
+
+        /// Milliseconds to wait for changes to settle
+        #[arg(long, default_value_t = 500)]
+        debounce_ms: u64,
+
+        /// Install and start a systemd user service running this watch
+        #[arg(long, conflicts_with = "uninstall_service")]
+        install_service: bool,
+
+        /// Stop and remove the systemd user service
+        #[arg(long)]
+        uninstall_service: bool,
+    },
+
+    /// Extract keywords into metadata.keywords for existing entries
+    Retag {
+        /// Recompute keywords for entries that already have them
+        #[arg(long)]
+        all: bool,
+
+        /// Only entries of this type (code entries are skipped otherwise)
+        #[arg(short = 't', long = "type")]
+        entry_type: Option<String>,
+
+        /// Only entries whose metadata.project matches
+        #[arg(long)]
+        project: Option<String>,
+    },
+
+    /// Add, list or remove synonyms that search treats as the same term
+    Synonyms {
+        #[command(subcommand)]
+        action: SynonymsCommand,
     },
 
     /// Search the knowledge base
     Search {
         /// Search query
-        #[arg(required = true)]
-        query: String,
+        #[arg(required_unless_present = "symbol")]
+        query: Option<String>,
+
+        /// Look up the definition of a code symbol (e.g. "parse_http_request" or "HttpClient::new")
+        #[arg(long, conflicts_with = "query")]
+        symbol: Option<String>,
+
+        /// Match by trigram similarity, tolerating typos and partial identifiers
+        #[arg(long, conflicts_with = "symbol")]
+        fuzzy: bool,
+
+        /// Rebuild the vocabulary used for typo suggestions if entries changed since
+        /// it was last built (ingest, gc and serve also do this)
+        #[arg(long, conflicts_with = "symbol")]
+        refresh: bool,
+
+        /// Only entries of this type
+        #[arg(short = 't', long = "type")]
+        entry_type: Option<String>,
+
+        /// Only entries whose metadata.project matches
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Only entries in this text search language (e.g. "english", "russian")
+        #[arg(long)]
+        lang: Option<String>,
+
+        /// Maximum number of results
+        #[arg(short = 'n', long, default_value = "10")]
+        limit: i64,
+
+        /// Rank function: ts_rank, or ts_rank_cd to favour words close together (default: search.ranking in config.yaml)
+        #[arg(long)]
+        rank: Option<String>,
+
+        /// ts_rank normalization bit mask (e.g. 1 penalizes long entries, 32 scales to 0-1)
+        #[arg(long)]
+        normalization: Option<i32>,
+
+        /// Halve scores for every this many days of age (0 ignores age)
+        #[arg(long)]
+        half_life: Option<f64>,
+
+        /// Section weights D,C,B,A: body is C, headings B, title A (e.g. 0.1,0.2,0.4,1.0)
+        #[arg(long)]
+        weights: Option<String>,
+
+        /// Multiply scores of an entry type, e.g. --boost document=1.5 --boost chat=0.5
+        #[arg(long, value_delimiter = ',')]
+        boost: Vec<String>,
+    },
+
+    /// Find entries similar to a given one
+    Similar {
+        /// Entry ID
+        id: i32,
+
+        /// Maximum number of entries to show
+        #[arg(short = 'n', long, default_value = "10")]
+        limit: i64,
+
+        /// Only entries of this type
+        #[arg(short = 't', long = "type")]
+        entry_type: Option<String>,
+
+        /// Only entries whose metadata.project matches
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Only entries in this text search language (default: the entry's language)
+        #[arg(long)]
+        lang: Option<String>,
+    },
+
+    /// Print the most relevant excerpts for a task as Markdown, within a token budget
+    Context {
+        /// Description of the task
+        task: String,
+
+        /// Approximate number of tokens to fill
+        #[arg(long, default_value = "8000")]
+        budget: usize,
+
+        /// Number of matching entries to draw excerpts from
+        #[arg(short = 'n', long, default_value = "20")]
+        limit: i64,
+
+        /// Only entries of this type
+        #[arg(short = 't', long = "type")]
+        entry_type: Option<String>,
+
+        /// Only entries whose metadata.project matches
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Only entries in this text search language (e.g. "english", "russian")
+        #[arg(long)]
+        lang: Option<String>,
+    },
+
+    /// Write extractive summaries of chats and documents as summary entries
+    Summarize {
+        /// Entry ID to summarize
+        #[arg(required_unless_present = "since", conflicts_with = "since")]
+        id: Option<i32>,
+
+        /// Summarize entries created since a date (2025-01-15) or duration (12h, 7d, 2w)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Combine entries into one summary per project and period
+        #[arg(long, value_parser = ["daily", "weekly"], requires = "since")]
+        rollup: Option<String>,
+
+        /// Entry type to summarize with --since (default: chat and document)
+        #[arg(short = 't', long = "type")]
+        entry_type: Option<String>,
+
+        /// Only entries whose metadata.project matches
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Number of sentences to keep
+        #[arg(short = 'n', long, default_value_t = summary::DEFAULT_SENTENCES)]
+        sentences: usize,
+
+        /// Summarizer backend, overriding summarizer.backend in config.yaml
+        #[arg(long, value_parser = ["textrank", "openai"])]
+        backend: Option<String>,
+    },
+
+    /// Link two entries (FROM <type> TO)
+    Link {
+        /// Entry the link starts from
+        #[arg(required_unless_present = "detect")]
+        from: Option<i32>,
+
+        /// Entry the link points to
+        #[arg(required_unless_present = "detect")]
+        to: Option<i32>,
+
+        /// Link type
+        #[arg(short = 't', long = "type", default_value = "references", value_parser = links::LINK_TYPES.to_vec())]
+        link_type: String,
+
+        /// Rescan all chats for mentions of indexed files and ticket ids
+        #[arg(long, conflicts_with_all = ["from", "to"])]
+        detect: bool,
+    },
+
+    /// Remove links between two entries
+    Unlink {
+        /// Entry the link starts from
+        from: i32,
+
+        /// Entry the link points to
+        to: i32,
+
+        /// Only remove links of this type (default: all)
+        #[arg(short = 't', long = "type", value_parser = links::LINK_TYPES.to_vec())]
+        link_type: Option<String>,
     },
 
     /// Ingest a chat session
@@ -74,6 +359,10 @@ enum Commands {
         /// Path to chat session file
         #[arg(required = true)]
         session_file: PathBuf,
+
+        /// Also write a summary entry (and a title, if the chat has none)
+        #[arg(long)]
+        summarize: bool,
     },
 
     /// Find and optionally ingest current Claude chat session
@@ -99,6 +388,187 @@ enum Commands {
         /// Show the tsvector (full-text search tokens)
         #[arg(long)]
         tsv: bool,
+
+        /// Output the stored file (attachment or original document) instead
+        #[arg(long, conflicts_with_all = ["content_only", "tsv"])]
+        blob: bool,
+
+        /// File to write the blob to (default: stdout)
+        #[arg(short, long, requires = "blob")]
+        output: Option<String>,
+
+        /// List linked entries instead of the entry itself
+        #[arg(long, conflicts_with_all = ["content_only", "tsv", "blob"])]
+        related: bool,
+
+        /// How many links to follow with --related (1-5)
+        #[arg(long, default_value_t = 1, requires = "related")]
+        depth: i32,
+
+        /// List earlier versions of the entry
+        #[arg(long, conflicts_with_all = ["content_only", "tsv", "blob", "related"])]
+        history: bool,
+
+        /// Show one version (see --history)
+        #[arg(long, conflicts_with_all = ["tsv", "blob", "related", "history"])]
+        version: Option<i32>,
+    },
+
+    /// List recent entries
+    List {
+        /// Only entries of this type
+        #[arg(short = 't', long = "type")]
+        entry_type: Option<String>,
+
+        /// Only entries whose metadata.project matches
+        #[arg(long)]
+        project: Option<String>,
+
+        /// Number of entries to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: i64,
+    },
+
+    /// Change an entry's content or metadata (the old content is kept as a version)
+    Update {
+        /// Entry ID
+        id: i32,
+
+        /// New content
+        #[arg(short, long)]
+        content: Option<String>,
+
+        /// Read new content from a file
+        #[arg(short, long)]
+        path: Option<String>,
+
+        /// JSON metadata to merge in (null values remove keys)
+        #[arg(short, long)]
+        metadata: Option<String>,
+
+        /// Change the text search language (e.g. "english", "russian")
+        #[arg(long)]
+        lang: Option<String>,
+    },
+
+    /// Move entries to the trash
+    Delete {
+        /// Entry IDs
+        #[arg(required = true)]
+        ids: Vec<i32>,
+    },
+
+    /// Report groups of near-duplicate entries
+    Dedupe {
+        /// Share of the smaller entry's text found in the larger one (0-1)
+        #[arg(long, default_value = "0.9")]
+        threshold: f64,
+
+        /// Only entries of this type
+        #[arg(short = 't', long = "type")]
+        entry_type: Option<String>,
+
+        /// Only entries whose metadata.project matches
+        #[arg(long)]
+        project: Option<String>,
+    },
+
+    /// Merge duplicate entries into one and move the rest to the trash
+    Merge {
+        /// Entry to keep
+        keep: i32,
+
+        /// Entries folded into it
+        #[arg(required = true)]
+        drop: Vec<i32>,
+
+        /// Remove the dropped entries permanently instead of trashing them
+        #[arg(long)]
+        purge: bool,
+    },
+
+    /// List, restore or permanently remove trashed entries
+    Trash {
+        #[command(subcommand)]
+        action: TrashCommand,
+    },
+
+    /// Apply retention rules from config.yaml (dry run unless --apply)
+    Gc {
+        /// Move matching entries to the trash and purge expired ones
+        #[arg(long)]
+        apply: bool,
+    },
+
+    /// Search interactively in the terminal (Enter prints the selected entry to stdout)
+    Tui,
+
+    /// Serve the HTTP/JSON API
+    Serve {
+        /// Address to listen on (default: server.bind in config.yaml, 127.0.0.1:8765)
+        #[arg(long)]
+        bind: Option<String>,
+    },
+
+    /// Show a unified diff between two versions of an entry
+    Diff {
+        /// Entry ID
+        id: i32,
+
+        /// Older version (default: the one before the newer version)
+        from: Option<i32>,
+
+        /// Newer version (default: current)
+        to: Option<i32>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TrashCommand {
+    /// List trashed entries
+    List,
+
+    /// Take entries out of the trash
+    Restore {
+        /// Entry IDs
+        #[arg(required = true)]
+        ids: Vec<i32>,
+    },
+
+    /// Permanently remove trashed entries
+    Empty {
+        /// Only entries deleted at least this many days ago
+        #[arg(long)]
+        older_than_days: Option<u32>,
+
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SynonymsCommand {
+    /// Make synonyms match a term and each other (e.g. kubernetes k8s kube)
+    Add {
+        /// Term or phrase
+        term: String,
+
+        /// Abbreviations, alternative spellings or jargon for it
+        #[arg(required = true)]
+        synonyms: Vec<String>,
+    },
+
+    /// List terms and their synonyms
+    List,
+
+    /// Remove synonyms of a term, or all of them
+    Remove {
+        /// Term or phrase
+        term: String,
+
+        /// Synonyms to remove (default: all)
+        synonyms: Vec<String>,
     },
 }
 
@@ -111,18 +581,151 @@ async fn main() -> Result<()> {
         Commands::Start => commands::start::run().await,
         Commands::Stop => commands::stop::run().await,
         Commands::Status => commands::status::run().await,
-        Commands::Destroy { include_data } => commands::destroy::run(include_data).await,
-        Commands::Add { entry_type, content, path, metadata } => {
-            commands::add::run(entry_type, content, path, metadata).await
+        Commands::Doctor => commands::doctor::run().await,
+        Commands::Destroy { include_data, no_backup } => {
+            commands::destroy::run(include_data, no_backup).await
         }
-        Commands::Ingest { patterns } => commands::ingest::run(patterns).await,
-        Commands::Search { query } => commands::search::run(query).await,
-        Commands::Chat { session_file } => commands::chat::run(session_file).await,
+        Commands::Backup { list, schedule } => commands::backup::run(list, schedule).await,
+        Commands::Restore { file, yes } => commands::restore::run(file, yes).await,
+        Commands::UpgradeDb => commands::upgrade_db::run().await,
+        Commands::Add { entry_type, content, path, attach, metadata, lang } => {
+            commands::add::run(entry_type, content, path, attach, metadata, lang).await
+        }
+        Commands::Ingest { patterns, repo, entry_type, metadata, max_size, lang } => {
+            let repo = repo.map(|dir| commands::ingest::RepoOptions {
+                dir,
+                entry_type,
+                metadata,
+                max_size,
+                language: lang,
+            });
+            commands::ingest::run(patterns, repo).await
+        }
+        Commands::Watch {
+            patterns,
+            entry_type,
+            metadata,
+            on_delete,
+            debounce_ms,
+            install_service,
+            uninstall_service,
+        } => {
+            commands::watch::run(commands::watch::WatchOptions {
+                patterns,
+                entry_type,
+                metadata,
+                on_delete,
+                debounce_ms,
+                install_service,
+                uninstall_service,
+            })
+            .await
+        }
+        Commands::Retag { all, entry_type, project } => commands::retag::run(all, entry_type, project).await,
+        Commands::Synonyms { action } => {
+            let action = match action {
+                SynonymsCommand::Add { term, synonyms } => commands::synonyms::Action::Add { term, synonyms },
+                SynonymsCommand::List => commands::synonyms::Action::List,
+                SynonymsCommand::Remove { term, synonyms } => commands::synonyms::Action::Remove { term, synonyms },
+            };
+            commands::synonyms::run(action).await
+        }
+        Commands::Search {
+            query,
+            symbol,
+            fuzzy,
+            refresh,
+            entry_type,
+            project,
+            lang,
+            limit,
+            rank,
+            normalization,
+            half_life,
+            weights,
+            boost,
+        } => {
+            commands::search::run(commands::search::SearchOptions {
+                query,
+                symbol,
+                fuzzy,
+                refresh,
+                filters: commands::search::Filters { entry_type, project, language: lang },
+                ranking: commands::search::RankingOverrides {
+                    function: rank,
+                    normalization,
+                    half_life_days: half_life,
+                    weights,
+                    boosts: boost,
+                },
+                limit,
+            })
+            .await
+        }
+        Commands::Similar { id, limit, entry_type, project, lang } => {
+            commands::similar::run(id, commands::search::Filters { entry_type, project, language: lang }, limit).await
+        }
+        Commands::Context { task, budget, limit, entry_type, project, lang } => {
+            commands::context::run(commands::context::ContextOptions {
+                task,
+                budget,
+                limit,
+                filters: commands::search::Filters { entry_type, project, language: lang },
+            })
+            .await
+        }
+        Commands::Summarize { id, since, rollup, entry_type, project, sentences, backend } => {
+            commands::summarize::run(commands::summarize::SummarizeOptions {
+                id,
+                since,
+                rollup,
+                entry_type,
+                project,
+                sentences,
+                backend,
+            })
+            .await
+        }
+        Commands::Link { from, to, link_type, detect } => commands::link::run(from, to, link_type, detect).await,
+        Commands::Unlink { from, to, link_type } => commands::unlink::run(from, to, link_type).await,
+        Commands::Chat { session_file, summarize } => commands::chat::run(session_file, summarize).await,
         Commands::CurrentChat { path_only, metadata } => {
             commands::current_chat::run(path_only, metadata).await
         }
-        Commands::Get { id, content_only, tsv } => {
-            commands::get::run(id, content_only, tsv).await
+        Commands::Get { id, content_only, tsv, blob, output, related, depth, history, version } => {
+            commands::get::run(commands::get::GetOptions {
+                id,
+                content_only,
+                show_tsv: tsv,
+                blob,
+                output,
+                related,
+                depth,
+                history,
+                version,
+            })
+            .await
+        }
+        Commands::List { entry_type, project, limit } => commands::list::run(entry_type, project, limit).await,
+        Commands::Update { id, content, path, metadata, lang } => {
+            commands::update::run(id, content, path, metadata, lang).await
+        }
+        Commands::Delete { ids } => commands::delete::run(ids).await,
+        Commands::Dedupe { threshold, entry_type, project } => {
+            commands::dedupe::run(threshold, commands::search::Filters { entry_type, project, language: None }).await
+        }
+        Commands::Merge { keep, drop, purge } => commands::merge::run(keep, drop, purge).await,
+        Commands::Trash { action } => {
+            let action = match action {
+                TrashCommand::List => commands::trash::Action::List,
+                TrashCommand::Restore { ids } => commands::trash::Action::Restore(ids),
+                TrashCommand::Empty { older_than_days, yes } => commands::trash::Action::Empty { older_than_days, yes },
+            };
+            commands::trash::run(action).await
         }
+        Commands::Gc { apply } => commands::gc::run(apply).await,
+        Commands::Tui => commands::tui::run().await,
+        Commands::Serve { bind } => commands::serve::run(bind).await,
+        Commands::Diff { id, from, to } => commands::diff::run(id, from, to).await,
     }
 }
@@ -0,0 +1,458 @@
+//! `elastic-claude serve`: a local HTTP/JSON API and the web UI on top of it.
+//! Handlers call the same query and write functions as the CLI commands.
+
+mod ui;
+
+use anyhow::{bail, Context, Result};
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio_postgres::Client;
+use crate::commands::{current_chat, get as get_cmd, list, search, status};
+use crate::config::Config;
+use crate::{db, entries, language, links, versions};
+
+/// OpenAPI 3.1 description of the routes below
+const OPENAPI: &str = include_str!("openapi.json");
+
+/// Largest `limit` accepted by search and list
+const MAX_LIMIT: i64 = 200;
+
+/// How often the server checks whether the search vocabulary is out of date
+const VOCABULARY_REFRESH: Duration = Duration::from_secs(300);
+
+pub struct AppState {
+    config: Config,
+    client: RwLock<Arc<Client>>,
+    /// Address the server listens on, accepted in `Host` headers
+    addr: SocketAddr,
+}
+
+impl AppState {
+    /// The shared connection, re-established if the database went away.
+    async fn client(&self) -> Result<Arc<Client>, ApiError> {
+        let client = self.client.read().await.clone();
+        if !client.is_closed() {
+            return Ok(client);
+        }
+        let mut guard = self.client.write().await;
+        if guard.is_closed() {
+            *guard = Arc::new(db::connect(&self.config).await?);
+        }
+        Ok(guard.clone())
+    }
+}
+
+/// Serve until Ctrl+C. Every API request needs `server.token`.
+pub async fn serve(config: Config, addr: SocketAddr) -> Result<()> {
+    if config.server.token.as_deref().is_none_or(str::is_empty) {
+        bail!("Refusing to serve without a token. Set server.token in {}.", Config::config_path()?.display());
+    }
+
+    let client = db::connect(&config).await?;
+    let version = db::schema_version(&client).await?.unwrap_or(0);
+    if version < db::latest_version() {
+        bail!("Database schema is at version {}. Run 'elastic-claude init' to upgrade it.", version);
+    }
+
+    let state = Arc::new(AppState {
+        config,
+        client: RwLock::new(Arc::new(client)),
+        addr,
+    });
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Could not listen on {}", addr))?;
+
+    println!("Listening on http://{}", addr);
+    println!("Web UI: http://{}/", addr);
+    println!("OpenAPI document: http://{}/api/openapi.json", addr);
+
+    tokio::spawn(refresh_vocabulary(state.clone()));
+    axum::serve(listener, router(state))
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+        .context("Server error")
+}
+
+/// Rebuild the search vocabulary in the background when entries change, so
+/// searches never wait on it.
+async fn refresh_vocabulary(state: Arc<AppState>) {
+    let mut interval = tokio::time::interval(VOCABULARY_REFRESH);
+    loop {
+        interval.tick().await;
+        let Ok(client) = state.client().await else { continue };
+        if let Err(e) = search::refresh_vocabulary(&client).await {
+            eprintln!("Warning: {:#}", e);
+        }
+    }
+}
+
+pub fn router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/api/status", get(status_handler))
+        .route("/api/search", get(search_handler))
+        .route("/api/entries", get(list_handler).post(add_handler))
+        .route(
+            "/api/entries/{id}",
+            get(get_handler).patch(update_handler).delete(delete_handler),
+        )
+        .route("/api/entries/{id}/related", get(related_handler))
+        .route("/api/entries/{id}/conversation", get(conversation_handler))
+        .route_layer(middleware::from_fn_with_state(state.clone(), authorize))
+        // The API description and the UI's static files are public; the UI asks
+        // for the token itself
+        .route("/api/openapi.json", get(openapi_handler))
+        .merge(ui::routes())
+        .layer(middleware::from_fn_with_state(state.clone(), check_host))
+        .with_state(state)
+}
+
+/// Reject requests addressed to another host name, so a web page cannot reach the
+/// server through DNS rebinding. Loopback names and the bound address are accepted;
+/// a server bound to all interfaces accepts any name.
+async fn check_host(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let host = request.headers().get(header::HOST).and_then(|v| v.to_str().ok()).unwrap_or("");
+    if allowed_host(host, &state.addr) {
+        next.run(request).await
+    } else {
+        ApiError::new(StatusCode::FORBIDDEN, format!("Host '{}' is not allowed", host)).into_response()
+    }
+}
+
+fn allowed_host(host: &str, addr: &SocketAddr) -> bool {
+    // Strip the port: "[::1]:8765" -> "::1", "localhost:8765" -> "localhost"
+    let name = match host.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or(""),
+        None => host.rsplit_once(':').map_or(host, |(name, _)| name),
+    };
+    if name.eq_ignore_ascii_case("localhost") || addr.ip().is_unspecified() {
+        return true;
+    }
+    name.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback() || ip == addr.ip())
+}
+
+/// Require `Authorization: Bearer <server.token>`.
+async fn authorize(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let token = state.config.server.token.as_deref().unwrap_or_default();
+    let given = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    match given {
+        Some(given) if !token.is_empty() && constant_time_eq(given.as_bytes(), token.as_bytes()) => {
+            next.run(request).await
+        }
+        _ => ApiError::new(StatusCode::UNAUTHORIZED, "Missing or invalid bearer token").into_response(),
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// An error response: `{"error": "..."}` with a status code
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn not_found(id: i32) -> Self {
+        Self::new(StatusCode::NOT_FOUND, format!("Entry {} not found", id))
+    }
+}
+
+/// 400 unless `language` is absent or a text search configuration
+async fn check_language(client: &Client, language: Option<&str>) -> ApiResult<()> {
+    if let Some(language) = language {
+        language::check(client, language)
+            .await
+            .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e.to_string()))?;
+    }
+    Ok(())
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", error))
+    }
+}
+
+/// Malformed bodies, paths and query strings get the same JSON error shape
+macro_rules! from_rejection {
+    ($($rejection:ty),*) => {$(
+        impl From<$rejection> for ApiError {
+            fn from(rejection: $rejection) -> Self {
+                Self::new(rejection.status(), rejection.body_text())
+            }
+        }
+    )*};
+}
+
+from_rejection!(JsonRejection, PathRejection, QueryRejection);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(serde_json::json!({ "error": self.message }))).into_response()
+    }
+}
+
+type ApiResult<T> = std::result::Result<T, ApiError>;
+
+async fn openapi_handler() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "application/json")], OPENAPI)
+}
+
+async fn status_handler(State(state): State<Arc<AppState>>) -> ApiResult<Json<serde_json::Value>> {
+    let client = state.client().await?;
+    let schema_version = db::schema_version(&client).await?;
+    let counts = status::entry_counts(&client).await?;
+    let by_type: serde_json::Map<String, serde_json::Value> =
+        counts.by_type.into_iter().map(|(t, n)| (t, n.into())).collect();
+    Ok(Json(serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "schema_version": schema_version,
+        "entries": by_type,
+        "trash": counts.trash,
+    })))
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    q: String,
+    #[serde(rename = "type")]
+    entry_type: Option<String>,
+    project: Option<String>,
+    limit: Option<i64>,
+    #[serde(default)]
+    fuzzy: bool,
+    language: Option<String>,
+    rank: Option<String>,
+    normalization: Option<i32>,
+    half_life: Option<f64>,
+    weights: Option<String>,
+    /// Comma-separated `type=weight` pairs
+    boost: Option<String>,
+}
+
+async fn search_handler(
+    State(state): State<Arc<AppState>>,
+    params: Result<Query<SearchParams>, QueryRejection>,
+) -> ApiResult<Json<Vec<search::SearchHit>>> {
+    let Query(params) = params?;
+    if params.q.trim().is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "Query parameter q is empty"));
+    }
+    let client = state.client().await?;
+    let limit = params.limit.unwrap_or(10).clamp(1, MAX_LIMIT);
+    let filters = search::Filters {
+        entry_type: params.entry_type.filter(|t| !t.is_empty()),
+        project: params.project.filter(|p| !p.is_empty()),
+        language: params.language.filter(|l| !l.is_empty()),
+    };
+    check_language(&client, filters.language.as_deref()).await?;
+    let overrides = search::RankingOverrides {
+        function: params.rank,
+        normalization: params.normalization,
+        half_life_days: params.half_life,
+        weights: params.weights,
+        boosts: params.boost.iter().flat_map(|b| b.split(',')).filter(|b| !b.is_empty()).map(str::to_string).collect(),
+    };
+    let ranking = overrides
+        .apply(&state.config.search.ranking)
+        .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, e.to_string()))?;
+    let hits = if params.fuzzy {
+        search::fuzzy_search(&client, &params.q, &filters, limit).await?
+    } else {
+        search::search(&client, &params.q, &filters, &ranking, limit).await?
+    };
+    Ok(Json(hits))
+}
+
+#[derive(Deserialize)]
+struct ListParams {
+    #[serde(rename = "type")]
+    entry_type: Option<String>,
+    project: Option<String>,
+    limit: Option<i64>,
+}
+
+async fn list_handler(
+    State(state): State<Arc<AppState>>,
+    params: Result<Query<ListParams>, QueryRejection>,
+) -> ApiResult<Json<Vec<list::ListedEntry>>> {
+    let Query(params) = params?;
+    let client = state.client().await?;
+    let limit = params.limit.unwrap_or(20).clamp(1, MAX_LIMIT);
+    let entry_type = params.entry_type.as_deref().filter(|t| !t.is_empty());
+    let project = params.project.as_deref().filter(|p| !p.is_empty());
+    let listed = list::list(&client, entry_type, project, limit).await?;
+    Ok(Json(listed))
+}
+
+async fn get_handler(
+    State(state): State<Arc<AppState>>,
+    id: Result<Path<i32>, PathRejection>,
+) -> ApiResult<Json<get_cmd::Entry>> {
+    let Path(id) = id?;
+    let client = state.client().await?;
+    match get_cmd::fetch(&client, id).await? {
+        Some(entry) => Ok(Json(entry)),
+        None => Err(ApiError::not_found(id)),
+    }
+}
+
+#[derive(Deserialize)]
+struct RelatedParams {
+    depth: Option<i32>,
+}
+
+async fn related_handler(
+    State(state): State<Arc<AppState>>,
+    id: Result<Path<i32>, PathRejection>,
+    params: Result<Query<RelatedParams>, QueryRejection>,
+) -> ApiResult<Json<Vec<links::Related>>> {
+    let Path(id) = id?;
+    let Query(params) = params?;
+    let client = state.client().await?;
+    let depth = params.depth.unwrap_or(1);
+    Ok(Json(links::related(&client, id, depth).await?))
+}
+
+/// A chat as speaker turns. The session file still on disk has the roles; otherwise
+/// the indexed text is split into paragraphs.
+async fn conversation_handler(
+    State(state): State<Arc<AppState>>,
+    id: Result<Path<i32>, PathRejection>,
+) -> ApiResult<Json<serde_json::Value>> {
+    let Path(id) = id?;
+    let client = state.client().await?;
+    let entry = get_cmd::fetch(&client, id).await?.ok_or_else(|| ApiError::not_found(id))?;
+    if entry.entry_type != "chat" {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, format!("Entry {} is not a chat", id)));
+    }
+
+    let session = entry.file_path.as_deref().and_then(session_file).and_then(|path| std::fs::read_to_string(path).ok());
+    if let Some(turns) = session.map(|raw| current_chat::extract_turns_from_jsonl(&raw)).filter(|t| !t.is_empty()) {
+        return Ok(Json(serde_json::json!({ "source": "file", "turns": turns })));
+    }
+
+    let turns: Vec<current_chat::Turn> = entry
+        .content
+        .split("\n\n")
+        .filter(|block| !block.trim().is_empty())
+        .map(|block| current_chat::Turn {
+            role: "unknown".to_string(),
+            kind: "text".to_string(),
+            text: block.trim().to_string(),
+        })
+        .collect();
+    Ok(Json(serde_json::json!({ "source": "content", "turns": turns })))
+}
+
+/// `path` if it is a session file under Claude's projects directory. API clients
+/// can store any `file_path`, so nothing else is read.
+fn session_file(path: &str) -> Option<std::path::PathBuf> {
+    let projects = current_chat::projects_dir().ok()?.canonicalize().ok()?;
+    let path = std::fs::canonicalize(path).ok()?;
+    (path.starts_with(&projects) && path.extension().is_some_and(|e| e == "jsonl")).then_some(path)
+}
+
+#[derive(Deserialize)]
+struct NewEntry {
+    entry_type: String,
+    content: String,
+    file_path: Option<String>,
+    metadata: Option<serde_json::Value>,
+    /// Text search language; detected when absent
+    language: Option<String>,
+}
+
+async fn add_handler(
+    State(state): State<Arc<AppState>>,
+    entry: Result<Json<NewEntry>, JsonRejection>,
+) -> ApiResult<(StatusCode, Json<serde_json::Value>)> {
+    let Json(entry) = entry?;
+    if entry.entry_type.trim().is_empty() || entry.content.trim().is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "entry_type and content are required"));
+    }
+    let metadata = entry.metadata.unwrap_or_else(|| serde_json::json!({}));
+    if !metadata.is_object() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "metadata must be a JSON object"));
+    }
+
+    let client = state.client().await?;
+    check_language(&client, entry.language.as_deref()).await?;
+    let id = entries::insert(
+        &client,
+        &entry.entry_type,
+        &entry.content,
+        entry.file_path.as_deref(),
+        &metadata,
+        None,
+        entry.language.as_deref(),
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(serde_json::json!({ "id": id }))))
+}
+
+#[derive(Deserialize)]
+struct EntryUpdate {
+    content: Option<String>,
+    /// Merged into the metadata; null values remove keys
+    metadata: Option<serde_json::Value>,
+    language: Option<String>,
+}
+
+async fn update_handler(
+    State(state): State<Arc<AppState>>,
+    id: Result<Path<i32>, PathRejection>,
+    update: Result<Json<EntryUpdate>, JsonRejection>,
+) -> ApiResult<Json<get_cmd::Entry>> {
+    let Path(id) = id?;
+    let Json(update) = update?;
+    if update.metadata.as_ref().is_some_and(|m| !m.is_object()) {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "metadata must be a JSON object"));
+    }
+    let client = state.client().await?;
+    check_language(&client, update.language.as_deref()).await?;
+    let (content, metadata, language) = (update.content.as_deref(), update.metadata.as_ref(), update.language.as_deref());
+    if !entries::edit(&client, id, content, metadata, language).await? {
+        return Err(ApiError::not_found(id));
+    }
+    versions::prune(&*client, Some(id), state.config.versions.keep).await?;
+    get_handler(State(state), Ok(Path(id))).await
+}
+
+/// Moves the entry to the trash, like `elastic-claude delete`.
+async fn delete_handler(
+    State(state): State<Arc<AppState>>,
+    id: Result<Path<i32>, PathRejection>,
+) -> ApiResult<StatusCode> {
+    let Path(id) = id?;
+    let client = state.client().await?;
+    if entries::trash(&*client, &[id]).await?.is_empty() {
+        return Err(ApiError::not_found(id));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
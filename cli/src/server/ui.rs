@@ -0,0 +1,24 @@
+//! The web UI: static files compiled into the binary. Everything it shows comes
+//! from the JSON API, so it needs the same bearer token.
+
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use std::sync::Arc;
+use super::AppState;
+
+const INDEX_HTML: &str = include_str!("ui/index.html");
+const APP_JS: &str = include_str!("ui/app.js");
+const STYLE_CSS: &str = include_str!("ui/style.css");
+
+pub fn routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(|| asset("text/html; charset=utf-8", INDEX_HTML)))
+        .route("/ui/app.js", get(|| asset("text/javascript; charset=utf-8", APP_JS)))
+        .route("/ui/style.css", get(|| asset("text/css; charset=utf-8", STYLE_CSS)))
+}
+
+async fn asset(content_type: &'static str, body: &'static str) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, content_type)], body)
+}
@@ -0,0 +1,307 @@
+// elastic-claude web UI. Routes live in the URL hash:
+//   #/?q=retry&type=chat&project=sync   search (or list when q is empty)
+//   #/entry/42?q=retry                   one entry, highlighting the query words
+"use strict";
+
+const TOKEN_KEY = "elastic-claude-token";
+const $ = (id) => document.getElementById(id);
+
+// --- API -------------------------------------------------------------------
+
+async function api(method, path, body) {
+  const headers = {};
+  const token = localStorage.getItem(TOKEN_KEY);
+  if (token) headers["Authorization"] = "Bearer " + token;
+  if (body !== undefined) headers["Content-Type"] = "application/json";
+
+  const response = await fetch(path, {
+    method,
+    headers,
+    body: body === undefined ? undefined : JSON.stringify(body),
+  });
+  if (response.status === 401) {
+    await askToken();
+    return api(method, path, body);
+  }
+  const text = await response.text();
+  const data = text ? JSON.parse(text) : null;
+  if (!response.ok) throw new Error((data && data.error) || response.statusText);
+  return data;
+}
+
+function askToken() {
+  return new Promise((resolve) => {
+    const dialog = $("token-dialog");
+    $("token").value = "";
+    dialog.addEventListener(
+      "close",
+      () => {
+        localStorage.setItem(TOKEN_KEY, $("token").value);
+        resolve();
+      },
+      { once: true },
+    );
+    dialog.showModal();
+  });
+}
+
+// --- Rendering helpers -----------------------------------------------------
+
+function el(tag, attrs = {}, ...children) {
+  const node = document.createElement(tag);
+  for (const [key, value] of Object.entries(attrs)) {
+    if (key === "class") node.className = value;
+    else if (key.startsWith("on")) node.addEventListener(key.slice(2), value);
+    else node.setAttribute(key, value);
+  }
+  for (const child of children.flat()) {
+    if (child !== null && child !== undefined) node.append(child);
+  }
+  return node;
+}
+
+function escapeHtml(text) {
+  return text.replace(/[&<>"']/g, (c) => ({ "&": "&amp;", "<": "&lt;", ">": "&gt;", '"': "&quot;", "'": "&#39;" })[c]);
+}
+
+// ts_headline marks matches with <b></b>; everything else in a snippet is content
+function snippetHtml(snippet) {
+  return escapeHtml(snippet).replace(/&lt;b&gt;/g, "<mark>").replace(/&lt;\/b&gt;/g, "</mark>");
+}
+
+// The text with each query word (and longer words starting with it) marked
+function highlighted(text, query) {
+  const node = el("div", { class: "text" });
+  const words = (query || "")
+    .split(/\s+/)
+    .map((w) => w.replace(/[^\p{L}\p{N}_]/gu, ""))
+    .filter((w) => w.length > 1);
+  if (!words.length) {
+    node.textContent = text;
+    return node;
+  }
+  const pattern = new RegExp("(" + words.map((w) => w.replace(/[.*+?^${}()|[\]\\]/g, "\\$&")).join("|") + ")[\\p{L}\\p{N}_]*", "giu");
+  node.innerHTML = escapeHtml(text).replace(pattern, "<mark>$&</mark>");
+  return node;
+}
+
+function entryLink(id, label, query) {
+  const suffix = query ? "?" + new URLSearchParams({ q: query }) : "";
+  return el("a", { href: "#/entry/" + id + suffix }, label);
+}
+
+function labelOf(entry) {
+  const m = entry.metadata || {};
+  return m.title || entry.file_path || (entry.content || "").slice(0, 60);
+}
+
+// --- Search and list -------------------------------------------------------
+
+async function showResults(params) {
+  const q = params.get("q") || "";
+  const type = params.get("type") || "";
+  const project = params.get("project") || "";
+  $("q").value = q;
+  $("type").value = type;
+  $("project").value = project;
+
+  const results = $("results");
+  results.replaceChildren(el("p", { class: "hint" }, "Loading…"));
+  try {
+    const filters = new URLSearchParams({ type, project, limit: "50" });
+    let items;
+    if (q.trim()) {
+      filters.set("q", q);
+      const hits = await api("GET", "/api/search?" + filters);
+      items = hits.map((hit) =>
+        el(
+          "li",
+          {},
+          el("div", { class: "row" }, entryLink(hit.id, "#" + hit.id + " " + labelOf(hit), q), el("span", { class: "badge" }, hit.entry_type), el("span", { class: "score" }, hit.rank.toFixed(3))),
+          hit.file_path ? el("div", { class: "path" }, hit.file_path) : null,
+          Object.assign(el("div", { class: "snippet" }), { innerHTML: snippetHtml(hit.snippet) }),
+        ),
+      );
+    } else {
+      const listed = await api("GET", "/api/entries?" + filters);
+      items = listed.map((entry) =>
+        el(
+          "li",
+          {},
+          el("div", { class: "row" }, entryLink(entry.id, "#" + entry.id + " " + entry.label), el("span", { class: "badge" }, entry.entry_type)),
+          el("div", { class: "path" }, entry.created_at.replace("T", " ").slice(0, 16)),
+        ),
+      );
+    }
+    results.replaceChildren(items.length ? el("ul", {}, items) : el("p", { class: "hint" }, q ? "No results for: " + q : "No entries."));
+  } catch (error) {
+    results.replaceChildren(el("p", { class: "error" }, error.message));
+  }
+}
+
+// --- Entry view ------------------------------------------------------------
+
+async function showEntry(id, query) {
+  const pane = $("entry");
+  pane.replaceChildren(el("p", { class: "hint" }, "Loading…"));
+  try {
+    const [entry, related] = await Promise.all([
+      api("GET", "/api/entries/" + id),
+      api("GET", "/api/entries/" + id + "/related?depth=1"),
+    ]);
+
+    const facts = el(
+      "dl",
+      {},
+      el("dt", {}, "Type"),
+      el("dd", {}, entry.entry_type),
+      entry.file_path ? [el("dt", {}, "File"), el("dd", {}, entry.file_path)] : null,
+      el("dt", {}, "Created"),
+      el("dd", {}, entry.created_at.replace("T", " ").slice(0, 19)),
+      el("dt", {}, "Updated"),
+      el("dd", {}, entry.updated_at.replace("T", " ").slice(0, 19)),
+      entry.source_id !== null ? [el("dt", {}, "Source"), el("dd", {}, entryLink(entry.source_id, "#" + entry.source_id, query))] : null,
+      entry.deleted_at ? [el("dt", {}, "Deleted"), el("dd", { class: "error" }, entry.deleted_at.replace("T", " ").slice(0, 19) + " (in trash)")] : null,
+    );
+
+    const body = el("div", { class: "body" });
+    pane.replaceChildren(
+      el("h2", {}, "#" + entry.id + " " + labelOf(entry)),
+      facts,
+      relatedList(related, query),
+      metadataEditor(entry),
+      el("h3", {}, entry.entry_type === "chat" ? "Conversation" : "Content"),
+      body,
+    );
+
+    if (entry.entry_type === "chat") {
+      const conversation = await api("GET", "/api/entries/" + id + "/conversation");
+      body.append(conversationView(conversation, query));
+    } else {
+      body.append(highlighted(entry.content, query));
+    }
+  } catch (error) {
+    pane.replaceChildren(el("p", { class: "error" }, error.message));
+  }
+}
+
+function relatedList(related, query) {
+  if (!related.length) return null;
+  return el(
+    "div",
+    { class: "related" },
+    el("h3", {}, "Linked entries"),
+    el(
+      "ul",
+      {},
+      related.map((r) =>
+        el("li", {}, el("span", { class: "link-type" }, (r.outgoing ? r.link_type + " →" : "← " + r.link_type)), " ", entryLink(r.id, "#" + r.id + " " + r.label, query), " ", el("span", { class: "badge" }, r.entry_type)),
+      ),
+    ),
+  );
+}
+
+function conversationView(conversation, query) {
+  const list = el("div", { class: "conversation" });
+  if (conversation.source === "content") {
+    list.append(el("p", { class: "hint" }, "The session file is no longer on disk; showing the indexed text."));
+  }
+  for (const turn of conversation.turns) {
+    const collapsible = turn.kind !== "text";
+    const text = highlighted(turn.text, query);
+    list.append(
+      collapsible
+        ? el("details", { class: "turn " + turn.role + " " + turn.kind }, el("summary", {}, turn.role + " · " + turn.kind.replace("_", " ")), text)
+        : el("div", { class: "turn " + turn.role }, el("div", { class: "speaker" }, turn.role), text),
+    );
+  }
+  return list;
+}
+
+// Edits the whole metadata object; keys removed in the editor are sent as null
+function metadataEditor(entry) {
+  const original = entry.metadata || {};
+  const textarea = el("textarea", { rows: "8", spellcheck: "false" });
+  textarea.value = JSON.stringify(original, null, 2);
+  const message = el("span", { class: "message" });
+
+  const save = async (event) => {
+    event.preventDefault();
+    let edited;
+    try {
+      edited = JSON.parse(textarea.value);
+    } catch (error) {
+      message.textContent = "Invalid JSON: " + error.message;
+      message.className = "message error";
+      return;
+    }
+    if (edited === null || typeof edited !== "object" || Array.isArray(edited)) {
+      message.textContent = "Metadata must be a JSON object";
+      message.className = "message error";
+      return;
+    }
+    const patch = { ...edited };
+    for (const key of Object.keys(original)) {
+      if (!(key in edited)) patch[key] = null;
+    }
+    try {
+      await api("PATCH", "/api/entries/" + entry.id, { metadata: patch });
+      message.textContent = "Saved";
+      message.className = "message";
+      route();
+    } catch (error) {
+      message.textContent = error.message;
+      message.className = "message error";
+    }
+  };
+
+  return el(
+    "details",
+    { class: "metadata" },
+    el("summary", {}, "Metadata"),
+    el("form", { onsubmit: save }, textarea, el("div", {}, el("button", { type: "submit" }, "Save metadata"), " ", message)),
+  );
+}
+
+// --- Routing ---------------------------------------------------------------
+
+function route() {
+  const hash = location.hash.replace(/^#/, "") || "/";
+  const [path, queryString] = hash.split("?");
+  const params = new URLSearchParams(queryString || "");
+
+  const match = path.match(/^\/entry\/(\d+)$/);
+  if (match) {
+    // Opened directly: fill the results pane with the newest entries
+    if (!$("results").hasChildNodes()) showResults(new URLSearchParams());
+    showEntry(match[1], params.get("q"));
+  } else {
+    showResults(params);
+  }
+}
+
+$("search-form").addEventListener("submit", (event) => {
+  event.preventDefault();
+  const params = new URLSearchParams();
+  for (const id of ["q", "type", "project"]) {
+    if ($(id).value.trim()) params.set(id, $(id).value.trim());
+  }
+  location.hash = "#/?" + params;
+});
+
+async function loadStatus() {
+  try {
+    const status = await api("GET", "/api/status");
+    const select = $("type");
+    for (const type of Object.keys(status.entries).sort()) {
+      if (![...select.options].some((o) => o.value === type)) select.append(el("option", { value: type }, type));
+    }
+    const total = Object.values(status.entries).reduce((a, b) => a + b, 0);
+    $("status").textContent = total + " entries · schema v" + status.schema_version;
+  } catch (error) {
+    $("status").textContent = error.message;
+  }
+}
+
+window.addEventListener("hashchange", route);
+loadStatus().then(route);
@@ -0,0 +1,50 @@
+//! Entry summaries.
+//!
+//! The default backend is offline TextRank. Set `summarizer.backend: openai` in
+//! config.yaml to use any OpenAI-compatible chat completions endpoint instead, such
+//! as a local llama.cpp server or Ollama.
+
+mod openai;
+mod textrank;
+
+use anyhow::{bail, Result};
+use futures_util::future::BoxFuture;
+use crate::config::SummarizerConfig;
+
+pub use openai::OpenAi;
+pub use textrank::TextRank;
+
+/// Sentences kept when no count is given
+pub const DEFAULT_SENTENCES: usize = 5;
+
+pub struct Summary {
+    pub text: String,
+    /// Short title suggested by the backend, if it produces one
+    pub title: Option<String>,
+}
+
+pub trait Summarizer {
+    /// Recorded as `metadata.method` on summary entries
+    fn method(&self) -> &str;
+
+    /// Recorded as `metadata.model` on summary entries
+    fn model(&self) -> Option<&str>;
+
+    /// Summary of `text` in about the configured number of sentences, or None when
+    /// there is nothing to summarize.
+    fn summarize<'a>(&'a self, text: &'a str) -> BoxFuture<'a, Result<Option<Summary>>>;
+}
+
+/// The backend named by `backend` (or else the config), keeping about `sentences`
+/// sentences.
+pub fn from_config(
+    config: &SummarizerConfig,
+    backend: Option<&str>,
+    sentences: usize,
+) -> Result<Box<dyn Summarizer>> {
+    match backend.unwrap_or(&config.backend) {
+        "textrank" => Ok(Box::new(TextRank { sentences })),
+        "openai" => Ok(Box::new(OpenAi::new(config, sentences)?)),
+        other => bail!("Unknown summarizer backend '{}'. Use textrank or openai", other),
+    }
+}
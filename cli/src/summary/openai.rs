@@ -0,0 +1,257 @@
+use anyhow::{bail, Context, Result};
+use futures_util::future::BoxFuture;
+use serde_json::json;
+use std::time::Duration;
+use crate::config::SummarizerConfig;
+use super::{textrank, Summarizer, Summary};
+
+const SYSTEM_PROMPT: &str = "You summarize notes, documents and chat transcripts from a developer's \
+knowledge base. Reply with a short title (at most ten words) on the first line, then a blank line, \
+then the summary as plain prose. Do not add any preamble.";
+
+/// Sentences kept when condensing an over-long input before sending it
+const CONDENSED_SENTENCES: usize = 80;
+
+/// A chat completions endpoint: llama.cpp server, Ollama, vLLM or the OpenAI API.
+pub struct OpenAi {
+    client: reqwest::Client,
+    url: String,
+    model: Option<String>,
+    api_key: Option<String>,
+    max_input_chars: usize,
+    sentences: usize,
+}
+
+impl OpenAi {
+    pub fn new(config: &SummarizerConfig, sentences: usize) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .context("Failed to create HTTP client")?;
+        Ok(Self {
+            client,
+            url: format!("{}/chat/completions", config.endpoint.trim_end_matches('/')),
+            model: config.model.clone(),
+            api_key: config.api_key.clone(),
+            max_input_chars: config.max_input_chars,
+            sentences,
+        })
+    }
+
+    async fn complete(&self, text: &str) -> Result<Option<Summary>> {
+        let input = condense(text, self.max_input_chars);
+        if input.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let mut body = json!({
+            "messages": [
+                { "role": "system", "content": SYSTEM_PROMPT },
+                {
+                    "role": "user",
+                    "content": format!("Summarize the following in at most {} sentences.\n\n{}", self.sentences, input),
+                },
+            ],
+            "temperature": 0.2,
+            "stream": false,
+        });
+        if let Some(model) = &self.model {
+            body["model"] = model.clone().into();
+        }
+
+        let mut request = self.client.post(&self.url).json(&body);
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Could not reach summarization endpoint {}", self.url))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let detail = response.text().await.unwrap_or_default();
+            bail!(
+                "Summarization endpoint returned {}: {}",
+                status,
+                detail.chars().take(300).collect::<String>()
+            );
+        }
+
+        let reply: serde_json::Value = response
+            .json()
+            .await
+            .context("Summarization endpoint did not return JSON")?;
+        let content = reply["choices"][0]["message"]["content"]
+            .as_str()
+            .context("Summarization endpoint returned no message content")?;
+        Ok(parse_reply(content))
+    }
+}
+
+impl Summarizer for OpenAi {
+    fn method(&self) -> &str {
+        "llm"
+    }
+
+    fn model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    fn summarize<'a>(&'a self, text: &'a str) -> BoxFuture<'a, Result<Option<Summary>>> {
+        Box::pin(self.complete(text))
+    }
+}
+
+/// Inputs over the limit are reduced to their most central sentences, then cut.
+fn condense(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let sentences = textrank::extract(text, CONDENSED_SENTENCES);
+    let condensed = if sentences.is_empty() { text.to_string() } else { sentences.join("\n") };
+    condensed.chars().take(max_chars).collect()
+}
+
+/// Split a "title, blank line, summary" reply. Reasoning models' `<think>` blocks
+/// are dropped; a reply without a separate title is all summary.
+fn parse_reply(reply: &str) -> Option<Summary> {
+    let reply = match reply.find("</think>") {
+        Some(end) if reply.trim_start().starts_with("<think>") => &reply[end + "</think>".len()..],
+        _ => reply,
+    };
+    let reply = reply.trim();
+    if reply.is_empty() {
+        return None;
+    }
+
+    let (first, rest) = reply.split_once('\n').unwrap_or((reply, ""));
+    let rest = rest.trim();
+    if rest.is_empty() {
+        return Some(Summary {
+            text: reply.to_string(),
+            title: None,
+        });
+    }
+
+    let title = first.trim().trim_start_matches('#').trim().trim_matches('*').trim();
+    let title = title.strip_prefix("Title:").unwrap_or(title).trim().trim_matches(['"', '\'']).trim();
+    let rest = rest.strip_prefix("Summary:").unwrap_or(rest).trim();
+    Some(Summary {
+        text: rest.to_string(),
+        title: (!title.is_empty()).then(|| title.to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve one HTTP request with `status` and `body`, returning the raw request.
+    async fn stub_server(status: &'static str, body: String) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/v1", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 4096];
+            loop {
+                let n = socket.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..n]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(header_end) = text.find("\r\n\r\n") {
+                    let length = text[..header_end]
+                        .lines()
+                        .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                        .unwrap_or(0);
+                    if request.len() >= header_end + 4 + length {
+                        break;
+                    }
+                }
+                if n == 0 {
+                    break;
+                }
+            }
+            let response = format!(
+                "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request).to_string()
+        });
+        (endpoint, handle)
+    }
+
+    fn config(endpoint: String) -> SummarizerConfig {
+        SummarizerConfig {
+            backend: "openai".to_string(),
+            endpoint,
+            model: Some("llama3.2".to_string()),
+            api_key: Some("secret".to_string()),
+            max_input_chars: 1000,
+            timeout_secs: 5,
+        }
+    }
+
+    fn completion(content: &str) -> String {
+        json!({ "choices": [{ "message": { "role": "assistant", "content": content } }] }).to_string()
+    }
+
+    #[tokio::test]
+    async fn sends_chat_completion_and_parses_title() {
+        let (endpoint, server) = stub_server(
+            "200 OK",
+            completion("Title: Retry policy for the sync worker\n\nThe worker now retries with exponential backoff."),
+        )
+        .await;
+        let summarizer = OpenAi::new(&config(endpoint), 3).unwrap();
+
+        let summary = summarizer.summarize("We discussed retries.").await.unwrap().unwrap();
+        assert_eq!(summary.title.as_deref(), Some("Retry policy for the sync worker"));
+        assert_eq!(summary.text, "The worker now retries with exponential backoff.");
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /v1/chat/completions "));
+        assert!(request.to_lowercase().contains("authorization: bearer secret"));
+        let body: serde_json::Value = serde_json::from_str(request.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(body["model"], "llama3.2");
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert!(body["messages"][1]["content"].as_str().unwrap().contains("at most 3 sentences"));
+        assert!(body["messages"][1]["content"].as_str().unwrap().ends_with("We discussed retries."));
+    }
+
+    #[tokio::test]
+    async fn reports_endpoint_errors() {
+        let (endpoint, server) = stub_server("500 Internal Server Error", r#"{"error":"model not loaded"}"#.to_string()).await;
+        let summarizer = OpenAi::new(&config(endpoint), 3).unwrap();
+
+        let error = summarizer.summarize("Some text to summarize.").await.err().unwrap();
+        assert!(error.to_string().contains("500"));
+        assert!(error.to_string().contains("model not loaded"));
+        server.await.unwrap();
+    }
+
+    #[test]
+    fn parses_replies() {
+        let summary = parse_reply("<think>short input</think>\n\n# **Deploy notes**\n\nDeployed on Friday.").unwrap();
+        assert_eq!(summary.title.as_deref(), Some("Deploy notes"));
+        assert_eq!(summary.text, "Deployed on Friday.");
+
+        let summary = parse_reply("Just one line of summary.").unwrap();
+        assert!(summary.title.is_none());
+        assert_eq!(summary.text, "Just one line of summary.");
+
+        assert!(parse_reply("  \n").is_none());
+    }
+
+    #[test]
+    fn condenses_long_input() {
+        let text = "The sync worker retries failed uploads with exponential backoff. ".repeat(100);
+        assert!(condense(&text, 500).chars().count() <= 500);
+        assert_eq!(condense("short", 500), "short");
+    }
+}
@@ -0,0 +1,278 @@
+//! Offline extractive summaries with TextRank.
+//!
+//! Sentences are nodes in a graph weighted by shared content words; PageRank over
+//! that graph scores how central each sentence is, and the top sentences are
+//! returned in their original order.
+
+use anyhow::Result;
+use futures_util::future::BoxFuture;
+use std::collections::HashSet;
+use crate::keywords::STOPWORDS;
+use super::{Summarizer, Summary};
+
+/// Only the first sentences of very long texts are ranked (the graph is quadratic)
+const MAX_SENTENCES: usize = 600;
+
+const DAMPING: f64 = 0.85;
+const ITERATIONS: usize = 50;
+const TOLERANCE: f64 = 1e-6;
+
+/// The offline backend: the most central sentences, one paragraph each.
+pub struct TextRank {
+    pub sentences: usize,
+}
+
+impl Summarizer for TextRank {
+    fn method(&self) -> &str {
+        "textrank"
+    }
+
+    fn model(&self) -> Option<&str> {
+        None
+    }
+
+    fn summarize<'a>(&'a self, text: &'a str) -> BoxFuture<'a, Result<Option<Summary>>> {
+        Box::pin(async move {
+            let picked = extract(text, self.sentences);
+            Ok((!picked.is_empty()).then(|| Summary {
+                text: picked.join("\n\n"),
+                title: None,
+            }))
+        })
+    }
+}
+
+struct Sentence {
+    text: String,
+    words: HashSet<String>,
+}
+
+/// The `count` most central sentences of `text`, in document order.
+pub fn extract(text: &str, count: usize) -> Vec<String> {
+    let sentences: Vec<Sentence> = split_sentences(text)
+        .into_iter()
+        .take(MAX_SENTENCES)
+        .filter_map(|text| {
+            let words = content_words(&text);
+            (words.len() >= 3).then_some(Sentence { text, words })
+        })
+        .collect();
+
+    if sentences.len() <= count {
+        return sentences.into_iter().map(|s| s.text).collect();
+    }
+
+    let scores = rank(&sentences);
+    let mut order: Vec<usize> = (0..sentences.len()).collect();
+    order.sort_by(|a, b| scores[*b].total_cmp(&scores[*a]).then(a.cmp(b)));
+
+    // Drop near-duplicates of already chosen sentences (repeated boilerplate in chats)
+    let mut chosen: Vec<usize> = Vec::new();
+    for i in order {
+        if chosen.iter().any(|&j| overlap(&sentences[i].words, &sentences[j].words) > 0.8) {
+            continue;
+        }
+        chosen.push(i);
+        if chosen.len() == count {
+            break;
+        }
+    }
+    chosen.sort_unstable();
+    chosen.into_iter().map(|i| sentences[i].text.clone()).collect()
+}
+
+/// PageRank over the sentence similarity graph.
+fn rank(sentences: &[Sentence]) -> Vec<f64> {
+    let n = sentences.len();
+    let mut weights = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in (i + 1)..n {
+            let w = similarity(&sentences[i].words, &sentences[j].words);
+            weights[i][j] = w;
+            weights[j][i] = w;
+        }
+    }
+    let out_weight: Vec<f64> = weights.iter().map(|row| row.iter().sum()).collect();
+
+    let mut scores = vec![1.0 / n as f64; n];
+    for _ in 0..ITERATIONS {
+        let mut next = vec![(1.0 - DAMPING) / n as f64; n];
+        for (i, row) in weights.iter().enumerate() {
+            if out_weight[i] == 0.0 {
+                continue;
+            }
+            for (j, w) in row.iter().enumerate() {
+                if *w > 0.0 {
+                    next[j] += DAMPING * scores[i] * w / out_weight[i];
+                }
+            }
+        }
+        let delta: f64 = next.iter().zip(&scores).map(|(a, b)| (a - b).abs()).sum();
+        scores = next;
+        if delta < TOLERANCE {
+            break;
+        }
+    }
+    scores
+}
+
+/// TextRank similarity: shared words normalised by sentence lengths.
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let shared = a.intersection(b).count() as f64;
+    if shared == 0.0 {
+        return 0.0;
+    }
+    let norm = (a.len() as f64).ln() + (b.len() as f64).ln();
+    if norm <= 0.0 {
+        return 0.0;
+    }
+    shared / norm
+}
+
+fn overlap(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    a.intersection(b).count() as f64 / a.len().min(b.len()).max(1) as f64
+}
+
+fn content_words(sentence: &str) -> HashSet<String> {
+    sentence
+        .split(|c: char| !c.is_alphanumeric())
+        .map(|w| w.to_lowercase())
+        .filter(|w| w.chars().count() >= 3 && !STOPWORDS.contains(&w.as_str()))
+        .collect()
+}
+
+/// Prose sentences, skipping fenced code, tables and lines that look like code.
+fn split_sentences(text: &str) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut paragraph = String::new();
+    let mut in_fence = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        let skip = in_fence || trimmed.starts_with('|') || trimmed.starts_with('#') || looks_like_code(trimmed);
+        if trimmed.is_empty() || skip {
+            if !paragraph.is_empty() {
+                paragraphs.push(std::mem::take(&mut paragraph));
+            }
+            continue;
+        }
+        // List items are sentences of their own
+        let list_item = trimmed.starts_with(['-', '*'])
+            || (trimmed.starts_with(|c: char| c.is_ascii_digit()) && trimmed.contains(". "));
+        if list_item && !paragraph.is_empty() {
+            paragraphs.push(std::mem::take(&mut paragraph));
+        }
+        if !paragraph.is_empty() {
+            paragraph.push(' ');
+        }
+        paragraph.push_str(trimmed.trim_start_matches(['-', '*', ' ']));
+    }
+    if !paragraph.is_empty() {
+        paragraphs.push(paragraph);
+    }
+
+    let mut sentences = Vec::new();
+    for paragraph in paragraphs {
+        let mut start = 0;
+        let chars: Vec<(usize, char)> = paragraph.char_indices().collect();
+        for (k, &(i, c)) in chars.iter().enumerate() {
+            if !matches!(c, '.' | '!' | '?') {
+                continue;
+            }
+            // Sentence end: punctuation, whitespace, then an uppercase letter or digit
+            let next_is_space = chars.get(k + 1).is_some_and(|(_, n)| n.is_whitespace());
+            let then_upper = chars
+                .get(k + 2)
+                .is_some_and(|(_, n)| n.is_uppercase() || n.is_ascii_digit() || matches!(n, '"' | '\'' | '('));
+            if next_is_space && then_upper {
+                push_sentence(&paragraph[start..i + c.len_utf8()], &mut sentences);
+                start = i + c.len_utf8();
+            }
+        }
+        push_sentence(&paragraph[start..], &mut sentences);
+    }
+    sentences
+}
+
+fn push_sentence(sentence: &str, sentences: &mut Vec<String>) {
+    let sentence = sentence.split_whitespace().collect::<Vec<_>>().join(" ");
+    let words = sentence.split(' ').count();
+    if (5..=80).contains(&words) {
+        sentences.push(sentence);
+    }
+}
+
+fn looks_like_code(line: &str) -> bool {
+    let symbols = line.chars().filter(|c| matches!(c, '{' | '}' | ';' | '=' | '<' | '>' | '(' | ')')).count();
+    symbols * 8 > line.len().max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_prose_into_sentences_and_skips_code() {
+        let text = "The cache stores rendered pages for ten minutes. Misses go to the database!\n\
+                    \n\
+                    ```\n\
+                    let cache = Cache::new(600);\n\
+                    ```\n\
+                    # Heading line is skipped\n\
+                    - Each list item becomes its own sentence here\n\
+                    - Version 2.5 keeps decimals inside a single sentence\n";
+        assert_eq!(
+            split_sentences(text),
+            [
+                "The cache stores rendered pages for ten minutes.",
+                "Misses go to the database!",
+                "Each list item becomes its own sentence here",
+                "Version 2.5 keeps decimals inside a single sentence",
+            ]
+        );
+    }
+
+    #[test]
+    fn central_sentences_rank_first_and_keep_document_order() {
+        let text = "Lunch options near the office include noodles and tacos today. \
+                    The deployment pipeline builds images and runs integration tests. \
+                    Integration tests in the deployment pipeline block broken images. \
+                    Broken images never reach production because the pipeline tests them. \
+                    The weather forecast predicts rain over the weekend afternoon.";
+        let picked = extract(text, 2);
+        assert_eq!(picked.len(), 2);
+        assert!(picked.iter().all(|s| s.contains("pipeline")), "{:?}", picked);
+        let positions: Vec<usize> = picked.iter().map(|s| text.find(s.as_str()).unwrap()).collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn returns_at_most_the_requested_number_of_sentences() {
+        let text = (1..=10)
+            .map(|i| format!("Sentence number {} describes replication lag monitoring alerts.", i))
+            .collect::<Vec<_>>()
+            .join(" ");
+        // Near-identical sentences are dropped as duplicates, so fewer may come back
+        assert!(extract(&text, 3).len() <= 3);
+        let varied = "Replication lag alerts page the on-call engineer quickly. \
+                      Backups are verified nightly against a restored replica. \
+                      Schema migrations run inside transactions with timeouts. \
+                      Connection pools cap clients at fifty per service instance.";
+        assert_eq!(extract(varied, 2).len(), 2);
+        assert_eq!(extract(varied, 10).len(), 4);
+    }
+
+    #[tokio::test]
+    async fn empty_and_single_sentence_input() {
+        assert!(extract("", 3).is_empty());
+        assert!(extract("Too short.", 3).is_empty());
+        let one = "The scheduler retries failed jobs with exponential backoff.";
+        assert_eq!(extract(one, 3), [one]);
+
+        assert!(TextRank { sentences: 3 }.summarize("   ").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,74 @@
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use std::process::Command;
+
+pub fn user_unit_dir() -> Result<PathBuf> {
+    let config = dirs::config_dir().context("Could not find user config directory")?;
+    Ok(config.join("systemd").join("user"))
+}
+
+/// Path of the running binary, for use in `ExecStart=`
+pub fn exec_path() -> Result<String> {
+    let exe = std::env::current_exe().context("Could not determine path of elastic-claude binary")?;
+    Ok(exe.to_string_lossy().to_string())
+}
+
+/// Quote an argument for `ExecStart=`, escaping specifiers and quotes.
+pub fn quote(arg: &str) -> String {
+    let escaped = arg
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%");
+    format!("\"{}\"", escaped)
+}
+
+/// Write unit files, then reload systemd and enable `enable` (e.g. a .timer or .service).
+/// If systemctl is unavailable the manual commands are printed instead.
+pub fn install(units: &[(&str, String)], enable: &str) -> Result<()> {
+    let dir = user_unit_dir()?;
+    std::fs::create_dir_all(&dir)?;
+
+    for (name, contents) in units {
+        let path = dir.join(name);
+        std::fs::write(&path, contents)
+            .with_context(|| format!("Failed to write unit file: {}", path.display()))?;
+        println!("Wrote {}", path.display());
+    }
+
+    let enabled = systemctl(&["daemon-reload"]) && systemctl(&["enable", "--now", enable]);
+    if enabled {
+        println!("✓ Enabled {}", enable);
+    } else {
+        println!("\nCould not run systemctl. Enable the unit manually with:");
+        println!("  systemctl --user daemon-reload");
+        println!("  systemctl --user enable --now {}", enable);
+    }
+
+    Ok(())
+}
+
+/// Disable `enable` and remove the unit files.
+pub fn uninstall(units: &[&str], enable: &str) -> Result<()> {
+    systemctl(&["disable", "--now", enable]);
+
+    let dir = user_unit_dir()?;
+    for name in units {
+        let path = dir.join(name);
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+            println!("Removed {}", path.display());
+        }
+    }
+
+    systemctl(&["daemon-reload"]);
+    Ok(())
+}
+
+fn systemctl(args: &[&str]) -> bool {
+    Command::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
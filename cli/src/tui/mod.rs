@@ -0,0 +1,411 @@
+//! `elastic-claude tui`: interactive search in the terminal.
+//!
+//! The interface is drawn on stderr so stdout stays free for the entry picked with
+//! Enter, e.g. `elastic-claude tui | less`. Queries run on background tasks and
+//! results that arrive after a newer query was sent are dropped.
+
+mod ui;
+
+use anyhow::{bail, Context, Result};
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use futures_util::StreamExt;
+use ratatui::backend::CrosstermBackend;
+use ratatui::widgets::ListState;
+use ratatui::Terminal;
+use std::io::{self, IsTerminal, Stderr, Write};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tokio_postgres::Client;
+use crate::commands::{get as get_cmd, list, search, status};
+use crate::config::RankingConfig;
+
+/// Pause after the last keystroke before a query is sent
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Rows fetched per query
+const RESULT_LIMIT: i64 = 100;
+
+/// What the user chose when leaving
+pub enum Outcome {
+    Quit,
+    /// Print this entry's content to stdout
+    Print(String),
+}
+
+/// One row of the result list
+struct Item {
+    id: i32,
+    entry_type: String,
+    label: String,
+    /// Rank for search hits; None when listing the newest entries
+    score: Option<f32>,
+}
+
+struct Preview {
+    entry: get_cmd::Entry,
+    lines: Vec<String>,
+}
+
+enum Message {
+    Results { seq: u64, result: Result<Vec<Item>> },
+    Preview { id: i32, result: Result<Option<Box<get_cmd::Entry>>> },
+}
+
+struct App {
+    client: Arc<Client>,
+    ranking: Arc<RankingConfig>,
+    tx: mpsc::UnboundedSender<Message>,
+    query: String,
+    types: Vec<String>,
+    type_filter: Option<usize>,
+    projects: Vec<String>,
+    project_filter: Option<usize>,
+    results: Vec<Item>,
+    list_state: ListState,
+    /// Sequence number of the newest query sent
+    seq: u64,
+    searching: bool,
+    search_due: Option<Instant>,
+    preview: Option<Preview>,
+    preview_scroll: usize,
+    status: String,
+}
+
+type Term = Terminal<CrosstermBackend<Stderr>>;
+
+pub async fn run(client: Client, ranking: RankingConfig) -> Result<Outcome> {
+    if !io::stderr().is_terminal() {
+        bail!("tui needs a terminal");
+    }
+    let client = Arc::new(client);
+    let types = status::entry_counts(&client).await?.by_type.into_iter().map(|(t, _)| t).collect();
+    let projects = projects(&client).await?;
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut app = App {
+        client,
+        ranking: Arc::new(ranking),
+        tx,
+        query: String::new(),
+        types,
+        type_filter: None,
+        projects,
+        project_filter: None,
+        results: Vec::new(),
+        list_state: ListState::default(),
+        seq: 0,
+        searching: false,
+        search_due: None,
+        preview: None,
+        preview_scroll: 0,
+        status: String::new(),
+    };
+    app.search();
+
+    let mut terminal = enter()?;
+    let outcome = event_loop(&mut terminal, &mut app, &mut rx).await;
+    leave(&mut terminal)?;
+    outcome
+}
+
+async fn event_loop(terminal: &mut Term, app: &mut App, rx: &mut mpsc::UnboundedReceiver<Message>) -> Result<Outcome> {
+    let mut events = EventStream::new();
+    loop {
+        terminal.draw(|frame| ui::draw(frame, app))?;
+
+        let due = app.search_due;
+        tokio::select! {
+            event = events.next() => {
+                let Some(event) = event else { return Ok(Outcome::Quit) };
+                if let Event::Key(key) = event.context("Failed to read terminal input")? {
+                    if key.kind == KeyEventKind::Press {
+                        if let Some(outcome) = handle_key(terminal, app, key)? {
+                            return Ok(outcome);
+                        }
+                    }
+                }
+            }
+            Some(message) = rx.recv() => app.apply(message),
+            _ = tokio::time::sleep_until(due.unwrap_or_else(Instant::now)), if due.is_some() => app.search(),
+        }
+    }
+}
+
+fn handle_key(terminal: &mut Term, app: &mut App, key: KeyEvent) -> Result<Option<Outcome>> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Esc => return Ok(Some(Outcome::Quit)),
+        KeyCode::Char('c') if ctrl => return Ok(Some(Outcome::Quit)),
+        KeyCode::Enter => {
+            if let Some(preview) = &app.preview {
+                return Ok(Some(Outcome::Print(preview.entry.content.clone())));
+            }
+        }
+        KeyCode::Char('e') if ctrl => app.open_in_editor(terminal)?,
+        KeyCode::Char('y') if ctrl => app.copy_id()?,
+        KeyCode::Char('u') if ctrl => {
+            app.query.clear();
+            app.query_changed();
+        }
+        KeyCode::Char(c) if !ctrl => {
+            app.query.push(c);
+            app.query_changed();
+        }
+        KeyCode::Backspace => {
+            app.query.pop();
+            app.query_changed();
+        }
+        KeyCode::Tab => {
+            app.type_filter = cycle(app.type_filter, app.types.len());
+            app.query_changed();
+        }
+        KeyCode::BackTab => {
+            app.project_filter = cycle(app.project_filter, app.projects.len());
+            app.query_changed();
+        }
+        KeyCode::Up => app.select_offset(-1),
+        KeyCode::Down => app.select_offset(1),
+        KeyCode::Char('p') if ctrl => app.select_offset(-1),
+        KeyCode::Char('n') if ctrl => app.select_offset(1),
+        KeyCode::PageDown => app.scroll_preview(terminal.size()?.height as isize / 2),
+        KeyCode::PageUp => app.scroll_preview(-(terminal.size()?.height as isize / 2)),
+        _ => {}
+    }
+    Ok(None)
+}
+
+/// None (no filter), then each value in turn
+fn cycle(current: Option<usize>, len: usize) -> Option<usize> {
+    match current {
+        None if len > 0 => Some(0),
+        Some(i) if i + 1 < len => Some(i + 1),
+        _ => None,
+    }
+}
+
+impl App {
+    fn type_name(&self) -> Option<&str> {
+        self.type_filter.map(|i| self.types[i].as_str())
+    }
+
+    fn project_name(&self) -> Option<&str> {
+        self.project_filter.map(|i| self.projects[i].as_str())
+    }
+
+    fn query_changed(&mut self) {
+        self.search_due = Some(Instant::now() + DEBOUNCE);
+    }
+
+    /// Search, or list the newest entries when the query is blank
+    fn search(&mut self) {
+        self.search_due = None;
+        self.seq += 1;
+        self.searching = true;
+
+        let seq = self.seq;
+        let client = self.client.clone();
+        let ranking = self.ranking.clone();
+        let tx = self.tx.clone();
+        let query = self.query.clone();
+        let filters = search::Filters {
+            entry_type: self.type_name().map(str::to_string),
+            project: self.project_name().map(str::to_string),
+            language: None,
+        };
+        tokio::spawn(async move {
+            let result = if query.trim().is_empty() {
+                list::list(&client, filters.entry_type.as_deref(), filters.project.as_deref(), RESULT_LIMIT)
+                    .await
+                    .map(|listed| {
+                        listed
+                            .into_iter()
+                            .map(|e| Item {
+                                id: e.id,
+                                entry_type: e.entry_type,
+                                label: e.label,
+                                score: None,
+                            })
+                            .collect()
+                    })
+            } else {
+                search::search(&client, &query, &filters, &ranking, RESULT_LIMIT).await.map(|hits| {
+                    hits.into_iter()
+                        .map(|hit| Item {
+                            id: hit.id,
+                            label: hit
+                                .metadata
+                                .get("title")
+                                .and_then(|t| t.as_str())
+                                .map(str::to_string)
+                                .or(hit.file_path)
+                                .unwrap_or_else(|| format!("{} entry", hit.entry_type)),
+                            entry_type: hit.entry_type,
+                            score: Some(hit.rank),
+                        })
+                        .collect()
+                })
+            };
+            let _ = tx.send(Message::Results { seq, result });
+        });
+    }
+
+    fn apply(&mut self, message: Message) {
+        match message {
+            Message::Results { seq, result } => {
+                if seq != self.seq {
+                    return;
+                }
+                self.searching = false;
+                match result {
+                    Ok(items) => {
+                        self.status.clear();
+                        self.results = items;
+                    }
+                    Err(e) => {
+                        self.status = format!("{:#}", e);
+                        self.results.clear();
+                    }
+                }
+                self.list_state.select((!self.results.is_empty()).then_some(0));
+                self.load_preview();
+            }
+            Message::Preview { id, result } => {
+                if self.selected_id() != Some(id) {
+                    return;
+                }
+                match result {
+                    Ok(Some(entry)) => {
+                        let lines: Vec<String> = entry.content.lines().map(str::to_string).collect();
+                        self.preview_scroll = ui::first_match(&lines, &self.query).unwrap_or(0).saturating_sub(2);
+                        self.preview = Some(Preview { entry: *entry, lines });
+                    }
+                    Ok(None) => self.preview = None,
+                    Err(e) => self.status = format!("{:#}", e),
+                }
+            }
+        }
+    }
+
+    fn selected_id(&self) -> Option<i32> {
+        self.list_state.selected().and_then(|i| self.results.get(i)).map(|item| item.id)
+    }
+
+    fn select_offset(&mut self, offset: isize) {
+        if self.results.is_empty() {
+            return;
+        }
+        let current = self.list_state.selected().unwrap_or(0) as isize;
+        let next = (current + offset).clamp(0, self.results.len() as isize - 1) as usize;
+        if Some(next) != self.list_state.selected() {
+            self.list_state.select(Some(next));
+            self.load_preview();
+        }
+    }
+
+    fn load_preview(&mut self) {
+        self.preview = None;
+        self.preview_scroll = 0;
+        let Some(id) = self.selected_id() else { return };
+        let client = self.client.clone();
+        let tx = self.tx.clone();
+        tokio::spawn(async move {
+            let result = get_cmd::fetch(&client, id).await.map(|entry| entry.map(Box::new));
+            let _ = tx.send(Message::Preview { id, result });
+        });
+    }
+
+    fn scroll_preview(&mut self, lines: isize) {
+        let max = self.preview.as_ref().map_or(0, |p| p.lines.len().saturating_sub(1));
+        self.preview_scroll = (self.preview_scroll as isize + lines).clamp(0, max as isize) as usize;
+    }
+
+    /// Suspend the interface while $VISUAL or $EDITOR has the entry's file open.
+    fn open_in_editor(&mut self, terminal: &mut Term) -> Result<()> {
+        let Some(preview) = &self.preview else { return Ok(()) };
+        let Some(path) = preview.entry.file_path.clone() else {
+            self.status = format!("Entry {} has no file", preview.entry.id);
+            return Ok(());
+        };
+        if !std::path::Path::new(&path).exists() {
+            self.status = format!("{} no longer exists", path);
+            return Ok(());
+        }
+
+        let editor = std::env::var("VISUAL")
+            .or_else(|_| std::env::var("EDITOR"))
+            .unwrap_or_else(|_| "vi".to_string());
+        let mut words = editor.split_whitespace();
+        let program = words.next().unwrap_or("vi");
+
+        leave(terminal)?;
+        let result = std::process::Command::new(program).args(words).arg(&path).status();
+        *terminal = enter()?;
+
+        match result {
+            Ok(status) if status.success() => self.status.clear(),
+            Ok(status) => self.status = format!("{} exited with {}", program, status),
+            Err(e) => self.status = format!("Could not run {}: {}", program, e),
+        }
+        Ok(())
+    }
+
+    /// Put the id on the clipboard with an OSC 52 escape, which terminals accept
+    /// from remote programs too (over SSH).
+    fn copy_id(&mut self) -> Result<()> {
+        let Some(id) = self.selected_id() else { return Ok(()) };
+        let mut stderr = io::stderr();
+        write!(stderr, "\x1b]52;c;{}\x07", base64(id.to_string().as_bytes()))?;
+        stderr.flush()?;
+        self.status = format!("Copied id {}", id);
+        Ok(())
+    }
+}
+
+fn enter() -> Result<Term> {
+    terminal::enable_raw_mode().context("Failed to enable raw mode")?;
+    execute!(io::stderr(), EnterAlternateScreen)?;
+    Terminal::new(CrosstermBackend::new(io::stderr())).context("Failed to start terminal UI")
+}
+
+fn leave(terminal: &mut Term) -> Result<()> {
+    terminal::disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    Ok(())
+}
+
+/// Distinct `metadata.project` values of live entries
+async fn projects(client: &Client) -> Result<Vec<String>> {
+    let rows = client
+        .query(
+            r#"
+            SELECT DISTINCT metadata->>'project' AS project FROM entries
+            WHERE metadata ? 'project' AND deleted_at IS NULL
+            ORDER BY project
+            "#,
+            &[],
+        )
+        .await
+        .context("Failed to list projects")?;
+    Ok(rows.iter().filter_map(|row| row.get::<_, Option<String>>(0)).collect())
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in data.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
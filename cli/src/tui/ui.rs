@@ -0,0 +1,147 @@
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph, Wrap};
+use ratatui::Frame;
+use super::App;
+
+const KEYS: &str = "↑↓ select  Tab type  S-Tab project  PgUp/PgDn scroll  Enter print  ^E edit  ^Y copy id  Esc quit";
+
+pub fn draw(frame: &mut Frame, app: &mut App) {
+    let [input, body, footer] =
+        Layout::vertical([Constraint::Length(3), Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+    let [results, preview] =
+        Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(body);
+
+    draw_input(frame, app, input);
+    draw_results(frame, app, results);
+    draw_preview(frame, app, preview);
+
+    let footer_text = if app.status.is_empty() { KEYS.to_string() } else { app.status.clone() };
+    frame.render_widget(Paragraph::new(footer_text).style(Style::new().fg(Color::DarkGray)), footer);
+}
+
+fn draw_input(frame: &mut Frame, app: &App, area: Rect) {
+    let filters = format!(
+        " type: {} · project: {} ",
+        app.type_name().unwrap_or("all"),
+        app.project_name().unwrap_or("all")
+    );
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Search ")
+        .title_bottom(Line::from(filters).right_aligned());
+    let inner = block.inner(area);
+    frame.render_widget(Paragraph::new(app.query.as_str()).block(block), area);
+
+    let cursor_x = inner.x + (app.query.chars().count() as u16).min(inner.width.saturating_sub(1));
+    frame.set_cursor_position((cursor_x, inner.y));
+}
+
+fn draw_results(frame: &mut Frame, app: &mut App, area: Rect) {
+    let title = if app.searching {
+        " Results (searching…) ".to_string()
+    } else {
+        format!(" Results ({}) ", app.results.len())
+    };
+    let items: Vec<ListItem> = app
+        .results
+        .iter()
+        .map(|item| {
+            let mut spans = vec![Span::styled(format!("{:>5} ", item.id), Style::new().fg(Color::DarkGray))];
+            if let Some(score) = item.score {
+                spans.push(Span::styled(format!("{:.3} ", score), Style::new().fg(Color::Green)));
+            }
+            spans.push(Span::styled(format!("{:<8} ", item.entry_type), Style::new().fg(Color::Cyan)));
+            spans.push(Span::raw(item.label.clone()));
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(list, area, &mut app.list_state);
+}
+
+fn draw_preview(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default().borders(Borders::ALL);
+    let Some(preview) = &app.preview else {
+        frame.render_widget(block.title(" Preview "), area);
+        return;
+    };
+
+    let entry = &preview.entry;
+    let mut title = format!(" #{} {} ", entry.id, entry.entry_type);
+    if let Some(path) = &entry.file_path {
+        title.push_str(&format!("· {} ", path));
+    } else if let Some(label) = entry.metadata.get("title").and_then(|t| t.as_str()) {
+        title.push_str(&format!("· {} ", label));
+    }
+    let position = format!(" {}/{} ", (app.preview_scroll + 1).min(preview.lines.len()), preview.lines.len());
+    let block = block.title(title).title_bottom(Line::from(position).right_aligned());
+
+    // Only the visible part is highlighted; chats can run to many thousands of lines
+    let terms = terms(&app.query);
+    let lines: Vec<Line> = preview
+        .lines
+        .iter()
+        .skip(app.preview_scroll)
+        .take(area.height as usize)
+        .map(|line| highlight(line, &terms))
+        .collect();
+    frame.render_widget(Paragraph::new(lines).block(block).wrap(Wrap { trim: false }), area);
+}
+
+/// Lowercased query words; a word in the text matches when it starts with one
+fn terms(query: &str) -> Vec<String> {
+    query
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|w| w.chars().count() > 1)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn is_match(word: &str, terms: &[String]) -> bool {
+    let word = word.to_lowercase();
+    terms.iter().any(|t| word.starts_with(t.as_str()))
+}
+
+fn highlight<'a>(line: &'a str, terms: &[String]) -> Line<'a> {
+    if terms.is_empty() {
+        return Line::raw(line);
+    }
+    let style = Style::new().fg(Color::Black).bg(Color::Yellow);
+    let mut spans = Vec::new();
+    let mut plain_start = 0;
+    let mut word_start = None;
+    for (i, c) in line.char_indices().chain(std::iter::once((line.len(), ' '))) {
+        let in_word = c.is_alphanumeric() || c == '_';
+        match (word_start, in_word) {
+            (None, true) => word_start = Some(i),
+            (Some(start), false) => {
+                if is_match(&line[start..i], terms) {
+                    spans.push(Span::raw(&line[plain_start..start]));
+                    spans.push(Span::styled(&line[start..i], style));
+                    plain_start = i;
+                }
+                word_start = None;
+            }
+            _ => {}
+        }
+    }
+    spans.push(Span::raw(&line[plain_start..]));
+    Line::from(spans)
+}
+
+/// Index of the first line containing a query word
+pub fn first_match(lines: &[String], query: &str) -> Option<usize> {
+    let terms = terms(query);
+    if terms.is_empty() {
+        return None;
+    }
+    lines.iter().position(|line| {
+        line.split(|c: char| !c.is_alphanumeric() && c != '_')
+            .any(|word| !word.is_empty() && is_match(word, &terms))
+    })
+}
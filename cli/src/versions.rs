@@ -0,0 +1,105 @@
+//! Earlier revisions of entries (the `entry_versions` table).
+//!
+//! Revisions are archived by a database trigger whenever an entry's content
+//! changes. Archived revisions are numbered from 1; the entry itself is the
+//! version after the last archived one.
+
+use anyhow::{Context, Result};
+use tokio_postgres::{Client, GenericClient};
+
+pub struct Version {
+    pub version: i32,
+    pub content: String,
+    pub metadata: serde_json::Value,
+    pub file_path: Option<String>,
+    pub created_at: Option<chrono::NaiveDateTime>,
+    pub current: bool,
+}
+
+/// Every version of an entry, newest (current) first. Empty if the entry does not exist.
+pub async fn list(client: &Client, id: i32) -> Result<Vec<Version>> {
+    let rows = client
+        .query(
+            r#"
+            SELECT (SELECT COALESCE(max(version), 0) + 1 FROM entry_versions WHERE entry_id = $1) AS version,
+                   content, metadata, file_path, COALESCE(updated_at, created_at) AS created_at, TRUE AS current
+            FROM entries
+            WHERE id = $1
+            UNION ALL
+            SELECT version, content, metadata, file_path, created_at, FALSE
+            FROM entry_versions
+            WHERE entry_id = $1
+            ORDER BY version DESC
+            "#,
+            &[&id],
+        )
+        .await
+        .context("Failed to query entry versions")?;
+
+    // Archived rows only exist for existing entries, so a missing entry lists nothing
+    if !rows.first().is_some_and(|r| r.get::<_, bool>("current")) {
+        return Ok(Vec::new());
+    }
+
+    Ok(rows
+        .iter()
+        .map(|row| Version {
+            version: row.get("version"),
+            content: row.get("content"),
+            metadata: row.get::<_, Option<serde_json::Value>>("metadata").unwrap_or_default(),
+            file_path: row.get("file_path"),
+            created_at: row.get("created_at"),
+            current: row.get("current"),
+        })
+        .collect())
+}
+
+/// One version of an entry, archived or current.
+pub async fn get(client: &Client, id: i32, version: i32) -> Result<Option<Version>> {
+    Ok(list(client, id).await?.into_iter().find(|v| v.version == version))
+}
+
+/// Delete all but the `keep` most recent archived versions of each entry (or only
+/// of `entry_id`). `keep` 0 keeps everything.
+pub async fn prune(client: &impl GenericClient, entry_id: Option<i32>, keep: usize) -> Result<u64> {
+    if keep == 0 {
+        return Ok(0);
+    }
+    client
+        .execute(
+            r#"
+            DELETE FROM entry_versions v
+            USING (
+                SELECT entry_id, version,
+                       row_number() OVER (PARTITION BY entry_id ORDER BY version DESC) AS rank
+                FROM entry_versions
+                WHERE $1::int IS NULL OR entry_id = $1
+            ) ranked
+            WHERE v.entry_id = ranked.entry_id AND v.version = ranked.version AND ranked.rank > $2
+            "#,
+            &[&entry_id, &(keep as i64)],
+        )
+        .await
+        .context("Failed to prune entry versions")
+}
+
+/// Archived versions `prune` would delete.
+pub async fn excess(client: &Client, keep: usize) -> Result<i64> {
+    if keep == 0 {
+        return Ok(0);
+    }
+    let row = client
+        .query_one(
+            r#"
+            SELECT count(*) FROM (
+                SELECT row_number() OVER (PARTITION BY entry_id ORDER BY version DESC) AS rank
+                FROM entry_versions
+            ) ranked
+            WHERE rank > $1
+            "#,
+            &[&(keep as i64)],
+        )
+        .await
+        .context("Failed to count entry versions")?;
+    Ok(row.get(0))
+}
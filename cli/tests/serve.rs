@@ -0,0 +1,609 @@
+//! Integration tests for `elastic-claude serve`.
+//!
+//! These need a PostgreSQL server the tests can create databases on, given as a
+//! libpq-style connection string:
+//!
+//! ```sh
+//! ELASTIC_CLAUDE_TEST_DATABASE_URL="host=127.0.0.1 port=5433 user=postgres password=elastic" cargo test
+//! ```
+//!
+//! Each test creates its own database, applies the schema from `docker/`, and runs
+//! the server binary with a temporary HOME. Without the variable they are skipped.
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+use tokio_postgres::config::Host;
+use tokio_postgres::NoTls;
+
+const TOKEN: &str = "test-token";
+
+struct Server {
+    base: String,
+    http: reqwest::Client,
+    child: Child,
+    home: PathBuf,
+    admin: tokio_postgres::Config,
+    database: String,
+}
+
+impl Server {
+    /// None when no test database is configured.
+    async fn start(name: &str) -> Option<Self> {
+        let Ok(url) = std::env::var("ELASTIC_CLAUDE_TEST_DATABASE_URL") else {
+            eprintln!("ELASTIC_CLAUDE_TEST_DATABASE_URL is not set; skipping");
+            return None;
+        };
+        let mut admin: tokio_postgres::Config = url.parse().expect("invalid ELASTIC_CLAUDE_TEST_DATABASE_URL");
+        admin.dbname("postgres");
+
+        let database = format!("elastic_claude_test_{}_{}", name, std::process::id());
+        let client = connect(&admin).await;
+        for statement in [format!("DROP DATABASE IF EXISTS {} WITH (FORCE)", database), format!("CREATE DATABASE {}", database)] {
+            client.batch_execute(&statement).await.unwrap();
+        }
+        let mut config = admin.clone();
+        config.dbname(&database);
+        apply_schema(&connect(&config).await).await;
+
+        let home = std::env::temp_dir().join(&database);
+        let _ = std::fs::remove_dir_all(&home);
+        std::fs::create_dir_all(home.join(".elastic-claude")).unwrap();
+        let host = match &admin.get_hosts()[0] {
+            Host::Tcp(host) => host.clone(),
+            #[cfg(unix)]
+            Host::Unix(path) => path.display().to_string(),
+        };
+        let config_yaml = json!({
+            "database": {
+                "host": host,
+                "port": admin.get_ports().first().copied().unwrap_or(5432),
+                "name": database,
+                "user": admin.get_user().unwrap_or("postgres"),
+                "password": String::from_utf8_lossy(admin.get_password().unwrap_or_default()),
+            },
+            "server": { "token": TOKEN },
+        });
+        std::fs::write(home.join(".elastic-claude/config.yaml"), config_yaml.to_string()).unwrap();
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let child = Command::new(env!("CARGO_BIN_EXE_elastic-claude"))
+            .args(["serve", "--bind", &format!("127.0.0.1:{}", port)])
+            .env("HOME", &home)
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap();
+
+        let server = Self {
+            base: format!("http://127.0.0.1:{}", port),
+            http: reqwest::Client::new(),
+            child,
+            home,
+            admin,
+            database,
+        };
+        server.wait_ready().await;
+        Some(server)
+    }
+
+    async fn wait_ready(&self) {
+        let deadline = Instant::now() + Duration::from_secs(20);
+        while Instant::now() < deadline {
+            if self.http.get(self.url("/api/openapi.json")).send().await.is_ok() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("server did not start");
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base, path)
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.http.request(method, self.url(path)).bearer_auth(TOKEN)
+    }
+
+    async fn get(&self, path: &str) -> (StatusCode, Value) {
+        send(self.request(reqwest::Method::GET, path)).await
+    }
+
+    async fn get_with_host(&self, path: &str, host: &str) -> (StatusCode, Value) {
+        send(self.request(reqwest::Method::GET, path).header(reqwest::header::HOST, host)).await
+    }
+
+    async fn add(&self, body: Value) -> i64 {
+        let (status, body) = send(self.request(reqwest::Method::POST, "/api/entries").json(&body)).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", body);
+        body["id"].as_i64().unwrap()
+    }
+
+    /// A connection to the test database
+    async fn db(&self) -> tokio_postgres::Client {
+        let mut config = self.admin.clone();
+        config.dbname(&self.database);
+        connect(&config).await
+    }
+
+    /// Run a CLI command against the same database, from HOME
+    fn cli(&self, args: &[&str]) {
+        let status = Command::new(env!("CARGO_BIN_EXE_elastic-claude"))
+            .args(args)
+            .current_dir(&self.home)
+            .env("HOME", &self.home)
+            .stdout(Stdio::null())
+            .status()
+            .unwrap();
+        assert!(status.success(), "elastic-claude {} failed", args.join(" "));
+    }
+
+    async fn stop(mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = std::fs::remove_dir_all(&self.home);
+        let client = connect(&self.admin).await;
+        client
+            .batch_execute(&format!("DROP DATABASE IF EXISTS {} WITH (FORCE)", self.database))
+            .await
+            .unwrap();
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        // Reached without stop() when a test fails
+        let _ = self.child.kill();
+    }
+}
+
+async fn connect(config: &tokio_postgres::Config) -> tokio_postgres::Client {
+    let (client, connection) = config.connect(NoTls).await.expect("could not connect to the test database");
+    tokio::spawn(connection);
+    client
+}
+
+/// The initial schema and every migration, recorded the way `init` records them.
+async fn apply_schema(client: &tokio_postgres::Client) {
+    let docker = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../docker");
+    client
+        .batch_execute(&std::fs::read_to_string(docker.join("init.sql")).unwrap())
+        .await
+        .unwrap();
+    client
+        .batch_execute(
+            "CREATE TABLE schema_migrations (version INT PRIMARY KEY, name TEXT NOT NULL, applied_at TIMESTAMP DEFAULT NOW());
+             INSERT INTO schema_migrations (version, name) VALUES (1, 'initial schema')",
+        )
+        .await
+        .unwrap();
+
+    let mut migrations: Vec<PathBuf> = std::fs::read_dir(docker.join("migrations"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "sql"))
+        .collect();
+    migrations.sort();
+    for path in migrations {
+        let name = path.file_stem().unwrap().to_string_lossy().to_string();
+        let version: i32 = name.split('_').next().unwrap().parse().unwrap();
+        client.batch_execute(&std::fs::read_to_string(&path).unwrap()).await.unwrap();
+        client
+            .execute("INSERT INTO schema_migrations (version, name) VALUES ($1, $2)", &[&version, &name])
+            .await
+            .unwrap();
+    }
+}
+
+async fn send(request: reqwest::RequestBuilder) -> (StatusCode, Value) {
+    let response = request.send().await.unwrap();
+    let status = response.status();
+    let text = response.text().await.unwrap();
+    let body = if text.is_empty() { Value::Null } else { serde_json::from_str(&text).unwrap() };
+    (status, body)
+}
+
+#[tokio::test]
+async fn requires_bearer_token() {
+    let Some(server) = Server::start("auth").await else { return };
+
+    let (status, body) = send(server.http.get(server.url("/api/status"))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(body["error"].as_str().unwrap().contains("token"));
+
+    let (status, _) = send(server.http.get(server.url("/api/entries")).bearer_auth("wrong")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Other host names are refused even with the token (DNS rebinding)
+    let (status, _) = server.get_with_host("/api/status", "attacker.example").await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = server.get_with_host("/api/status", "localhost:8765").await;
+    assert_eq!(status, StatusCode::OK);
+
+    // The OpenAPI document is public
+    let (status, body) = send(server.http.get(server.url("/api/openapi.json"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["paths"]["/api/entries/{id}"]["patch"].is_object());
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn adds_searches_updates_and_deletes_entries() {
+    let Some(server) = Server::start("entries").await else { return };
+
+    let id = server
+        .add(json!({
+            "entry_type": "document",
+            "content": "The sync worker retries failed uploads with exponential backoff.",
+            "file_path": "/notes/sync.md",
+            "metadata": { "project": "sync", "title": "Sync retries" },
+        }))
+        .await;
+    server
+        .add(json!({ "entry_type": "note", "content": "Lunch options near the office." }))
+        .await;
+
+    let (status, entry) = server.get(&format!("/api/entries/{}", id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(entry["entry_type"], "document");
+    assert_eq!(entry["file_path"], "/notes/sync.md");
+    assert_eq!(entry["metadata"]["project"], "sync");
+    assert_eq!(entry["language"], "english");
+
+    let (status, hits) = server.get("/api/search?q=backoff").await;
+    assert_eq!(status, StatusCode::OK);
+    let hits = hits.as_array().unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0]["id"], id);
+    assert!(hits[0]["snippet"].as_str().unwrap().contains("backoff"));
+
+    let (status, _) = server.get("/api/search?q=").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (_, hits) = server.get("/api/search?q=backoff&language=german").await;
+    assert!(hits.as_array().unwrap().is_empty());
+    let (status, _) = server.get("/api/search?q=backoff&language=klingon").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // A misspelling only matches by trigram similarity
+    let (_, hits) = server.get("/api/search?q=backof").await;
+    assert!(hits.as_array().unwrap().is_empty());
+    let (status, hits) = server.get("/api/search?q=backof&fuzzy=true").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(hits[0]["id"], id);
+    assert!(hits[0]["snippet"].as_str().unwrap().contains("<b>backoff</b>"));
+
+    let (_, listed) = server.get("/api/entries?project=sync").await;
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    assert_eq!(listed[0]["label"], "Sync retries");
+    let (_, listed) = server.get("/api/entries?type=note").await;
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+
+    // Content and a metadata merge; null removes a key
+    let (status, updated) = send(
+        server
+            .request(reqwest::Method::PATCH, &format!("/api/entries/{}", id))
+            .json(&json!({
+                "content": "The sync worker retries failed uploads with jittered backoff.",
+                "metadata": { "title": null, "status": "done" },
+            })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{}", updated);
+    assert!(updated["content"].as_str().unwrap().contains("jittered"));
+    assert_eq!(updated["metadata"]["status"], "done");
+    assert_eq!(updated["metadata"]["project"], "sync");
+    assert!(updated["metadata"].get("title").is_none());
+    let (_, hits) = server.get("/api/search?q=jittered").await;
+    assert_eq!(hits.as_array().unwrap().len(), 1);
+
+    let (status, _) = send(server.request(reqwest::Method::POST, "/api/entries").json(&json!({ "content": "x" }))).await;
+    assert!(status.is_client_error());
+
+    // Deleting moves the entry to the trash
+    let (status, _) = send(server.request(reqwest::Method::DELETE, &format!("/api/entries/{}", id))).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(server.request(reqwest::Method::DELETE, &format!("/api/entries/{}", id))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, hits) = server.get("/api/search?q=backoff").await;
+    assert!(hits.as_array().unwrap().is_empty());
+    let (_, entry) = server.get(&format!("/api/entries/{}", id)).await;
+    assert!(entry["deleted_at"].is_string());
+
+    let (status, _) = server.get("/api/entries/999999").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn reports_status() {
+    let Some(server) = Server::start("status").await else { return };
+
+    server.add(json!({ "entry_type": "note", "content": "First note" })).await;
+    let id = server.add(json!({ "entry_type": "note", "content": "Second note" })).await;
+    send(server.request(reqwest::Method::DELETE, &format!("/api/entries/{}", id))).await;
+
+    let (status, body) = server.get("/api/status").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["entries"]["note"], 1);
+    assert_eq!(body["trash"], 1);
+    assert!(body["schema_version"].as_i64().unwrap() >= 7);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn serves_web_ui_and_entry_views() {
+    let Some(server) = Server::start("ui").await else { return };
+
+    // Static files need no token
+    let page = server.http.get(server.url("/")).send().await.unwrap();
+    assert_eq!(page.status(), StatusCode::OK);
+    assert!(page.text().await.unwrap().contains("/ui/app.js"));
+    let script = server.http.get(server.url("/ui/app.js")).send().await.unwrap();
+    assert_eq!(script.status(), StatusCode::OK);
+    assert!(script.headers()["content-type"].to_str().unwrap().starts_with("text/javascript"));
+
+    // A session file under ~/.claude/projects gives the conversation with its speakers
+    let project = server.home.join(".claude/projects/-work-sync");
+    std::fs::create_dir_all(&project).unwrap();
+    let session = project.join("session.jsonl");
+    let lines = [
+        json!({ "type": "user", "message": { "role": "user", "content": "How should uploads retry?" } }),
+        json!({ "type": "assistant", "message": { "role": "assistant", "content": [
+            { "type": "thinking", "thinking": "Backoff is the usual answer." },
+            { "type": "text", "text": "Retry with exponential backoff." },
+        ] } }),
+    ];
+    std::fs::write(&session, lines.map(|l| l.to_string()).join("\n")).unwrap();
+    let chat = server
+        .add(json!({
+            "entry_type": "chat",
+            "content": "How should uploads retry?\n\nRetry with exponential backoff.",
+            "file_path": session.to_string_lossy(),
+            "metadata": { "project": "sync" },
+        }))
+        .await;
+    let (status, conversation) = server.get(&format!("/api/entries/{}/conversation", chat)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(conversation["source"], "file");
+    let turns = conversation["turns"].as_array().unwrap();
+    assert_eq!(turns.len(), 3);
+    assert_eq!((turns[0]["role"].as_str(), turns[0]["kind"].as_str()), (Some("user"), Some("text")));
+    assert_eq!(turns[1]["kind"], "thinking");
+    assert_eq!(turns[2]["text"], "Retry with exponential backoff.");
+
+    // Files elsewhere are never read, whatever file_path a client stored
+    let outside = server.home.join("outside.jsonl");
+    std::fs::copy(&session, &outside).unwrap();
+    let planted = server
+        .add(json!({ "entry_type": "chat", "content": "Planted.", "file_path": outside.to_string_lossy() }))
+        .await;
+    let (_, conversation) = server.get(&format!("/api/entries/{}/conversation", planted)).await;
+    assert_eq!(conversation["source"], "content");
+
+    // Without the file, the indexed text is split into paragraphs
+    std::fs::remove_file(&session).unwrap();
+    let (_, conversation) = server.get(&format!("/api/entries/{}/conversation", chat)).await;
+    assert_eq!(conversation["source"], "content");
+    assert_eq!(conversation["turns"].as_array().unwrap().len(), 2);
+
+    let doc = server
+        .add(json!({ "entry_type": "document", "content": "Uploads retry with exponential backoff.", "metadata": { "project": "other" } }))
+        .await;
+    let (status, _) = server.get(&format!("/api/entries/{}/conversation", doc)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Search filters
+    let (_, hits) = server.get("/api/search?q=backoff&type=chat").await;
+    assert_eq!(hits.as_array().unwrap().len(), 1);
+    assert_eq!(hits[0]["id"], chat);
+    let (_, hits) = server.get("/api/search?q=backoff&project=other").await;
+    assert_eq!(hits.as_array().unwrap().len(), 1);
+    assert_eq!(hits[0]["id"], doc);
+    let (_, hits) = server.get("/api/search?q=backoff&type=").await;
+    assert_eq!(hits.as_array().unwrap().len(), 2);
+
+    // Type weights reorder hits; invalid ranking options are rejected
+    for (boost, first) in [("chat=2", chat), ("document=2", doc)] {
+        let (_, hits) = server.get(&format!("/api/search?q=backoff&boost={}", boost)).await;
+        assert_eq!(hits[0]["id"], first, "{}", boost);
+    }
+    let (status, _) = server.get("/api/search?q=backoff&rank=bm25").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // A chat mentioning the document's file is linked to it
+    let (_, entry) = send(
+        server
+            .request(reqwest::Method::PATCH, &format!("/api/entries/{}", doc))
+            .json(&json!({ "metadata": { "title": "Upload retries" } })),
+    )
+    .await;
+    assert_eq!(entry["metadata"]["title"], "Upload retries");
+    let noted = server
+        .add(json!({ "entry_type": "document", "content": "Runbook", "file_path": "/notes/runbook.md" }))
+        .await;
+    let mention = server
+        .add(json!({ "entry_type": "chat", "content": "Updated /notes/runbook.md with the new steps." }))
+        .await;
+    let (_, related) = server.get(&format!("/api/entries/{}/related", noted)).await;
+    assert_eq!(related[0]["id"], mention);
+    assert_eq!(related[0]["link_type"], "references");
+    assert_eq!(related[0]["outgoing"], false);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn synonyms_match_entries_and_queries() {
+    let Some(server) = Server::start("synonyms").await else { return };
+
+    let before = server
+        .add(json!({ "entry_type": "note", "content": "Deploy the worker to the k8s cluster." }))
+        .await;
+    server.cli(&["synonyms", "add", "Kubernetes", "k8s"]);
+    let after = server
+        .add(json!({ "entry_type": "note", "content": "Kubernetes rollouts wait for readiness probes." }))
+        .await;
+
+    // Entries written before and after the synonym was added match either term
+    for q in ["kubernetes", "k8s"] {
+        let (_, hits) = server.get(&format!("/api/search?q={}", q)).await;
+        let mut ids: Vec<i64> = hits.as_array().unwrap().iter().map(|h| h["id"].as_i64().unwrap()).collect();
+        ids.sort();
+        assert_eq!(ids, [before, after], "{}", q);
+    }
+
+    server.cli(&["synonyms", "remove", "kubernetes"]);
+    let (_, hits) = server.get("/api/search?q=k8s").await;
+    assert_eq!(hits.as_array().unwrap().len(), 1);
+    assert_eq!(hits[0]["id"], before);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn reingesting_leaves_trashed_files_in_the_trash() {
+    let Some(server) = Server::start("reingest").await else { return };
+
+    let repo = server.home.join("notes");
+    std::fs::create_dir_all(&repo).unwrap();
+    std::fs::write(repo.join("retry.md"), "# Retries\n\nUploads retry with backoff.\n").unwrap();
+    let status = Command::new("git").arg("init").arg("-q").arg(&repo).status().unwrap();
+    assert!(status.success());
+    let repo = repo.to_string_lossy();
+    server.cli(&["ingest", "--repo", &repo]);
+
+    let (_, listed) = server.get("/api/entries?project=notes").await;
+    let id = listed[0]["id"].as_i64().unwrap();
+    let (status, _) = send(server.request(reqwest::Method::DELETE, &format!("/api/entries/{}", id))).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    // Neither an unchanged nor an edited file brings the entry back
+    server.cli(&["ingest", "--repo", &repo]);
+    std::fs::write(server.home.join("notes/retry.md"), "# Retries\n\nUploads retry with jitter.\n").unwrap();
+    server.cli(&["ingest", "--repo", &repo]);
+
+    let (_, entry) = server.get(&format!("/api/entries/{}", id)).await;
+    assert!(entry["deleted_at"].is_string(), "{}", entry);
+    assert!(entry["content"].as_str().unwrap().contains("backoff"));
+    let (_, listed) = server.get("/api/entries?project=notes").await;
+    assert!(listed.as_array().unwrap().is_empty());
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn versions_skip_system_maintained_metadata() {
+    let Some(server) = Server::start("versions").await else { return };
+    let db = server.db().await;
+    let versions = |id: i64| {
+        let db = &db;
+        async move {
+            db.query_one("SELECT count(*) FROM entry_versions WHERE entry_id = $1", &[&(id as i32)])
+                .await
+                .unwrap()
+                .get::<_, i64>(0)
+        }
+    };
+
+    let id = server
+        .add(json!({ "entry_type": "note", "content": "Nightly backups rotate after ten days of retention." }))
+        .await;
+    // What `retag` and `watch --on-delete mark` write when the corpus or the file changes
+    db.execute(
+        r#"UPDATE entries SET metadata = metadata || '{"keywords": ["rotation"], "file_deleted": true}' WHERE id = $1"#,
+        &[&(id as i32)],
+    )
+    .await
+    .unwrap();
+    assert_eq!(versions(id).await, 0);
+
+    let (status, _) = send(
+        server
+            .request(reqwest::Method::PATCH, &format!("/api/entries/{}", id))
+            .json(&json!({ "metadata": { "status": "done" } })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(versions(id).await, 1);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn watch_updates_the_entry_added_by_path() {
+    let Some(server) = Server::start("watchpath").await else { return };
+
+    let docs = server.home.join("docs");
+    std::fs::create_dir_all(&docs).unwrap();
+    std::fs::write(docs.join("notes.md"), "Deploys run from the release branch.\n").unwrap();
+    server.cli(&["add", "-t", "document", "-p", "docs/notes.md"]);
+
+    let mut watch = Command::new(env!("CARGO_BIN_EXE_elastic-claude"))
+        .args(["watch", "docs", "--debounce-ms", "100"])
+        .current_dir(&server.home)
+        .env("HOME", &server.home)
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    std::fs::write(docs.join("notes.md"), "Deploys run from tagged commits.\n").unwrap();
+
+    let db = server.db().await;
+    let deadline = Instant::now() + Duration::from_secs(20);
+    let rows = loop {
+        let rows = db.query("SELECT file_path, content FROM entries ORDER BY id", &[]).await.unwrap();
+        let updated = rows.iter().any(|row| row.get::<_, &str>(1).contains("tagged"));
+        if updated || Instant::now() > deadline {
+            break rows;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    };
+    let _ = watch.kill();
+    let _ = watch.wait();
+
+    let stored: Vec<(String, String)> = rows.iter().map(|row| (row.get(0), row.get(1))).collect();
+    let path = docs.canonicalize().unwrap().join("notes.md").to_string_lossy().to_string();
+    assert_eq!(stored, [(path, "Deploys run from tagged commits.\n".to_string())]);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn reingested_file_gets_the_language_of_its_new_content() {
+    let Some(server) = Server::start("relang").await else { return };
+
+    let repo = server.home.join("handbook");
+    std::fs::create_dir_all(&repo).unwrap();
+    let file = repo.join("retries.md");
+    std::fs::write(
+        &file,
+        "The worker retries the request with exponential backoff, and it is not allowed to exceed the limit that was set for this queue.\n",
+    )
+    .unwrap();
+    let status = Command::new("git").arg("init").arg("-q").arg(&repo).status().unwrap();
+    assert!(status.success());
+    let repo = repo.to_string_lossy();
+    server.cli(&["ingest", "--repo", &repo]);
+
+    let (_, listed) = server.get("/api/entries?project=handbook").await;
+    let id = listed[0]["id"].as_i64().unwrap();
+    let (_, entry) = server.get(&format!("/api/entries/{}", id)).await;
+    assert_eq!(entry["language"], "english");
+
+    std::fs::write(
+        &file,
+        "Der Worker wiederholt die Anfrage mit exponentiellem Backoff, und das Limit ist nicht zu erreichen, weil die Warteschlange sich auf den Wert stellt.\n",
+    )
+    .unwrap();
+    server.cli(&["ingest", "--repo", &repo]);
+    let (_, entry) = server.get(&format!("/api/entries/{}", id)).await;
+    assert_eq!(entry["language"], "german");
+
+    server.stop().await;
+}
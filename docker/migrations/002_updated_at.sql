@@ -0,0 +1,3 @@
+-- Track when an entry's content was last refreshed (e.g. by watch re-ingestion)
+
+ALTER TABLE entries ADD COLUMN updated_at TIMESTAMP;
@@ -0,0 +1,15 @@
+-- Index code identifiers verbatim and split into words, alongside the English text
+
+ALTER TABLE entries ADD COLUMN identifiers TEXT[];
+ALTER TABLE entries ADD COLUMN identifier_words TEXT;
+
+-- Generated expressions cannot be altered in place; this also drops idx_content_tsv
+ALTER TABLE entries DROP COLUMN content_tsv;
+
+ALTER TABLE entries ADD COLUMN content_tsv TSVECTOR GENERATED ALWAYS AS (
+    to_tsvector('english', content)
+    || to_tsvector('english', COALESCE(identifier_words, ''))
+    || array_to_tsvector(COALESCE(identifiers, '{}'))
+) STORED;
+
+CREATE INDEX idx_content_tsv ON entries USING GIN(content_tsv);
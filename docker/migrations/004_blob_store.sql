@@ -0,0 +1,4 @@
+-- Attachments too large to keep inline are written to the content-addressed blob
+-- store (~/.elastic-claude/blobs); the entry keeps their SHA-256 instead of the bytes
+
+ALTER TABLE entries ADD COLUMN blob_sha256 TEXT;
@@ -0,0 +1,23 @@
+-- Typed edges between entries. source_id stays as a summary's parent; existing
+-- values are copied over as links so the graph is complete
+
+CREATE TABLE entry_links (
+    from_id INT NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+    to_id INT NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+    link_type TEXT NOT NULL CHECK (
+        link_type IN ('derived_from', 'references', 'summarizes', 'duplicates', 'follows_up')
+    ),
+    -- Detected from content rather than added with `link`
+    auto BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP DEFAULT NOW(),
+    PRIMARY KEY (from_id, to_id, link_type),
+    CHECK (from_id <> to_id)
+);
+
+-- Incoming links (the primary key covers outgoing ones)
+CREATE INDEX idx_entry_links_to_id ON entry_links(to_id);
+
+INSERT INTO entry_links (from_id, to_id, link_type, auto)
+SELECT id, source_id, CASE WHEN entry_type = 'summary' THEN 'summarizes' ELSE 'derived_from' END, TRUE
+FROM entries
+WHERE source_id IS NOT NULL AND source_id <> id;
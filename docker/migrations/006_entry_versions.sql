@@ -0,0 +1,35 @@
+-- Earlier revisions of an entry. A trigger archives the old content and metadata
+-- whenever the content changes; `versions.keep` in config.yaml limits how many are kept
+
+CREATE TABLE entry_versions (
+    entry_id INT NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+    version INT NOT NULL,
+    content TEXT NOT NULL,
+    metadata JSONB,
+    file_path TEXT,
+    -- When this revision was written (the entry's updated_at, or created_at)
+    created_at TIMESTAMP,
+    archived_at TIMESTAMP DEFAULT NOW(),
+    PRIMARY KEY (entry_id, version)
+);
+
+CREATE FUNCTION archive_entry_version() RETURNS trigger AS $$
+BEGIN
+    INSERT INTO entry_versions (entry_id, version, content, metadata, file_path, created_at)
+    VALUES (
+        OLD.id,
+        COALESCE((SELECT max(version) FROM entry_versions WHERE entry_id = OLD.id), 0) + 1,
+        OLD.content,
+        OLD.metadata,
+        OLD.file_path,
+        COALESCE(OLD.updated_at, OLD.created_at)
+    );
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER entries_archive_version
+    BEFORE UPDATE OF content ON entries
+    FOR EACH ROW
+    WHEN (OLD.content IS DISTINCT FROM NEW.content)
+    EXECUTE FUNCTION archive_entry_version();
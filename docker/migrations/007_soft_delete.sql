@@ -0,0 +1,6 @@
+-- Deleted entries go to the trash first; they are hidden from search and list
+-- until restored, and removed for good by `trash empty` or `gc`
+
+ALTER TABLE entries ADD COLUMN deleted_at TIMESTAMP;
+
+CREATE INDEX idx_deleted_at ON entries(deleted_at) WHERE deleted_at IS NOT NULL;
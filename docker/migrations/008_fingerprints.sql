@@ -0,0 +1,6 @@
+-- MinHash signatures of each entry's word shingles, for near-duplicate detection
+-- (`dedupe`). Entries written before this migration are fingerprinted by the
+-- first `dedupe` run
+
+ALTER TABLE entries ADD COLUMN minhash INT[];
+ALTER TABLE entries ADD COLUMN shingle_count INT;
@@ -0,0 +1,17 @@
+-- Typo-tolerant search. Trigram indexes serve `search --fuzzy`, which matches
+-- titles, file paths and content by word similarity instead of exact lexemes
+
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX idx_title_trgm ON entries USING GIN ((metadata->>'title') gin_trgm_ops);
+CREATE INDEX idx_file_path_trgm ON entries USING GIN (file_path gin_trgm_ops);
+CREATE INDEX idx_content_trgm ON entries USING GIN (content gin_trgm_ops);
+
+-- Unstemmed words of all content, for "did you mean" suggestions. Refreshed by
+-- `search` when entries have been written since refreshed_at
+CREATE MATERIALIZED VIEW search_vocabulary AS
+SELECT word, ndoc, LOCALTIMESTAMP AS refreshed_at
+FROM ts_stat('SELECT to_tsvector(''simple'', content) FROM entries WHERE deleted_at IS NULL')
+WHERE word ~ '^[[:alpha:]]{3,40}$';
+
+CREATE INDEX idx_search_vocabulary_word ON search_vocabulary USING GIN (word gin_trgm_ops);
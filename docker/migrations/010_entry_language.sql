@@ -0,0 +1,22 @@
+-- Per-entry text search configuration. content_tsv is built with it, so docs in
+-- different languages are stemmed correctly. New entries get a detected language,
+-- `--lang`, or the connection's default_text_search_config (`search.language`)
+
+ALTER TABLE entries ADD COLUMN language REGCONFIG NOT NULL DEFAULT get_current_ts_config();
+
+-- Existing entries written mostly in Cyrillic are Russian; the rest keep the default
+UPDATE entries
+SET language = 'russian'
+WHERE length(regexp_replace(content, '[^А-Яа-яЁё]', '', 'g')) > length(regexp_replace(content, '[^A-Za-z]', '', 'g'));
+
+-- Generated expressions cannot be altered in place; this also drops idx_content_tsv
+ALTER TABLE entries DROP COLUMN content_tsv;
+
+ALTER TABLE entries ADD COLUMN content_tsv TSVECTOR GENERATED ALWAYS AS (
+    to_tsvector(language, content)
+    || to_tsvector(language, COALESCE(identifier_words, ''))
+    || array_to_tsvector(COALESCE(identifiers, '{}'))
+) STORED;
+
+CREATE INDEX idx_content_tsv ON entries USING GIN(content_tsv);
+CREATE INDEX idx_language ON entries(language);
@@ -0,0 +1,80 @@
+-- Synonyms and project jargon, managed with `elastic-claude synonyms`. PostgreSQL's
+-- synonym and thesaurus dictionaries read their files from the server's share
+-- directory, which the CLI cannot write to, so the pairs are kept in a table and
+-- applied by the functions below: entries get the synonyms of every term they
+-- contain in synonym_words (indexed by content_tsv), and query words are expanded
+-- with theirs by synonym_tsquery().
+
+-- A term and its synonyms form a group; every member matches all the others.
+-- Both are stored lowercased and may be phrases ("pull request")
+CREATE TABLE synonyms (
+    term TEXT NOT NULL,
+    synonym TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT NOW(),
+    PRIMARY KEY (term, synonym)
+);
+
+CREATE INDEX idx_synonyms_synonym ON synonyms(synonym);
+
+-- Members of every group that `content` mentions, space-separated (NULL for none)
+CREATE FUNCTION entry_synonyms(config REGCONFIG, content TEXT) RETURNS TEXT AS $$
+    WITH doc AS (SELECT to_tsvector(config, content) AS tsv),
+    groups AS (
+        SELECT DISTINCT s.term FROM synonyms s, doc
+        WHERE doc.tsv @@ phraseto_tsquery(config, s.term)
+           OR doc.tsv @@ phraseto_tsquery(config, s.synonym)
+    )
+    SELECT string_agg(word, ' ' ORDER BY word) FROM (
+        SELECT term AS word FROM groups
+        UNION
+        SELECT s.synonym FROM synonyms s JOIN groups USING (term)
+    ) w
+$$ LANGUAGE sql STABLE;
+
+-- `word` as a tsquery, OR the members of every group it belongs to
+CREATE FUNCTION synonym_tsquery(config REGCONFIG, word TEXT) RETURNS TSQUERY AS $$
+DECLARE
+    expanded TSQUERY := plainto_tsquery(config, word);
+    member TEXT;
+BEGIN
+    FOR member IN
+        WITH groups AS (
+            SELECT DISTINCT term FROM synonyms WHERE term = lower(word) OR synonym = lower(word)
+        )
+        SELECT term FROM groups
+        UNION
+        SELECT s.synonym FROM synonyms s JOIN groups USING (term)
+    LOOP
+        expanded := expanded || phraseto_tsquery(config, member);
+    END LOOP;
+    RETURN expanded;
+END;
+$$ LANGUAGE plpgsql STABLE;
+
+ALTER TABLE entries ADD COLUMN synonym_words TEXT;
+
+-- Generated columns are computed after BEFORE triggers, so content_tsv sees the result.
+-- `elastic-claude synonyms` recomputes synonym_words when the table changes
+CREATE FUNCTION set_synonym_words() RETURNS trigger AS $$
+BEGIN
+    NEW.synonym_words := entry_synonyms(NEW.language, NEW.content);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER entries_synonym_words
+    BEFORE INSERT OR UPDATE OF content, language ON entries
+    FOR EACH ROW
+    EXECUTE FUNCTION set_synonym_words();
+
+-- Generated expressions cannot be altered in place; this also drops idx_content_tsv
+ALTER TABLE entries DROP COLUMN content_tsv;
+
+ALTER TABLE entries ADD COLUMN content_tsv TSVECTOR GENERATED ALWAYS AS (
+    to_tsvector(language, content)
+    || to_tsvector(language, COALESCE(identifier_words, ''))
+    || array_to_tsvector(COALESCE(identifiers, '{}'))
+    || to_tsvector(language, COALESCE(synonym_words, ''))
+) STORED;
+
+CREATE INDEX idx_content_tsv ON entries USING GIN(content_tsv);
@@ -0,0 +1,20 @@
+-- Weighted sections in content_tsv, so ranking can favour where a word occurs:
+-- metadata.title is A, the text of metadata.headings is B, and the body (with
+-- identifiers and synonyms) is C. `search.ranking.weights` sets what each is worth
+
+-- Generated expressions cannot be altered in place; this also drops idx_content_tsv
+ALTER TABLE entries DROP COLUMN content_tsv;
+
+ALTER TABLE entries ADD COLUMN content_tsv TSVECTOR GENERATED ALWAYS AS (
+    setweight(to_tsvector(language, COALESCE(metadata->>'title', '')), 'A')
+    || setweight(jsonb_to_tsvector(language, COALESCE(metadata->'headings', '[]'), '["string"]'), 'B')
+    || setweight(
+        to_tsvector(language, content)
+        || to_tsvector(language, COALESCE(identifier_words, ''))
+        || array_to_tsvector(COALESCE(identifiers, '{}'))
+        || to_tsvector(language, COALESCE(synonym_words, '')),
+        'C'
+    )
+) STORED;
+
+CREATE INDEX idx_content_tsv ON entries USING GIN(content_tsv);
@@ -0,0 +1,17 @@
+-- Archive a version when the metadata changes too, not only the content. Keys the
+-- system maintains itself are left out of the comparison, so `retag`, refreshed
+-- summaries and `watch --on-delete mark` do not archive copies of an unchanged entry
+
+-- Metadata without the keys written by the system rather than the user
+CREATE FUNCTION user_metadata(metadata JSONB) RETURNS JSONB AS $$
+    SELECT COALESCE(metadata, '{}') - '{keywords,file_deleted,generated_at,method,model}'::text[]
+$$ LANGUAGE sql IMMUTABLE;
+
+DROP TRIGGER entries_archive_version ON entries;
+
+CREATE TRIGGER entries_archive_version
+    BEFORE UPDATE OF content, metadata ON entries
+    FOR EACH ROW
+    WHEN (OLD.content IS DISTINCT FROM NEW.content
+          OR user_metadata(OLD.metadata) IS DISTINCT FROM user_metadata(NEW.metadata))
+    EXECUTE FUNCTION archive_entry_version();
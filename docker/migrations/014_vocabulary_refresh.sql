@@ -0,0 +1,14 @@
+-- Refresh search_vocabulary without blocking readers. REFRESH ... CONCURRENTLY needs
+-- a unique index; live_entries lets a restore from the trash, which sets no
+-- timestamp, still mark the vocabulary stale
+
+DROP MATERIALIZED VIEW search_vocabulary;
+
+CREATE MATERIALIZED VIEW search_vocabulary AS
+SELECT word, ndoc, LOCALTIMESTAMP AS refreshed_at,
+       (SELECT count(*) FROM entries WHERE deleted_at IS NULL) AS live_entries
+FROM ts_stat('SELECT to_tsvector(''simple'', content) FROM entries WHERE deleted_at IS NULL')
+WHERE word ~ '^[[:alpha:]]{3,40}$';
+
+CREATE UNIQUE INDEX idx_search_vocabulary_unique_word ON search_vocabulary (word);
+CREATE INDEX idx_search_vocabulary_word ON search_vocabulary USING GIN (word gin_trgm_ops);